@@ -0,0 +1,267 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ndarray::{linalg::Dot, Array1, Array2, Array3};
+use num_complex::Complex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sse_solver::{
+    distribution::StandardComplexNormal,
+    solvers::{EulerSolver, Solver, StepWorkspace},
+    sparse::{BandedArray, FactorizedArray},
+    sse_system::{Adjoint, FullNoise, SSESystem},
+};
+
+const SIZES: [usize; 3] = [64, 256, 1024];
+const N_SOURCES: [usize; 2] = [1, 8];
+
+fn random_vector(rng: &mut StdRng, n: usize) -> Array1<Complex<f64>> {
+    Array1::from_iter(rng.sample_iter(StandardComplexNormal).take(n))
+}
+
+fn random_state(rng: &mut StdRng, n: usize) -> Array1<Complex<f64>> {
+    let state = random_vector(rng, n);
+    let norm = state.iter().map(Complex::norm_sqr).sum::<f64>().sqrt();
+    state / Complex::from(norm)
+}
+
+/// A hopping hamiltonian, and `n_sources` dense operators each coupling neighbouring states
+fn dense_system(
+    n_states: usize,
+    n_sources: usize,
+) -> SSESystem<Array2<Complex<f64>>, FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>>> {
+    let mut hamiltonian = Array2::zeros([n_states, n_states]);
+    let mut operators = Array3::zeros([n_sources, n_states, n_states]);
+    for i in 1..n_states {
+        hamiltonian[[i - 1, i]] = Complex::from(1f64);
+        hamiltonian[[i, i - 1]] = Complex::from(1f64);
+        for s in 0..n_sources {
+            operators[[s, i - 1, i]] = Complex::from(0.1);
+        }
+    }
+    SSESystem {
+        hamiltonian,
+        noise: FullNoise::from_operators(&operators),
+    }
+}
+
+fn bench_euler_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("euler_step");
+    group.sample_size(10);
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        for n_sources in N_SOURCES {
+            let system = dense_system(n_states, n_sources);
+            let state = random_state(&mut rng, n_states);
+            group.bench_with_input(
+                BenchmarkId::new(format!("{n_sources}_sources"), n_states),
+                &state,
+                |b, state| {
+                    b.iter(|| EulerSolver::step(state, &system, 0f64, 1e-3, &mut rng));
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// A closed system, with a banded hopping hamiltonian so the step is not dominated by the hamiltonian
+fn bench_closed_euler_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("closed_euler_step");
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        let hopping = Array1::from_elem(n_states - 1, Complex::from(1f64));
+        let system = SSESystem {
+            hamiltonian: BandedArray::from_diagonals(
+                [n_states, n_states],
+                &[-1, 1],
+                vec![hopping.clone(), hopping],
+            )
+            .unwrap(),
+            noise: FullNoise::<Array2<Complex<f64>>, Array2<Complex<f64>>>::from_operators(
+                &Array3::zeros([0, n_states, n_states]),
+            ),
+        };
+        let state = random_state(&mut rng, n_states);
+        group.bench_with_input(BenchmarkId::from_parameter(n_states), &state, |b, state| {
+            b.iter(|| EulerSolver::step(state, &system, 0f64, 1e-3, &mut rng));
+        });
+    }
+    group.finish();
+}
+
+fn bench_euler_step_in_place(c: &mut Criterion) {
+    let mut group = c.benchmark_group("euler_step_in_place");
+    group.sample_size(10);
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        for n_sources in N_SOURCES {
+            let system = dense_system(n_states, n_sources);
+            let mut state = random_state(&mut rng, n_states);
+            let mut workspace = StepWorkspace::default();
+            group.bench_function(
+                BenchmarkId::new(format!("{n_sources}_sources"), n_states),
+                |b| {
+                    b.iter(|| {
+                        EulerSolver::step_in_place(
+                            &mut state,
+                            &system,
+                            0f64,
+                            1e-3,
+                            &mut rng,
+                            &mut workspace,
+                        );
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_integrate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("integrate");
+    group.sample_size(10);
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        let system = dense_system(n_states, 1);
+        let state = random_state(&mut rng, n_states);
+        group.bench_with_input(BenchmarkId::from_parameter(n_states), &state, |b, state| {
+            b.iter(|| {
+                let mut current_t = 0f64;
+                EulerSolver::integrate(state, &system, &mut current_t, 10, 1e-3, &mut rng)
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_banded_dot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("banded_dot");
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        let bands = vec![
+            random_vector(&mut rng, n_states - 1),
+            random_vector(&mut rng, n_states),
+            random_vector(&mut rng, n_states - 1),
+        ];
+        let banded = BandedArray::from_diagonals([n_states, n_states], &[-1, 0, 1], bands).unwrap();
+        let state = random_vector(&mut rng, n_states);
+        group.bench_with_input(BenchmarkId::from_parameter(n_states), &state, |b, state| {
+            b.iter(|| black_box(&banded).dot(state));
+        });
+    }
+    group.finish();
+}
+
+fn bench_factorized_dot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("factorized_dot");
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        let factorized = FactorizedArray::from_bra_ket(
+            Complex::from(1f64),
+            random_vector(&mut rng, n_states),
+            random_vector(&mut rng, n_states),
+        );
+        let state = random_vector(&mut rng, n_states);
+        group.bench_with_input(BenchmarkId::from_parameter(n_states), &state, |b, state| {
+            b.iter(|| black_box(&factorized).dot(state));
+        });
+    }
+    group.finish();
+}
+
+fn bench_factorized_adjoint_dot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("factorized_adjoint_dot");
+    let mut rng = StdRng::seed_from_u64(0);
+    for n_states in SIZES {
+        let factorized = FactorizedArray::from_bra_ket(
+            Complex::from(1f64),
+            random_vector(&mut rng, n_states),
+            random_vector(&mut rng, n_states),
+        );
+        let adjoint = factorized.adjoint();
+        let state = random_vector(&mut rng, n_states);
+        group.bench_with_input(
+            BenchmarkId::new("precomputed", n_states),
+            &state,
+            |b, state| {
+                b.iter(|| black_box(&adjoint).dot(state));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("materialized", n_states),
+            &state,
+            |b, state| {
+                b.iter(|| black_box(&factorized).adjoint().dot(state));
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_noise_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("noise_layout");
+    let mut rng = StdRng::seed_from_u64(0);
+    let (n_states, n_sources) = (64, 100);
+    let factorized = |rng: &mut StdRng| {
+        let amplitudes = random_vector(rng, n_sources);
+        let bra =
+            Array2::from_shape_fn([n_sources, n_states], |_| rng.sample(StandardComplexNormal));
+        let ket =
+            Array2::from_shape_fn([n_sources, n_states], |_| rng.sample(StandardComplexNormal));
+        FullNoise::from_bra_ket(amplitudes, &bra, &ket)
+    };
+    let hamiltonian = Array2::<Complex<f64>>::eye(n_states);
+    let aos = SSESystem {
+        hamiltonian: hamiltonian.clone(),
+        noise: factorized(&mut StdRng::seed_from_u64(1)),
+    };
+    let soa = SSESystem {
+        hamiltonian,
+        noise: factorized(&mut StdRng::seed_from_u64(1)).into_soa(),
+    };
+    let state = random_state(&mut rng, n_states);
+    group.bench_with_input(BenchmarkId::new("aos", n_sources), &state, |b, state| {
+        b.iter(|| EulerSolver::step(state, &aos, 0f64, 1e-3, &mut rng));
+    });
+    group.bench_with_input(BenchmarkId::new("soa", n_sources), &state, |b, state| {
+        b.iter(|| EulerSolver::step(state, &soa, 0f64, 1e-3, &mut rng));
+    });
+    group.finish();
+}
+
+fn bench_cached_l_dagger_l(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cached_l_dagger_l");
+    group.sample_size(10);
+    let mut rng = StdRng::seed_from_u64(0);
+    let (n_states, n_sources) = (200, 10);
+    let uncached = dense_system(n_states, n_sources);
+    let cached = SSESystem {
+        hamiltonian: uncached.hamiltonian.clone(),
+        noise: uncached.noise.clone().with_cached_l_dagger_l(),
+    };
+    let state = random_state(&mut rng, n_states);
+    group.bench_with_input(
+        BenchmarkId::new("uncached", n_states),
+        &state,
+        |b, state| {
+            b.iter(|| EulerSolver::step(state, &uncached, 0f64, 1e-3, &mut rng));
+        },
+    );
+    group.bench_with_input(BenchmarkId::new("cached", n_states), &state, |b, state| {
+        b.iter(|| EulerSolver::step(state, &cached, 0f64, 1e-3, &mut rng));
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_euler_step,
+    bench_closed_euler_step,
+    bench_euler_step_in_place,
+    bench_integrate,
+    bench_banded_dot,
+    bench_factorized_dot,
+    bench_factorized_adjoint_dot,
+    bench_noise_layout,
+    bench_cached_l_dagger_l
+);
+criterion_main!(benches);
@@ -0,0 +1,791 @@
+use ndarray::{linalg::Dot, s, Array1, Array2, ArrayView1, Axis, ShapeBuilder};
+use ndarray_linalg::{Eigh, UPLO};
+use num_complex::Complex;
+
+use crate::sse_system::{Adjoint, FullNoise, Tensor};
+
+/// Get the eigenvalues and (column) eigenvectors of a hermitian matrix.
+///
+/// Note `Eigh` on a C-layout complex matrix returns the eigenvectors of the transpose,
+/// so we always decompose a F-layout copy.
+pub(crate) fn eigh_hermitian(matrix: &Array2<Complex<f64>>) -> (Array1<f64>, Array2<Complex<f64>>) {
+    let mut fortran = Array2::zeros(matrix.raw_dim().f());
+    fortran.assign(matrix);
+    fortran.eigh(UPLO::Lower).unwrap()
+}
+
+/// Calculate the quantum Fisher information of the density matrix `rho`
+/// with respect to a parameter encoded by the hermitian `generator`,
+/// ie `rho(theta) = exp(-i theta G) rho exp(i theta G)`.
+///
+/// The QFI is computed from the symmetric logarithmic derivative in the eigenbasis of `rho`
+/// `F = 2 \sum_{ij} (p_i - p_j)^2 / (p_i + p_j) |<i|G|j>|^2`
+/// where terms with `p_i + p_j = 0` are dropped, which is the standard limit for
+/// degenerate (zero) eigenvalues. For a pure state this reduces to `4 Var(G)`.
+///
+/// # Panics
+///
+/// Will panic if `rho` is not square, or if the eigendecomposition of `rho` fails
+#[must_use]
+pub fn quantum_fisher_information(
+    rho: &Array2<Complex<f64>>,
+    generator: &Array2<Complex<f64>>,
+) -> f64 {
+    assert_eq!(rho.shape(), generator.shape());
+    let (eigenvalues, eigenvectors) = eigh_hermitian(rho);
+    // <i|G|j> in the eigenbasis of rho
+    let generator_eigenbasis = eigenvectors
+        .t()
+        .map(Complex::conj)
+        .dot(&generator.dot(&eigenvectors));
+
+    // Eigenvalues of rho below this are treated as zero
+    let tolerance = 1e-12 * eigenvalues.iter().fold(0f64, |a, p| a.max(p.abs()));
+
+    let mut out = 0f64;
+    for (i, p_i) in eigenvalues.iter().enumerate() {
+        for (j, p_j) in eigenvalues.iter().enumerate() {
+            let total = p_i + p_j;
+            if total > tolerance {
+                out += 2f64 * (p_i - p_j).powi(2) / total * generator_eigenbasis[[i, j]].norm_sqr();
+            }
+        }
+    }
+    out
+}
+
+/// Calculate the trace `Tr(rho)` of a density matrix, which should remain one.
+///
+/// # Panics
+///
+/// Will panic if `rho` is not square
+#[must_use]
+pub fn trace(rho: &Array2<Complex<f64>>) -> Complex<f64> {
+    assert!(rho.is_square());
+    rho.diag().sum()
+}
+
+/// Calculate the purity `Tr(rho^2)` of a density matrix, which is one for a pure state
+/// and decays towards `1 / d` for a maximally mixed state of dimension `d`.
+///
+/// # Panics
+///
+/// Will panic if `rho` is not square
+#[must_use]
+pub fn purity(rho: &Array2<Complex<f64>>) -> f64 {
+    assert!(rho.is_square());
+    // Tr(rho^2) = \sum_{ij} rho_{ij} rho_{ji}, which is real for a hermitian rho
+    rho.indexed_iter()
+        .map(|((i, j), value)| (value * rho[[j, i]]).re)
+        .sum()
+}
+
+/// Calculate the reduced density matrix of `subsystem` for the pure `state`
+/// of a composite system with subsystem dimensions `dims`.
+///
+/// The state is assumed to be stored in kronecker order, so the index of
+/// `|a_0, a_1, ...>` is `((a_0 * dims[1]) + a_1) * dims[2] + ...`
+///
+/// # Panics
+///
+/// Will panic if the product of `dims` does not match the length of `state`,
+/// or if `subsystem` is out of range.
+#[must_use]
+pub fn partial_trace(
+    state: ArrayView1<'_, Complex<f64>>,
+    dims: &[usize],
+    subsystem: usize,
+) -> Array2<Complex<f64>> {
+    assert_eq!(dims.iter().product::<usize>(), state.len());
+    let outer = dims[..subsystem].iter().product::<usize>();
+    let dim = dims[subsystem];
+    let inner = dims[(subsystem + 1)..].iter().product::<usize>();
+
+    let reshaped = state.into_shape([outer, dim, inner]).unwrap();
+    let mut out = Array2::zeros([dim, dim]);
+    for (a, row_a) in reshaped.axis_iter(Axis(1)).enumerate() {
+        for (b, row_b) in reshaped.axis_iter(Axis(1)).enumerate() {
+            out[[a, b]] = row_a
+                .iter()
+                .zip(row_b.iter())
+                .map(|(psi_a, psi_b)| psi_a * psi_b.conj())
+                .sum();
+        }
+    }
+    out
+}
+
+/// Calculate the population of each basis state of `subsystem` at each time in `result`,
+/// returning an array of shape `[n, dims[subsystem]]`.
+///
+/// Populations are taken from the diagonal of the reduced density matrix, normalized
+/// such that they sum to one at each time.
+///
+/// # Panics
+///
+/// Will panic if the product of `dims` does not match the number of states in `result`,
+/// or if `subsystem` is out of range.
+#[must_use]
+pub fn subsystem_populations(
+    result: &Array2<Complex<f64>>,
+    dims: &[usize],
+    subsystem: usize,
+) -> Array2<f64> {
+    let mut out = Array2::zeros([result.nrows(), dims[subsystem]]);
+    for (state, mut populations) in result.outer_iter().zip(out.outer_iter_mut()) {
+        let reduced = partial_trace(state, dims, subsystem);
+        // The diagonal of a density matrix is real
+        populations.assign(&reduced.diag().map(|p| p.re));
+        let total = populations.sum();
+        populations /= total;
+    }
+    out
+}
+
+/// Calculate the expectation `<O>` and variance `<O^2> - <O>^2` of a hermitian `operator`,
+/// applying the operator to the state only once.
+///
+/// For hermitian `O`, `<O^2> = ||O |\psi>||^2`. For a non-hermitian operator this
+/// instead returns `<O^\dagger O> - |<O>|^2`, which is not the variance of `O`.
+/// The state does not need to be normalized.
+#[must_use]
+pub fn expectation_and_variance<T: Tensor>(
+    state: &Array1<Complex<f64>>,
+    operator: &T,
+) -> (Complex<f64>, f64) {
+    let operator_state = operator.dot(state);
+    let norm_sqr = state.iter().map(Complex::norm_sqr).sum::<f64>();
+
+    let mut expectation = Complex::default();
+    for (psi, o_psi) in state.iter().zip(operator_state.iter()) {
+        expectation += psi.conj() * o_psi;
+    }
+    expectation /= norm_sqr;
+    let mean_sqr = operator_state.iter().map(Complex::norm_sqr).sum::<f64>() / norm_sqr;
+
+    (expectation, mean_sqr - expectation.norm_sqr())
+}
+
+/// Calculate the expectation `<O(t)>` of a time dependent `observable` at each time in `result`,
+/// where `times[i]` is the time of the state `result[i]`.
+///
+/// The state does not need to be normalized.
+///
+/// # Panics
+///
+/// Will panic if the number of times does not match the number of states in `result`
+#[must_use]
+pub fn time_dependent_expectation<F: Fn(f64) -> Array2<Complex<f64>>>(
+    result: &Array2<Complex<f64>>,
+    times: &[f64],
+    observable: F,
+) -> Array1<Complex<f64>> {
+    assert_eq!(result.nrows(), times.len());
+    let mut cached: Option<(f64, Array2<Complex<f64>>)> = None;
+    result
+        .outer_iter()
+        .zip(times)
+        .map(|(state, t)| {
+            // Avoid rebuilding the operator if the time is repeated
+            let operator = match cached.take() {
+                #[allow(clippy::float_cmp)]
+                Some((cached_t, operator)) if cached_t == *t => operator,
+                _ => observable(*t),
+            };
+            let (expectation, _) = expectation_and_variance(&state.to_owned(), &operator);
+            cached = Some((*t, operator));
+            expectation
+        })
+        .collect()
+}
+
+/// Calculate the time derivative of a `series` sampled every `dt`, ie the expectation of an
+/// observable at each save point.
+///
+/// A central difference is used for interior points, and a second order one-sided
+/// difference at each end. For noisy (ie ensemble averaged) data the series can first be smoothed
+/// with a centered moving average of `smoothing` points, truncated at the ends of the series.
+///
+/// # Panics
+///
+/// Will panic if the series has fewer than three points, or if `smoothing` is even
+#[must_use]
+pub fn time_derivative(
+    series: &Array1<Complex<f64>>,
+    dt: f64,
+    smoothing: Option<usize>,
+) -> Array1<Complex<f64>> {
+    let n = series.len();
+    assert!(n >= 3, "At least three points are required");
+
+    let smoothed = match smoothing {
+        Some(window) => {
+            assert!(window % 2 == 1, "The smoothing window must be odd");
+            let half = window / 2;
+            Array1::from_shape_fn(n, |i| {
+                let points = series.slice(s![i.saturating_sub(half)..(i + half + 1).min(n)]);
+                #[allow(clippy::cast_precision_loss)]
+                let n_points = points.len() as f64;
+                points.sum() / n_points
+            })
+        }
+        None => series.to_owned(),
+    };
+
+    let scale = 0.5 / dt;
+    Array1::from_shape_fn(n, |i| {
+        scale
+            * if i == 0 {
+                -3f64 * smoothed[0] + 4f64 * smoothed[1] - smoothed[2]
+            } else if i == n - 1 {
+                3f64 * smoothed[n - 1] - 4f64 * smoothed[n - 2] + smoothed[n - 3]
+            } else {
+                smoothed[i + 1] - smoothed[i - 1]
+            }
+    })
+}
+
+/// The real part of each amplitude of `result`
+#[must_use]
+pub fn real_part(result: &Array2<Complex<f64>>) -> Array2<f64> {
+    result.map(|psi| psi.re)
+}
+
+/// The imaginary part of each amplitude of `result`
+#[must_use]
+pub fn imag_part(result: &Array2<Complex<f64>>) -> Array2<f64> {
+    result.map(|psi| psi.im)
+}
+
+/// The probability `|\psi_i|^2` of each basis state at each time in `result`.
+///
+/// Unlike [`subsystem_populations`] the probabilities are not normalized.
+#[must_use]
+pub fn probabilities(result: &Array2<Complex<f64>>) -> Array2<f64> {
+    result.map(Complex::norm_sqr)
+}
+
+/// The phase of the amplitude of basis state `component` at each time in `result`,
+/// unwrapped so that it varies continuously rather than jumping at `±pi`.
+///
+/// # Panics
+///
+/// Panics if `component` is out of bounds for the states in `result`.
+#[must_use]
+pub fn unwrap_phase(result: &Array2<Complex<f64>>, component: usize) -> Array1<f64> {
+    assert!(
+        component < result.ncols(),
+        "component out of bounds for states in result"
+    );
+    let mut offset = 0f64;
+    let mut previous: Option<f64> = None;
+    result
+        .column(component)
+        .iter()
+        .map(|psi| {
+            let phase = psi.arg();
+            if let Some(previous) = previous {
+                let jump = phase - previous;
+                offset -= std::f64::consts::TAU * (jump / std::f64::consts::TAU).round();
+            }
+            previous = Some(phase);
+            phase + offset
+        })
+        .collect()
+}
+
+/// Calculate the shannon entropy `-\sum_i p_i ln p_i` of the basis state populations
+/// `p_i = |\psi_i|^2` at each time in `result`.
+///
+/// Populations are normalized to sum to one at each time, and `0 ln 0` is taken to be zero.
+#[must_use]
+pub fn population_entropy(result: &Array2<Complex<f64>>) -> Array1<f64> {
+    result
+        .outer_iter()
+        .map(|state| {
+            let total = state.iter().map(Complex::norm_sqr).sum::<f64>();
+            -state
+                .iter()
+                .map(|psi| psi.norm_sqr() / total)
+                .filter(|p| *p > 0f64)
+                .map(|p| p * p.ln())
+                .sum::<f64>()
+        })
+        .collect()
+}
+
+/// Calculate the projector onto the decoherence free subspace of `noise`,
+/// the common kernel of all collapse operators `L_i` acting on `n_states` states.
+///
+/// This is the kernel of the positive semi-definite `\sum_i L_i^\dagger L_i`, so the projector is
+/// zero if there is no decoherence free subspace, and the identity if there is no noise.
+///
+/// # Panics
+///
+/// Will panic if the eigendecomposition of `\sum_i L_i^\dagger L_i` fails
+#[must_use]
+pub fn dfs_projector<T: Tensor, U: Tensor>(
+    noise: &FullNoise<T, U>,
+    n_states: usize,
+) -> Array2<Complex<f64>> {
+    let (eigenvalues, eigenvectors) = eigh_hermitian(&noise.l_dagger_l(n_states));
+    // Eigenvalues below this are treated as zero
+    let tolerance = 1e-10 * eigenvalues.iter().fold(1f64, |a, p| a.max(p.abs()));
+
+    let mut out = Array2::zeros([n_states, n_states]);
+    for (eigenvalue, vector) in eigenvalues.iter().zip(eigenvectors.columns()) {
+        if eigenvalue.abs() < tolerance {
+            for ((i, j), o) in out.indexed_iter_mut() {
+                *o += vector[i] * vector[j].conj();
+            }
+        }
+    }
+    out
+}
+
+/// Calculate the leakage `1 - <\psi|P|\psi>` of a state out of the subspace with `projector` P.
+///
+/// The state does not need to be normalized.
+#[must_use]
+pub fn leakage(state: &Array1<Complex<f64>>, projector: &Array2<Complex<f64>>) -> f64 {
+    let (expectation, _) = expectation_and_variance(state, projector);
+    1f64 - expectation.re
+}
+
+/// Calculate `<\psi|A B|\psi>` as `(A^\dagger |\psi>)^\dagger (B |\psi>)`,
+/// without forming the product `AB`.
+#[must_use]
+pub fn sandwich<A: Adjoint, B: Tensor>(state: &Array1<Complex<f64>>, a: &A, b: &B) -> Complex<f64> {
+    let a_state = a.adjoint().dot(state);
+    let b_state = b.dot(state);
+    a_state
+        .iter()
+        .zip(b_state.iter())
+        .map(|(l, r)| l.conj() * r)
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{Array1, Array2, Array3};
+    use num_complex::Complex;
+    use rand::Rng;
+
+    use crate::distribution::StandardComplexNormal;
+
+    use crate::{
+        solvers::{EulerSolver, NormalizedEulerSolver, Solver},
+        sse_system::{FullNoise, SSESystem},
+    };
+
+    use super::{
+        dfs_projector, expectation_and_variance, imag_part, leakage, partial_trace,
+        population_entropy, probabilities, purity, quantum_fisher_information, real_part, sandwich,
+        subsystem_populations, time_dependent_expectation, time_derivative, trace, unwrap_phase,
+    };
+
+    #[test]
+    fn test_pure_state_qfi_is_variance() {
+        let n_states = 6;
+        let rng = rand::thread_rng();
+        let mut state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states),
+        );
+        let norm = state.iter().map(Complex::norm_sqr).sum::<f64>().sqrt();
+        state /= Complex { re: norm, im: 0f64 };
+
+        let random = Array2::from_shape_vec(
+            [n_states, n_states],
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states * n_states)
+                .collect(),
+        )
+        .unwrap();
+        let generator = &random + &random.t().map(Complex::conj);
+
+        let rho = Array2::from_shape_fn([n_states, n_states], |(i, j)| state[i] * state[j].conj());
+
+        let g_state = generator.dot(&state);
+        let mean = state.map(Complex::conj).dot(&g_state).re;
+        let mean_sq = g_state.iter().map(Complex::norm_sqr).sum::<f64>();
+        let expected = 4f64 * (mean_sq - mean * mean);
+
+        let actual = quantum_fisher_information(&rho, &generator);
+        assert!((expected - actual).abs() < 1e-8 * expected.abs().max(1f64));
+    }
+
+    #[test]
+    fn test_maximally_mixed_qfi_is_zero() {
+        let n_states = 4;
+        let rho = Array2::from_diag(&Array1::from_elem(
+            n_states,
+            Complex {
+                #[allow(clippy::cast_precision_loss)]
+                re: 1f64 / n_states as f64,
+                im: 0f64,
+            },
+        ));
+        let generator = Array2::from_diag(&Array1::from_iter((0..n_states).map(|i| Complex {
+            #[allow(clippy::cast_precision_loss)]
+            re: i as f64,
+            im: 0f64,
+        })));
+
+        assert!(quantum_fisher_information(&rho, &generator).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_partial_trace_product_state() {
+        // |psi> = |0> (x) (|0> + |1>) / sqrt(2)
+        let amplitude = Complex {
+            re: std::f64::consts::FRAC_1_SQRT_2,
+            im: 0f64,
+        };
+        let state = Array1::from(vec![
+            amplitude,
+            amplitude,
+            Complex::default(),
+            Complex::default(),
+        ]);
+
+        let first = partial_trace(state.view(), &[2, 2], 0);
+        assert!((first[[0, 0]].re - 1f64).abs() < 1e-12);
+        assert!(first[[1, 1]].norm() < 1e-12);
+
+        let second = partial_trace(state.view(), &[2, 2], 1);
+        for value in &second {
+            assert!((value.re - 0.5).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_subsystem_populations_exchange() {
+        // Exchange coupling J (|01><10| + |10><01|) transfers an excitation
+        // between the two qubits, with P_A(1) = cos^2(Jt) and P_B(1) = sin^2(Jt)
+        let coupling = 1f64;
+        let mut hamiltonian = Array2::zeros([4, 4]);
+        hamiltonian[[1, 2]] = Complex {
+            re: coupling,
+            im: 0f64,
+        };
+        hamiltonian[[2, 1]] = Complex {
+            re: coupling,
+            im: 0f64,
+        };
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 4, 4])),
+        };
+        let mut initial_state = Array1::zeros([4]);
+        initial_state[2] = Complex { re: 1f64, im: 0f64 };
+
+        let (n, step, dt) = (20, 100, 1e-3);
+        let result = EulerSolver::solve(&initial_state, &system, n, step, dt);
+
+        let first = subsystem_populations(&result, &[2, 2], 0);
+        let second = subsystem_populations(&result, &[2, 2], 1);
+        assert_eq!(first.shape(), [n, 2]);
+        for i in 0..n {
+            #[allow(clippy::cast_precision_loss)]
+            let t = (i * step) as f64 * dt;
+            assert!((first[[i, 1]] - (coupling * t).cos().powi(2)).abs() < 1e-2);
+            assert!((second[[i, 1]] - (coupling * t).sin().powi(2)).abs() < 1e-2);
+            assert!((first.row(i).sum() - 1f64).abs() < 1e-12);
+            assert!((second.row(i).sum() - 1f64).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_coherent_state_quadrature_variance() {
+        // A coherent state |alpha> has <x> = sqrt(2) Re(alpha) and Var(x) = 1/2
+        // where x = (a + a^\dagger) / sqrt(2)
+        let n_states = 40;
+        let alpha = Complex { re: 1.5, im: -0.5 };
+        let mut state = Array1::<Complex<f64>>::zeros([n_states]);
+        state[0] = Complex::from_polar((-0.5f64 * alpha.norm_sqr()).exp(), 0f64);
+        for n in 1..n_states {
+            #[allow(clippy::cast_precision_loss)]
+            let factor = alpha / (n as f64).sqrt();
+            state[n] = state[n - 1] * factor;
+        }
+
+        let mut position = Array2::zeros([n_states, n_states]);
+        for n in 1..n_states {
+            #[allow(clippy::cast_precision_loss)]
+            let element = Complex {
+                re: (n as f64 / 2f64).sqrt(),
+                im: 0f64,
+            };
+            position[[n - 1, n]] = element;
+            position[[n, n - 1]] = element;
+        }
+
+        let (expectation, variance) = expectation_and_variance(&state, &position);
+        assert!((expectation.re - std::f64::consts::SQRT_2 * alpha.re).abs() < 1e-10);
+        assert!(expectation.im.abs() < 1e-10);
+        assert!((variance - 0.5).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_sandwich_matches_dense_product() {
+        let n_states = 8;
+        let rng = rand::thread_rng();
+        let random_operator = || {
+            Array2::from_shape_vec(
+                [n_states, n_states],
+                rng.clone()
+                    .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                    .take(n_states * n_states)
+                    .collect(),
+            )
+            .unwrap()
+        };
+        let a = random_operator();
+        let b = random_operator();
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states),
+        );
+
+        let expected = state.map(Complex::conj).dot(&a.dot(&b.dot(&state)));
+        let actual = sandwich(&state, &a, &b);
+        assert!((expected - actual).norm() < 1e-12 * expected.norm().max(1f64));
+    }
+
+    #[test]
+    fn test_population_entropy() {
+        let n_states = 5;
+        let mut result = Array2::zeros([2, n_states]);
+        // A basis state
+        result[[0, 3]] = Complex { re: 0f64, im: 2f64 };
+        // An (unnormalized) uniform superposition
+        result.row_mut(1).fill(Complex { re: 1f64, im: 1f64 });
+
+        let entropy = population_entropy(&result);
+        assert!(entropy[0].abs() < 1e-12);
+        #[allow(clippy::cast_precision_loss)]
+        let expected = (n_states as f64).ln();
+        assert!((entropy[1] - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_probabilities_of_normalized_trajectory() {
+        let n_states = 4;
+        let initial_state = Array1::from_elem([n_states], Complex::from(0.5));
+        let mut operators = Array3::zeros([1, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex { re: 0.5, im: 0.2 };
+        }
+        let mut hamiltonian = Array2::zeros([n_states, n_states]);
+        for i in 1..n_states {
+            hamiltonian[[i - 1, i]] = Complex::from(1f64);
+            hamiltonian[[i, i - 1]] = Complex::from(1f64);
+        }
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&operators),
+        };
+
+        let result = NormalizedEulerSolver::solve(&initial_state, &system, 20, 10, 0.01);
+        let probabilities = probabilities(&result);
+        assert_eq!(probabilities.shape(), result.shape());
+        assert!(probabilities
+            .outer_iter()
+            .all(|row| (row.sum() - 1f64).abs() < 1e-8));
+
+        let (real, imag) = (real_part(&result), imag_part(&result));
+        let expected = &real * &real + &imag * &imag;
+        assert!((&expected - &probabilities).iter().all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_rotating_observable_expectation() {
+        // O(t) = cos(wt) sigma_x + sin(wt) sigma_y
+        let frequency = 3f64;
+        let observable = |t: f64| {
+            let (sin, cos) = (frequency * t).sin_cos();
+            Array2::from_shape_vec(
+                [2, 2],
+                vec![
+                    Complex::default(),
+                    Complex { re: cos, im: -sin },
+                    Complex { re: cos, im: sin },
+                    Complex::default(),
+                ],
+            )
+            .unwrap()
+        };
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex { re: 1f64, im: 0f64 };
+        hamiltonian[[1, 0]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[0] = Complex { re: 0.6, im: 0f64 };
+        initial_state[1] = Complex { re: 0f64, im: 0.8 };
+
+        let (n, step, dt) = (10, 10, 1e-2);
+        let result = EulerSolver::solve(&initial_state, &system, n, step, dt);
+        #[allow(clippy::cast_precision_loss)]
+        let times = (0..n).map(|i| (i * step) as f64 * dt).collect::<Vec<_>>();
+
+        let actual = time_dependent_expectation(&result, &times, observable);
+        assert_eq!(actual.len(), n);
+        for ((state, t), a) in result.outer_iter().zip(&times).zip(&actual) {
+            let (expected, _) = expectation_and_variance(&state.to_owned(), &observable(*t));
+            assert!((expected - a).norm() < 1e-12);
+        }
+        // The observable is not constant, so neither is the expectation
+        assert!((actual[0] - actual[n - 1]).norm() > 1e-3);
+    }
+
+    #[test]
+    fn test_time_derivative_of_exponential_decay() {
+        let (gamma, dt, n) = (0.8, 1e-3, 200);
+        #[allow(clippy::cast_precision_loss)]
+        let series = Array1::from_shape_fn(n, |i| Complex {
+            re: (-gamma * i as f64 * dt).exp(),
+            im: 0f64,
+        });
+
+        // The error of both stencils is O(dt^2)
+        let derivative = time_derivative(&series, dt, None);
+        for (d, o) in derivative.iter().zip(series.iter()) {
+            assert!((d + gamma * o).norm() < 1e-6);
+        }
+
+        // Smoothing a linear series has no effect on the derivative,
+        #[allow(clippy::cast_precision_loss)]
+        let linear = Array1::from_shape_fn(n, |i| Complex {
+            re: -2f64 * i as f64 * dt,
+            im: 0f64,
+        });
+        let derivative = time_derivative(&linear, dt, Some(5));
+        // away from the ends, where the window is truncated
+        for d in derivative.iter().skip(3).take(n - 6) {
+            assert!((d.re + 2f64).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_collective_noise_singlet_dfs() {
+        // Collective noise L_a = sigma_a (x) I + I (x) sigma_a for a = x, y, z
+        // annihilates only the singlet (|01> - |10>) / sqrt(2)
+        let (zero, one, i) = (
+            Complex::default(),
+            Complex { re: 1f64, im: 0f64 },
+            Complex { re: 0f64, im: 1f64 },
+        );
+        let paulis = [
+            [[zero, one], [one, zero]],
+            [[zero, -i], [i, zero]],
+            [[one, zero], [zero, -one]],
+        ];
+        let mut operators = Array3::zeros([3, 4, 4]);
+        for (n, pauli) in paulis.iter().enumerate() {
+            for (a, b, c, d) in two_qubit_indices() {
+                let identity = |x: usize, y: usize| if x == y { one } else { zero };
+                // <ab| L |cd> = <a|s|c><b|d> + <a|c><b|s|d>
+                operators[[n, 2 * a + b, 2 * c + d]] =
+                    pauli[a][c] * identity(b, d) + identity(a, c) * pauli[b][d];
+            }
+        }
+        let noise = FullNoise::from_operators(&operators);
+        let projector = dfs_projector(&noise, 4);
+
+        let amplitude = std::f64::consts::FRAC_1_SQRT_2;
+        let singlet = Array1::from(vec![zero, one * amplitude, -one * amplitude, zero]);
+        for ((x, y), value) in projector.indexed_iter() {
+            let expected = singlet[x] * singlet[y].conj();
+            assert!((value - expected).norm() < 1e-10);
+        }
+        assert!(leakage(&singlet, &projector).abs() < 1e-10);
+        let triplet = Array1::from(vec![one, zero, zero, zero]);
+        assert!((leakage(&triplet, &projector) - 1f64).abs() < 1e-10);
+
+        // There is no decoherence free subspace for L = I
+        let identity = Array2::from_diag(&Array1::from_elem(4, one));
+        let noise = FullNoise::from_operators(&identity.into_shape([1, 4, 4]).unwrap());
+        assert!(dfs_projector(&noise, 4).iter().all(|p| p.norm() < 1e-10));
+
+        // and the whole space is decoherence free without noise
+        let noise = FullNoise::from_operators(&Array3::zeros([0, 4, 4]));
+        let projector = dfs_projector(&noise, 4);
+        for ((x, y), value) in projector.indexed_iter() {
+            let expected = if x == y { 1f64 } else { 0f64 };
+            assert!((value - expected).norm() < 1e-10);
+        }
+    }
+
+    /// All indices (a, b, c, d) of the matrix elements <ab| O |cd> of a two qubit operator
+    fn two_qubit_indices() -> impl Iterator<Item = (usize, usize, usize, usize)> {
+        (0..16).map(|n| (n >> 3 & 1, n >> 2 & 1, n >> 1 & 1, n & 1))
+    }
+
+    fn outer(state: &Array1<Complex<f64>>) -> Array2<Complex<f64>> {
+        Array2::from_shape_fn([state.len(), state.len()], |(i, j)| {
+            state[i] * state[j].conj()
+        })
+    }
+
+    #[test]
+    fn test_purity_of_dephased_ensemble() {
+        let n_states = 3;
+        let initial_state = Array1::from_elem([n_states], Complex::from(1f64 / 3f64.sqrt()));
+        let pure = outer(&initial_state);
+        assert!((trace(&pure) - 1f64).norm() < 1e-12);
+        assert!((purity(&pure) - 1f64).abs() < 1e-12);
+
+        // Strong dephasing between each of the states
+        let mut operators = Array3::zeros([1, n_states, n_states]);
+        for i in 0..n_states {
+            #[allow(clippy::cast_precision_loss)]
+            let rate = 2f64 * i as f64;
+            operators[[0, i, i]] = Complex::from(rate);
+        }
+        let system = SSESystem {
+            hamiltonian: Array2::zeros([n_states, n_states]),
+            noise: FullNoise::from_operators(&operators),
+        };
+
+        let n_trajectories = 200;
+        let mut rho = Array2::zeros([n_states, n_states]);
+        for _ in 0..n_trajectories {
+            let result = NormalizedEulerSolver::solve(&initial_state, &system, 2, 250, 0.01);
+            let single = outer(&result.row(1).to_owned());
+            assert!((purity(&single) - 1f64).abs() < 1e-8);
+            rho += &(single / Complex::from(f64::from(n_trajectories)));
+        }
+        assert!((trace(&rho) - 1f64).norm() < 1e-8);
+        assert!((purity(&rho) - 1f64 / 3f64).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_unwrap_phase_of_rotating_phasor() {
+        let n_times = 200;
+        let omega = 0.7;
+        let initial_phase = 0.3;
+        let mut result = Array2::zeros([n_times, 2]);
+        for (i, mut state) in result.outer_iter_mut().enumerate() {
+            let phase = initial_phase + omega * f64::from(u32::try_from(i).unwrap());
+            state[0] = Complex::from(1f64);
+            state[1] = Complex::from_polar(0.5, phase);
+        }
+
+        let unwrapped = unwrap_phase(&result, 1);
+        assert_eq!(unwrapped.len(), n_times);
+        for (i, phase) in unwrapped.iter().enumerate() {
+            let expected = initial_phase + omega * f64::from(u32::try_from(i).unwrap());
+            assert!((phase - expected).abs() < 1e-10, "{phase} != {expected}");
+        }
+        assert!(unwrap_phase(&result, 0)
+            .iter()
+            .all(|phase| phase.abs() < 1e-12));
+    }
+}
@@ -0,0 +1,202 @@
+use ndarray::{linalg::Dot, Array1, Array2};
+use num_complex::Complex;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sparse::{BandedArray, FactorizedArray, TransposedBandedArray},
+    sse_system::{Adjoint, FullNoise, OperatorShape, SSESystem},
+    SseError,
+};
+
+/// A collapse operator in any of the representations accepted by [`SseSystemBuilder`].
+///
+/// Operators of other types, such as a [`crate::sparse::CsrArray`], can instead be
+/// combined in a [`FullNoise`] as a [`crate::sparse::BoxedTensor`].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CollapseOperator {
+    Dense(Array2<Complex<f64>>),
+    Banded(BandedArray<Complex<f64>>),
+    TransposedBanded(TransposedBandedArray<Complex<f64>>),
+    Factorized(FactorizedArray<Complex<f64>>),
+}
+
+impl Dot<Array1<Complex<f64>>> for CollapseOperator {
+    type Output = Array1<Complex<f64>>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<Complex<f64>>) -> Self::Output {
+        match self {
+            CollapseOperator::Dense(o) => o.dot(rhs),
+            CollapseOperator::Banded(o) => o.dot(rhs),
+            CollapseOperator::TransposedBanded(o) => o.dot(rhs),
+            CollapseOperator::Factorized(o) => o.dot(rhs),
+        }
+    }
+}
+
+impl OperatorShape for CollapseOperator {
+    fn operator_shape(&self) -> [usize; 2] {
+        match self {
+            CollapseOperator::Dense(o) => o.operator_shape(),
+            CollapseOperator::Banded(o) => o.operator_shape(),
+            CollapseOperator::TransposedBanded(o) => o.operator_shape(),
+            CollapseOperator::Factorized(o) => o.operator_shape(),
+        }
+    }
+}
+
+impl Adjoint for CollapseOperator {
+    type Output = CollapseOperator;
+
+    fn adjoint(&self) -> Self::Output {
+        match self {
+            CollapseOperator::Dense(o) => CollapseOperator::Dense(o.adjoint()),
+            CollapseOperator::Banded(o) => CollapseOperator::TransposedBanded(o.adjoint()),
+            CollapseOperator::TransposedBanded(o) => CollapseOperator::Banded(o.adjoint()),
+            CollapseOperator::Factorized(o) => CollapseOperator::Factorized(o.adjoint()),
+        }
+    }
+}
+
+/// Build a [`SSESystem`] by adding collapse operators one at a time.
+///
+/// Operators of each representation are stored as a [`CollapseOperator`],
+/// and the shapes of all operators are checked when the system is built.
+#[derive(Clone)]
+pub struct SseSystemBuilder<H = Array2<Complex<f64>>> {
+    hamiltonian: Option<H>,
+    operators: Vec<CollapseOperator>,
+}
+
+impl<H> Default for SseSystemBuilder<H> {
+    fn default() -> Self {
+        Self {
+            hamiltonian: None,
+            operators: Vec::new(),
+        }
+    }
+}
+
+impl<H: OperatorShape> SseSystemBuilder<H> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_hamiltonian(mut self, hamiltonian: H) -> Self {
+        self.hamiltonian = Some(hamiltonian);
+        self
+    }
+
+    #[must_use]
+    pub fn add_collapse_operator(mut self, operator: Array2<Complex<f64>>) -> Self {
+        self.operators.push(CollapseOperator::Dense(operator));
+        self
+    }
+
+    #[must_use]
+    pub fn add_banded_collapse(mut self, operator: BandedArray<Complex<f64>>) -> Self {
+        self.operators.push(CollapseOperator::Banded(operator));
+        self
+    }
+
+    #[must_use]
+    pub fn add_factorized_collapse(mut self, operator: FactorizedArray<Complex<f64>>) -> Self {
+        self.operators.push(CollapseOperator::Factorized(operator));
+        self
+    }
+
+    /// Build the system, checking that every operator acts on the states of the hamiltonian.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SseError::MissingHamiltonian`] if no hamiltonian was given,
+    /// or an error if an operator is not square or the operators differ in shape
+    pub fn build(
+        self,
+    ) -> Result<SSESystem<H, FullNoise<CollapseOperator, CollapseOperator>>, SseError> {
+        let hamiltonian = self.hamiltonian.ok_or(SseError::MissingHamiltonian)?;
+        let noise = FullNoise::try_from_collapse_operators(&self.operators)?;
+        SSESystem::try_new(hamiltonian, noise)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{Array1, Array2, Array3, Axis};
+    use num_complex::Complex;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        solvers::{EulerSolver, Solver},
+        sparse::BandedArray,
+        sse_system::{FullNoise, SSESystem},
+        SseError,
+    };
+
+    use super::SseSystemBuilder;
+
+    #[test]
+    fn test_builder_matches_direct_construction() {
+        let n_states = 5;
+        let mut hamiltonian = Array2::zeros([n_states, n_states]);
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            hamiltonian[[i - 1, i]] = Complex { re: 1f64, im: 0f64 };
+            hamiltonian[[i, i - 1]] = Complex { re: 1f64, im: 0f64 };
+            operators[[0, i - 1, i]] = Complex { re: 0.5, im: 0f64 };
+            operators[[1, i, i]] = Complex { re: 0f64, im: 0.2 };
+        }
+
+        let built = SseSystemBuilder::new()
+            .with_hamiltonian(hamiltonian.clone())
+            .add_collapse_operator(operators.index_axis(Axis(0), 0).to_owned())
+            .add_banded_collapse(BandedArray::from_dense(
+                &operators.index_axis(Axis(0), 1).to_owned(),
+            ))
+            .build()
+            .unwrap();
+        let direct = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&operators),
+        };
+
+        let mut initial_state = Array1::zeros([n_states]);
+        initial_state[0] = Complex { re: 1f64, im: 0f64 };
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &direct,
+            5,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(1),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &built,
+            5,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-12));
+
+        let mismatched = SseSystemBuilder::new()
+            .with_hamiltonian(Array2::<Complex<f64>>::zeros([n_states, n_states]))
+            .add_collapse_operator(Array2::zeros([n_states + 1, n_states + 1]))
+            .build();
+        assert!(matches!(
+            mismatched,
+            Err(SseError::HamiltonianMismatch { .. })
+        ));
+
+        let missing = SseSystemBuilder::<Array2<Complex<f64>>>::new()
+            .add_collapse_operator(Array2::zeros([n_states, n_states]))
+            .build();
+        assert!(matches!(missing, Err(SseError::MissingHamiltonian)));
+    }
+}
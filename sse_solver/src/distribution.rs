@@ -25,6 +25,128 @@ impl Distribution<Complex<f64>> for StandardComplexNormal {
     }
 }
 
+/// Fill `out` with samples of the [`StandardComplexNormal`] distribution drawn from `rng`.
+///
+/// The samples are drawn in order, so this is identical to sampling each element in turn.
+/// Any rng can be used, for example a [`rand::SeedableRng`] managed by the caller.
+#[inline]
+pub fn fill_complex_normal<R: Rng + ?Sized>(out: &mut [Complex<f64>], rng: &mut R) {
+    for value in out {
+        *value = StandardComplexNormal.sample(rng);
+    }
+}
+
+/// A complex normal distribution with `<z z*> = variance`,
+/// where the real and imaginary parts each have a variance of `variance / 2`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplexNormal {
+    variance: f64,
+}
+
+impl ComplexNormal {
+    /// # Panics
+    ///
+    /// Will panic if `variance` is negative or not finite
+    #[must_use]
+    pub fn new(variance: f64) -> Self {
+        assert!(variance.is_finite() && variance >= 0f64);
+        Self { variance }
+    }
+
+    /// Build the distribution with a standard deviation `sqrt(<z z*>)` of `std`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `std` is negative or not finite
+    #[must_use]
+    pub fn with_std(std: f64) -> Self {
+        assert!(std.is_finite() && std >= 0f64);
+        Self::new(std * std)
+    }
+
+    #[must_use]
+    pub fn variance(&self) -> f64 {
+        self.variance
+    }
+}
+
+impl Distribution<Complex<f32>> for ComplexNormal {
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Complex<f32> {
+        let sample: Complex<f32> = StandardComplexNormal.sample(rng);
+        sample * (self.variance.sqrt() as f32)
+    }
+}
+
+impl Distribution<Complex<f64>> for ComplexNormal {
+    #[inline]
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Complex<f64> {
+        let sample: Complex<f64> = StandardComplexNormal.sample(rng);
+        sample * self.variance.sqrt()
+    }
+}
+
+/// A sampled path of the Ornstein-Uhlenbeck process `dx = -gamma x dt + sigma dW`,
+/// at the times `k dt`.
+///
+/// This is colored noise, with an autocorrelation `<x(t) x(0)> = sigma^2 / (2 gamma) e^{-gamma t}`
+/// and a lorentzian spectral density `sigma^2 / (gamma^2 + w^2)`. It can be coupled into
+/// a system as the envelope of a [`ModulatedHamiltonian`], using [`OrnsteinUhlenbeckPath::value`].
+/// The path is sampled up front, so the solvers (which may evaluate the hamiltonian several
+/// times per step) see the same realization of the noise.
+///
+/// [`ModulatedHamiltonian`]: crate::sse_system::ModulatedHamiltonian
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrnsteinUhlenbeckPath {
+    dt: f64,
+    values: Vec<f64>,
+}
+
+impl OrnsteinUhlenbeckPath {
+    /// Sample `n` points of the process, starting from the stationary distribution.
+    ///
+    /// The exact update `x_{k+1} = e^{-gamma dt} x_k + sigma sqrt((1 - e^{-2 gamma dt}) / (2 gamma)) xi_k`
+    /// is used, so the statistics of the path do not depend on `dt`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `gamma` or `dt` is not positive
+    pub fn sample<R: Rng + ?Sized>(gamma: f64, sigma: f64, dt: f64, n: usize, rng: &mut R) -> Self {
+        assert!(gamma > 0f64 && dt > 0f64);
+        let decay = (-gamma * dt).exp();
+        let stationary_std = sigma / (2f64 * gamma).sqrt();
+        let step_std = stationary_std * (1f64 - decay * decay).sqrt();
+
+        let mut current = stationary_std * rng.sample::<f64, _>(StandardNormal);
+        let values = (0..n)
+            .map(|_| {
+                let value = current;
+                current = decay * current + step_std * rng.sample::<f64, _>(StandardNormal);
+                value
+            })
+            .collect();
+        Self { dt, values }
+    }
+
+    /// The value of the sample nearest to time `t`, or the last sample after the end of the path.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the path is empty
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn value(&self, t: f64) -> f64 {
+        let index = (t / self.dt).round().max(0f64) as usize;
+        self.values[index.min(self.values.len() - 1)]
+    }
+
+    #[must_use]
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+}
+
 // The V distribution for n incoherent operators, according to eqn 14.2.8 - 14.2.10
 // in TODO paper
 pub struct VMatrix {
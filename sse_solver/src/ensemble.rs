@@ -0,0 +1,370 @@
+use ndarray::{Array, ArrayBase, Data, Dimension, ShapeBuilder, Zip};
+use num_complex::Complex;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::system::ComplexScalar;
+
+/// The number of trajectories accumulated together before merging, when solving an ensemble
+pub(crate) const ENSEMBLE_CHUNK_SIZE: usize = 32;
+
+/// Get the rng used for trajectory `index` of an ensemble with the given `master_seed`.
+///
+/// Each trajectory has an independent stream, which can be reconstructed
+/// without running any of the other trajectories in the ensemble.
+#[must_use]
+pub fn trajectory_rng(master_seed: u64, index: u64) -> StdRng {
+    let mut seed = <StdRng as SeedableRng>::Seed::default();
+    seed[..8].copy_from_slice(&master_seed.to_le_bytes());
+    seed[8..16].copy_from_slice(&index.to_le_bytes());
+    StdRng::from_seed(seed)
+}
+
+/// Accumulates the mean and variance of samples online (using welford's algorithm),
+/// without storing every sample.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnsembleAccumulator<D: Dimension> {
+    mean: Array<Complex<f64>, D>,
+    // The sum of |x - mean|^2
+    m2: Array<f64, D>,
+    n_samples: usize,
+}
+
+impl<D: Dimension> EnsembleAccumulator<D> {
+    #[must_use]
+    pub fn new<Sh: ShapeBuilder<Dim = D>>(shape: Sh) -> Self {
+        let mean = Array::zeros(shape);
+        Self {
+            m2: Array::zeros(mean.raw_dim()),
+            mean,
+            n_samples: 0,
+        }
+    }
+
+    /// Add a single sample to the accumulator
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the shape of the sample does not match the accumulator
+    #[allow(clippy::cast_precision_loss)]
+    pub fn push<S: Data<Elem = A>, A: ComplexScalar>(&mut self, sample: &ArrayBase<S, D>) {
+        assert_eq!(self.mean.shape(), sample.shape());
+        self.n_samples += 1;
+        let n = self.n_samples as f64;
+        Zip::from(&mut self.mean)
+            .and(&mut self.m2)
+            .and(sample)
+            .for_each(|mean, m2, x| {
+                let x = x.to_c64();
+                let delta = x - *mean;
+                *mean += delta / n;
+                *m2 += (delta.conj() * (x - *mean)).re;
+            });
+    }
+
+    /// Combine with the accumulator of a disjoint set of samples
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the shape of the accumulators do not match
+    #[allow(clippy::cast_precision_loss)]
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(self.mean.shape(), other.mean.shape());
+        if other.n_samples == 0 {
+            return;
+        }
+        let n_a = self.n_samples as f64;
+        let n_b = other.n_samples as f64;
+        let n = n_a + n_b;
+        Zip::from(&mut self.mean)
+            .and(&mut self.m2)
+            .and(&other.mean)
+            .and(&other.m2)
+            .for_each(|mean, m2, other_mean, other_m2| {
+                let delta = other_mean - *mean;
+                *mean += delta * (n_b / n);
+                *m2 += other_m2 + delta.norm_sqr() * n_a * n_b / n;
+            });
+        self.n_samples += other.n_samples;
+    }
+
+    #[must_use]
+    pub fn n_samples(&self) -> usize {
+        self.n_samples
+    }
+
+    #[must_use]
+    pub fn mean(&self) -> &Array<Complex<f64>, D> {
+        &self.mean
+    }
+
+    /// The (unbiased) sample variance `E[|x - mean|^2]` of each element
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are fewer than two samples
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn variance(&self) -> Array<f64, D> {
+        assert!(self.n_samples > 1, "At least two samples are required");
+        &self.m2 / (self.n_samples - 1) as f64
+    }
+
+    /// The standard error of the mean of each element
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are fewer than two samples
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn standard_error(&self) -> Array<f64, D> {
+        let n = self.n_samples as f64;
+        self.variance().mapv_into(|v| (v / n).sqrt())
+    }
+}
+
+/// Accumulates samples (ie the observables of each trajectory in an ensemble)
+/// into `n_bins` bins, storing only the running sum of each bin.
+///
+/// Samples are assigned to bins in turn, so if the number of samples is not
+/// divisible by the number of bins the first bins hold one extra sample.
+/// The per-bin means can be used for binning (jackknife or bootstrap) error estimates
+/// without storing every sample.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinnedAccumulator<D: Dimension> {
+    sums: Vec<Array<Complex<f64>, D>>,
+    counts: Vec<usize>,
+    n_samples: usize,
+}
+
+impl<D: Dimension> BinnedAccumulator<D> {
+    /// # Panics
+    ///
+    /// Will panic if `n_bins` is zero
+    #[must_use]
+    pub fn new<Sh: ShapeBuilder<Dim = D>>(shape: Sh, n_bins: usize) -> Self {
+        assert!(n_bins > 0);
+        let zeros = Array::zeros(shape);
+        Self {
+            sums: vec![zeros; n_bins],
+            counts: vec![0; n_bins],
+            n_samples: 0,
+        }
+    }
+
+    /// Add a single sample to the accumulator
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the shape of the sample does not match the accumulator
+    pub fn push<S: Data<Elem = Complex<f64>>>(&mut self, sample: &ArrayBase<S, D>) {
+        let bin = self.n_samples % self.sums.len();
+        assert_eq!(self.sums[bin].shape(), sample.shape());
+        self.sums[bin] += sample;
+        self.counts[bin] += 1;
+        self.n_samples += 1;
+    }
+
+    #[must_use]
+    pub fn n_samples(&self) -> usize {
+        self.n_samples
+    }
+
+    #[must_use]
+    pub fn n_bins(&self) -> usize {
+        self.sums.len()
+    }
+
+    /// The number of samples in each bin
+    #[must_use]
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// The mean of the samples in each bin
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bin_means(&self) -> Vec<Array<Complex<f64>, D>> {
+        self.sums
+            .iter()
+            .zip(&self.counts)
+            .map(|(sum, count)| sum / Complex::from(*count as f64))
+            .collect()
+    }
+
+    /// The mean of all samples
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> Array<Complex<f64>, D> {
+        self.total() / Complex::from(self.n_samples as f64)
+    }
+
+    fn total(&self) -> Array<Complex<f64>, D> {
+        let mut total = self.sums[0].clone();
+        for sum in &self.sums[1..] {
+            total += sum;
+        }
+        total
+    }
+
+    /// Estimate the standard error of the mean of each element using the jackknife,
+    /// leaving out one bin at a time.
+    ///
+    /// The error of a complex element is the error in its modulus `sqrt(err_re^2 + err_im^2)`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are fewer than two non-empty bins
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn jackknife_error(&self) -> Array<f64, D> {
+        let n_filled = self.counts.iter().filter(|c| **c > 0).count();
+        assert!(n_filled > 1, "At least two non-empty bins are required");
+
+        let total = self.total();
+        let leave_one_out = self
+            .sums
+            .iter()
+            .zip(&self.counts)
+            .filter(|(_, count)| **count > 0)
+            .map(|(sum, count)| (&total - sum) / Complex::from((self.n_samples - count) as f64))
+            .collect::<Vec<_>>();
+
+        let mut jackknife_mean = Array::zeros(total.raw_dim());
+        for mean in &leave_one_out {
+            jackknife_mean += mean;
+        }
+        jackknife_mean /= Complex::from(n_filled as f64);
+
+        let mut variance = Array::<f64, D>::zeros(total.raw_dim());
+        for mean in &leave_one_out {
+            Zip::from(&mut variance)
+                .and(mean)
+                .and(&jackknife_mean)
+                .for_each(|v, m, j| *v += (m - j).norm_sqr());
+        }
+        let factor = (n_filled - 1) as f64 / n_filled as f64;
+        variance.mapv_into(|v| (factor * v).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::Array1;
+    use num_complex::Complex;
+    use rand::Rng;
+
+    use crate::distribution::StandardComplexNormal;
+
+    use super::{trajectory_rng, BinnedAccumulator, EnsembleAccumulator};
+
+    #[test]
+    fn test_bin_means_average_to_mean() {
+        let n_samples = 103;
+        let mut accumulator = BinnedAccumulator::new(4, 10);
+        let mut rng = rand::thread_rng();
+        for _ in 0..n_samples {
+            let sample = Array1::from_iter(
+                (&mut rng)
+                    .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                    .take(4),
+            );
+            accumulator.push(&sample);
+        }
+        assert_eq!(accumulator.n_samples(), n_samples);
+        assert_eq!(accumulator.counts().iter().sum::<usize>(), n_samples);
+        assert_eq!(accumulator.counts()[0], 11);
+        assert_eq!(accumulator.counts()[9], 10);
+
+        let mut weighted = Array1::zeros(4);
+        for (mean, count) in accumulator.bin_means().iter().zip(accumulator.counts()) {
+            #[allow(clippy::cast_precision_loss)]
+            let weight = *count as f64 / n_samples as f64;
+            weighted += &(mean * Complex::from(weight));
+        }
+        for (w, m) in weighted.iter().zip(accumulator.mean().iter()) {
+            assert!((w - m).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_jackknife_error_of_normal_samples() {
+        // The standard error of the mean of N samples with E[|z|^2] = 1 is 1 / sqrt(N)
+        let n_samples = 5000;
+        let mut accumulator = BinnedAccumulator::new(3, 50);
+        let mut rng = rand::thread_rng();
+        for _ in 0..n_samples {
+            let sample = Array1::from_iter(
+                (&mut rng)
+                    .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                    .take(3),
+            );
+            accumulator.push(&sample);
+        }
+
+        let expected = 1f64 / f64::from(n_samples).sqrt();
+        for error in accumulator.jackknife_error() {
+            assert!(error > 0.5 * expected && error < 1.5 * expected);
+        }
+    }
+
+    #[test]
+    fn test_ensemble_accumulator_merge() {
+        let mut rng = trajectory_rng(7, 0);
+        let samples = (0..100)
+            .map(|_| {
+                Array1::from_iter(
+                    (&mut rng)
+                        .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                        .take(3),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut full = EnsembleAccumulator::new(3);
+        let mut first = EnsembleAccumulator::new(3);
+        let mut second = EnsembleAccumulator::new(3);
+        for (i, sample) in samples.iter().enumerate() {
+            full.push(sample);
+            if i < 40 {
+                first.push(sample);
+            } else {
+                second.push(sample);
+            }
+        }
+        first.merge(&second);
+        assert_eq!(first.n_samples(), full.n_samples());
+
+        let mean = samples
+            .iter()
+            .fold(Array1::<Complex<f64>>::zeros(3), |a, s| a + s)
+            / Complex::from(100f64);
+        let variance = samples.iter().fold(Array1::<f64>::zeros(3), |a, s| {
+            a + (s - &mean).map(Complex::norm_sqr)
+        }) / 99f64;
+        for i in 0..3 {
+            assert!((full.mean()[i] - mean[i]).norm() < 1e-12);
+            assert!((first.mean()[i] - mean[i]).norm() < 1e-12);
+            assert!((full.variance()[i] - variance[i]).abs() < 1e-12);
+            assert!((first.variance()[i] - variance[i]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_trajectory_rng_streams() {
+        use rand::RngCore;
+        assert_eq!(
+            trajectory_rng(1, 2).next_u64(),
+            trajectory_rng(1, 2).next_u64()
+        );
+        assert_ne!(
+            trajectory_rng(1, 2).next_u64(),
+            trajectory_rng(1, 3).next_u64()
+        );
+        assert_ne!(
+            trajectory_rng(1, 2).next_u64(),
+            trajectory_rng(2, 1).next_u64()
+        );
+    }
+}
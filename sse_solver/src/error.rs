@@ -0,0 +1,66 @@
+use std::{error::Error, fmt};
+
+/// An error encountered while building a system
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum SseError {
+    /// The operator at `index` has the given `shape`, but operators must be square
+    NonSquareOperator { index: usize, shape: [usize; 2] },
+    /// The operator at `index` has the given `shape`, which differs from the
+    /// `expected` shape of the preceding operators
+    MismatchedOperator {
+        index: usize,
+        shape: [usize; 2],
+        expected: [usize; 2],
+    },
+    /// The number of amplitudes, bras and kets used to build a noise differ
+    MismatchedLength {
+        amplitudes: usize,
+        bras: usize,
+        kets: usize,
+    },
+    /// The hamiltonian has the given `shape`, but the noise acts on `n_states` states
+    HamiltonianMismatch { shape: [usize; 2], n_states: usize },
+    /// The hamiltonian is not hermitian, with a largest element `error` of `H - H^\dagger`
+    NonHermitian { error: f64 },
+    /// The system was built without a hamiltonian
+    MissingHamiltonian,
+}
+
+impl fmt::Display for SseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SseError::NonSquareOperator { index, shape } => write!(
+                f,
+                "operator {index} has shape {shape:?}, but operators must be square"
+            ),
+            SseError::MismatchedOperator {
+                index,
+                shape,
+                expected,
+            } => write!(
+                f,
+                "operator {index} has shape {shape:?}, but the previous operators have shape {expected:?}"
+            ),
+            SseError::MismatchedLength {
+                amplitudes,
+                bras,
+                kets,
+            } => write!(
+                f,
+                "got {amplitudes} amplitudes, {bras} bras and {kets} kets, which must be equal"
+            ),
+            SseError::HamiltonianMismatch { shape, n_states } => write!(
+                f,
+                "hamiltonian has shape {shape:?}, but the noise acts on {n_states} states"
+            ),
+            SseError::NonHermitian { error } => write!(
+                f,
+                "hamiltonian is not hermitian, with an element of H - H^dagger of size {error}"
+            ),
+            SseError::MissingHamiltonian => write!(f, "no hamiltonian was given"),
+        }
+    }
+}
+
+impl Error for SseError {}
@@ -0,0 +1,140 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use ndarray::Array2;
+#[cfg(feature = "npy")]
+use ndarray_npy::{read_npy, write_npy, ReadNpyError, WriteNpyError};
+use num_complex::Complex;
+
+/// Save a time series of (complex) observables to a CSV file at `path`.
+///
+/// `observables` has shape `[times.len(), labels.len()]`, and each observable is written
+/// as a pair of columns `{label}_re,{label}_im` following a leading `t` column.
+/// Values are written with the shortest representation that round-trips exactly.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be created or written to
+///
+/// # Panics
+///
+/// Will panic if the shape of `observables` does not match `times` and `labels`,
+/// or if a label contains a comma or newline.
+pub fn save_csv<P: AsRef<Path>>(
+    path: P,
+    times: &[f64],
+    observables: &Array2<Complex<f64>>,
+    labels: &[&str],
+) -> std::io::Result<()> {
+    assert_eq!(observables.shape(), [times.len(), labels.len()]);
+    for label in labels {
+        assert!(!label.contains([',', '\n', '\r']), "Invalid label {label}");
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "t")?;
+    for label in labels {
+        write!(writer, ",{label}_re,{label}_im")?;
+    }
+    writeln!(writer)?;
+
+    for (t, row) in times.iter().zip(observables.outer_iter()) {
+        write!(writer, "{t}")?;
+        for value in row {
+            write!(writer, ",{},{}", value.re, value.im)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Save a trajectory to a `.npy` file at `path`.
+///
+/// The values are stored with the `complex128` dtype, ie as interleaved real
+/// and imaginary parts, so the file can be loaded directly with `numpy.load`.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be created or written to
+#[cfg(feature = "npy")]
+pub fn save_trajectory<P: AsRef<Path>>(
+    path: P,
+    trajectory: &Array2<Complex<f64>>,
+) -> Result<(), WriteNpyError> {
+    write_npy(path, trajectory)
+}
+
+/// Load a trajectory saved with [`save_trajectory`], or any two dimensional
+/// `complex128` array saved by `numpy.save`.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, or does not contain
+/// a two dimensional `complex128` array
+#[cfg(feature = "npy")]
+pub fn load_trajectory<P: AsRef<Path>>(path: P) -> Result<Array2<Complex<f64>>, ReadNpyError> {
+    read_npy(path)
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::Array2;
+    use num_complex::Complex;
+
+    use super::save_csv;
+
+    #[test]
+    // Values are written with a representation that round-trips exactly
+    #[allow(clippy::float_cmp)]
+    fn test_save_csv_round_trip() {
+        let times = [0f64, 0.1, 0.2];
+        let observables = Array2::from_shape_fn([3, 2], |(i, j)| {
+            #[allow(clippy::cast_precision_loss)]
+            Complex {
+                re: (i as f64 + 1f64) / 3f64,
+                im: -(j as f64) * std::f64::consts::PI,
+            }
+        });
+        let path = std::env::temp_dir().join("sse_solver_test_save_csv_round_trip.csv");
+        save_csv(&path, &times, &observables, &["x", "p"]).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(lines.next(), Some("t,x_re,x_im,p_re,p_im"));
+
+        for (i, line) in lines.enumerate() {
+            let values = line
+                .split(',')
+                .map(|v| v.parse::<f64>().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(values.len(), 5);
+            assert_eq!(values[0], times[i]);
+            for j in 0..2 {
+                assert_eq!(values[1 + 2 * j], observables[[i, j]].re);
+                assert_eq!(values[2 + 2 * j], observables[[i, j]].im);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "npy")]
+    fn test_save_trajectory_round_trip() {
+        let trajectory = Array2::from_shape_fn([4, 3], |(i, j)| {
+            #[allow(clippy::cast_precision_loss)]
+            Complex {
+                re: (i as f64) / 7f64,
+                im: -(j as f64) * std::f64::consts::E,
+            }
+        });
+        let path = std::env::temp_dir().join("sse_solver_test_save_trajectory_round_trip.npy");
+        super::save_trajectory(&path, &trajectory).unwrap();
+
+        let loaded = super::load_trajectory(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, trajectory);
+    }
+}
@@ -1,24 +1,52 @@
 #![warn(clippy::pedantic)]
 
+pub mod analysis;
+pub mod builder;
 pub mod distribution;
+pub mod ensemble;
+pub mod error;
+pub mod io;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod solvers;
 pub mod sparse;
 pub mod sse_system;
 pub mod system;
 
+pub use error::SseError;
+
 #[cfg(test)]
 mod tests {
     type DiagonalNoise = FullNoise<FactorizedArray<Complex<f64>>, FactorizedArray<Complex<f64>>>;
+    type DenseNoise = FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>>;
+    use std::ops::ControlFlow;
 
-    use ndarray::{linalg::Dot, s, Array1, Array2};
+    use ndarray::{linalg::Dot, s, Array1, Array2, Array3, ArrayView1, Axis};
+    use ndarray_linalg::Norm;
     use num_complex::{Complex, ComplexFloat};
-    use rand::Rng;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
 
     use crate::{
-        distribution::StandardComplexNormal,
-        solvers::{EulerSolver, Solver},
-        sparse::{BandedArray, FactorizedArray},
-        sse_system::{FullNoise, SSESystem},
+        distribution::{
+            fill_complex_normal, ComplexNormal, OrnsteinUhlenbeckPath, StandardComplexNormal,
+        },
+        ensemble::trajectory_rng,
+        solvers::{
+            check_contractive, solve_steady_state, solve_with_energy, two_time_correlation,
+            validate_only, EulerSolver, HeunSolver, ImaginaryTimeSolver, ImplicitEulerSolver,
+            JumpSolver, LindbladSolver, MidpointSolver, MilstenSolver, NormalizedEulerSolver,
+            Order2ExplicitWeakSolver, RichardsonSolver, SolveOptions, Solver, SolverError,
+            SolverState, SrkSolver, StepWorkspace, StratonovichHeunSolver, WeakOrder2Solver,
+        },
+        sparse::{
+            BandedArray, BoxedTensor, CsrArray, DiagonalArray, FactorizedArray, FactorizedSum,
+        },
+        sse_system::{
+            Adjoint, FullNoise, NoiseConvention, OperatorFnHamiltonian, OperatorShape, SSESystem,
+            StratonovichNoise, Tensor,
+        },
+        system::{ComplexScalar, SDEStep, SDESystem},
     };
 
     fn get_random_noise(
@@ -86,12 +114,123 @@ mod tests {
         }
     }
 
+    /// A closed system `H = \sum_n E_n |n><n|` with an equal superposition initial state,
+    /// and the exact state `\psi_n(t_end) = \psi_n(0) e^{-i E_n t_end}`
+    #[allow(clippy::type_complexity)]
+    fn diagonal_closed_system(
+        t_end: f64,
+    ) -> (
+        SSESystem<Array2<Complex<f64>>, DiagonalNoise>,
+        Array1<Complex<f64>>,
+        Array1<Complex<f64>>,
+    ) {
+        let energies = Array1::from(vec![0.5, -1.0, 2.0, 0.2]);
+        let system = SSESystem {
+            noise: get_random_noise(0, energies.len()),
+            hamiltonian: Array2::from_diag(&energies.map(|e| Complex { re: *e, im: 0f64 })),
+        };
+        let amplitude = Complex { re: 0.5, im: 0f64 };
+        let initial_state = Array1::from_elem(energies.len(), amplitude);
+        let expected = energies.map(|e| amplitude * Complex::from_polar(1f64, -e * t_end));
+        (system, initial_state, expected)
+    }
+
+    /// The L2 norm of `actual - expected`
+    fn l2_error(actual: ArrayView1<'_, Complex<f64>>, expected: &Array1<Complex<f64>>) -> f64 {
+        (&actual - expected)
+            .iter()
+            .map(Complex::norm_sqr)
+            .sum::<f64>()
+            .sqrt()
+    }
+
     pub(crate) fn get_initial_state(n_states: usize) -> Array1<Complex<f64>> {
         let mut state = Array1::zeros([n_states]);
         state[0] = Complex { im: 0f64, re: 1f64 };
 
         state
     }
+    #[test]
+    fn test_fill_complex_normal_matches_euler_increments() {
+        let n_states = 5;
+        let system = get_random_system(4, n_states);
+        let state = get_initial_state(n_states);
+        let dt = 1e-2f64;
+
+        let mut increments = vec![Complex::default(); 4];
+        fill_complex_normal(&mut increments, &mut StdRng::seed_from_u64(9));
+        for d in &mut increments {
+            *d *= dt.sqrt();
+        }
+
+        let expected = EulerSolver::step(&state, &system, 0f64, dt, &mut StdRng::seed_from_u64(9));
+        let actual = EulerSolver::step_with_increments(&state, &system, 0f64, dt, &increments);
+        assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_complex_normal_variance() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let n_samples = 100_000;
+        for distribution in [ComplexNormal::new(0.25), ComplexNormal::with_std(3f64)] {
+            let samples = (&mut rng)
+                .sample_iter::<Complex<f64>, _>(distribution)
+                .take(n_samples)
+                .collect::<Vec<_>>();
+            #[allow(clippy::cast_precision_loss)]
+            let (variance, real_variance) = (
+                samples.iter().map(Complex::norm_sqr).sum::<f64>() / n_samples as f64,
+                samples.iter().map(|s| s.re * s.re).sum::<f64>() / n_samples as f64,
+            );
+            // The standard error of the variance is sqrt(2 / n) * variance
+            assert!((variance - distribution.variance()).abs() < 0.02 * distribution.variance());
+            assert!(
+                (real_variance - 0.5 * distribution.variance()).abs()
+                    < 0.02 * distribution.variance()
+            );
+        }
+        assert_eq!(ComplexNormal::with_std(3f64), ComplexNormal::new(9f64));
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_ornstein_uhlenbeck_spectral_density() {
+        let (gamma, sigma, dt) = (1f64, 1.5, 0.01);
+        let (n_segments, segment_length) = (64, 2048);
+        let path = OrnsteinUhlenbeckPath::sample(
+            gamma,
+            sigma,
+            dt,
+            n_segments * segment_length,
+            &mut StdRng::seed_from_u64(2),
+        );
+        assert!((path.value(3.0 * dt + 1e-12) - path.values()[3]).abs() < f64::EPSILON);
+
+        // Average the periodogram dt / N |\sum_k x_k e^{-i w k dt}|^2 over segments,
+        // and over neighbouring frequencies w_m = 2 pi m / (N dt)
+        let periodogram = |m: usize| {
+            let w = 2f64 * std::f64::consts::PI * m as f64 / (segment_length as f64 * dt);
+            path.values()
+                .chunks(segment_length)
+                .map(|segment| {
+                    let transform = segment
+                        .iter()
+                        .enumerate()
+                        .map(|(k, x)| *x * Complex::from_polar(1f64, -w * k as f64 * dt))
+                        .sum::<Complex<f64>>();
+                    transform.norm_sqr() * dt / segment_length as f64
+                })
+                .sum::<f64>()
+                / n_segments as f64
+        };
+        for m in [2usize, 4, 8, 16] {
+            let estimate = (periodogram(m - 1) + periodogram(m) + periodogram(m + 1)) / 3f64;
+            let w = 2f64 * std::f64::consts::PI * m as f64 / (segment_length as f64 * dt);
+            let expected = sigma * sigma / (gamma * gamma + w * w);
+            assert!((estimate - expected).abs() < 0.25 * expected);
+        }
+    }
+
     #[test]
     fn test_initial_state_is_initial() {
         let n_states = 10;
@@ -116,61 +255,2760 @@ mod tests {
     }
 
     #[test]
-    fn test_banded_dot_product() {
-        let rng = rand::thread_rng();
-        let shape = [10, 100];
+    fn test_solve_with_seeded_rng_is_reproducible() {
+        let n_states = 4;
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        operators[[1, 2, 3]] = Complex { re: 0f64, im: 0.5 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::from_elem([n_states, n_states], Complex { re: 1f64, im: 0f64 }),
+        };
+        let initial_state = Array1::from_elem(n_states, Complex { re: 0.5, im: 0f64 });
 
-        let full = Array2::from_shape_vec(
-            shape,
-            rng.clone()
-                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
-                .take(shape[0] * shape[1])
-                .collect(),
-        )
-        .unwrap();
-        let banded = BandedArray::from_dense(&full);
+        let solve = |seed| {
+            EulerSolver::solve_with_rng(
+                &initial_state,
+                &system,
+                5,
+                10,
+                0.01,
+                &mut StdRng::seed_from_u64(seed),
+            )
+        };
+        assert_eq!(solve(42), solve(42));
+        assert_ne!(solve(42), solve(43));
+    }
 
-        let state = Array1::from_iter(
-            rng.clone()
-                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
-                .take(shape[1]),
+    #[test]
+    fn test_solve_observables_matches_solve() {
+        let n_states = 4;
+        let mut operators = Array3::zeros([1, n_states, n_states]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::from_elem([n_states, n_states], Complex { re: 1f64, im: 0f64 }),
+        };
+        let initial_state = Array1::from_elem(n_states, Complex { re: 0.5, im: 0f64 });
+        let observables = [
+            Array2::from_diag(&Array1::from_iter((0..n_states).map(|i| Complex {
+                #[allow(clippy::cast_precision_loss)]
+                re: i as f64,
+                im: 0f64,
+            }))),
+            operators.slice(s![0, .., ..]).to_owned(),
+        ];
+
+        let (n, step, dt) = (6, 10, 0.01);
+        let result = EulerSolver::solve_with_rng(
+            &initial_state,
+            &system,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(3),
+        );
+        let actual = EulerSolver::solve_observables_with_rng(
+            &initial_state,
+            &system,
+            &observables,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(3),
         );
 
-        let expected = full.dot(&state);
-        let actual = banded.dot(&state);
-        for i in 0..shape[0] {
-            assert!((expected[i] - actual[i]).abs() < 1e-8);
+        assert_eq!(actual.shape(), [n, observables.len()]);
+        for (state, row) in result.outer_iter().zip(actual.outer_iter()) {
+            for (observable, a) in observables.iter().zip(row.iter()) {
+                let expected = state.map(Complex::conj).dot(&observable.dot(&state));
+                assert!((expected - a).norm() < 1e-12);
+            }
         }
-        assert_eq!(expected.len(), actual.len());
     }
 
     #[test]
-    fn test_banded_transposed_dot_product() {
-        let rng = rand::thread_rng();
-        let shape = [100, 10];
+    fn test_solve_ensemble_decay() {
+        // Decay of |1> through L = |0><1|, for which the average population P_1 = e^(-t)
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[1] = Complex { re: 1f64, im: 0f64 };
+        let mut population = Array2::zeros([2, 2]);
+        population[[1, 1]] = Complex { re: 1f64, im: 0f64 };
 
-        let full = Array2::from_shape_vec(
-            [shape[1], shape[0]],
-            rng.clone()
-                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
-                .take(shape[0] * shape[1])
-                .collect(),
+        let (n, step, dt) = (5, 50, 5e-3);
+        let ensemble = EulerSolver::solve_ensemble(
+            &initial_state,
+            &system,
+            &[population],
+            200,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(11),
+        );
+        assert_eq!(ensemble.n_samples(), 200);
+        assert_eq!(ensemble.mean().shape(), [n, 1]);
+        let error = ensemble.standard_error();
+        for i in 0..n {
+            #[allow(clippy::cast_precision_loss)]
+            let expected = (-((i * step) as f64) * dt).exp();
+            // Allow for the O(dt) error of the euler scheme
+            assert!((ensemble.mean()[[i, 0]].re - expected).abs() < 4f64 * error[[i, 0]] + 1e-2);
+        }
+        assert!(error[[0, 0]].abs() < 1e-12);
+        assert!(error[[n - 1, 0]] > 0f64);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_par_solve_ensemble_is_independent_of_threads() {
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let initial_state = Array1::from_elem(
+            2,
+            Complex {
+                re: 0.5f64.sqrt(),
+                im: 0f64,
+            },
+        );
+        let observables = [operators.slice(s![0, .., ..]).to_owned()];
+
+        let solve = |n_threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(n_threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                EulerSolver::par_solve_ensemble(
+                    &initial_state,
+                    &system,
+                    &observables,
+                    100,
+                    4,
+                    10,
+                    0.01,
+                    &mut StdRng::seed_from_u64(5),
+                )
+            })
+        };
+        let single = solve(1);
+        let multiple = solve(4);
+        assert_eq!(single.mean(), multiple.mean());
+        assert_eq!(single.variance(), multiple.variance());
+
+        let sequential = EulerSolver::solve_ensemble(
+            &initial_state,
+            &system,
+            &observables,
+            100,
+            4,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(5),
+        );
+        assert_eq!(sequential.mean(), single.mean());
+    }
+
+    #[test]
+    fn test_antithetic_ensemble_reduces_variance() {
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex::from(0.5);
+        hamiltonian[[1, 0]] = Complex::from(0.5);
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian,
+        };
+        let initial_state = Array1::from_elem(2, Complex::from(0.5f64.sqrt()));
+        let mut population = Array2::zeros([2, 2]);
+        population[[1, 1]] = Complex::from(1f64);
+        let observables = [population];
+
+        let (n_pairs, n, step, dt) = (200, 3, 50, 5e-3);
+        // Compare at an equal number of trajectories
+        let plain = EulerSolver::solve_ensemble(
+            &initial_state,
+            &system,
+            &observables,
+            2 * n_pairs,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(3),
+        );
+        let antithetic = EulerSolver::solve_ensemble_antithetic(
+            &initial_state,
+            &system,
+            &observables,
+            n_pairs,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(3),
+        );
+        assert_eq!(antithetic.n_samples(), n_pairs);
+        let (plain_error, antithetic_error) = (plain.standard_error(), antithetic.standard_error());
+        assert!(antithetic_error[[n - 1, 0]] < 0.75 * plain_error[[n - 1, 0]]);
+        assert!(
+            (antithetic.mean()[[n - 1, 0]] - plain.mean()[[n - 1, 0]]).norm()
+                < 4f64 * plain_error[[n - 1, 0]]
+        );
+    }
+
+    #[test]
+    fn test_steady_state_of_driven_damped_qubit() {
+        // H = omega / 2 sigma_x and L = |0><1|, for which the excited population is
+        // omega^2 / (1 + 2 omega^2), and |<0|rho|1>| = omega / (1 + 2 omega^2)
+        let omega = 1f64;
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex::from(0.5 * omega);
+        hamiltonian[[1, 0]] = Complex::from(0.5 * omega);
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian,
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[0] = Complex::from(1f64);
+
+        let (rho, n_samples) = solve_steady_state::<EulerSolver, _, _>(
+            &initial_state,
+            &system,
+            5e-3,
+            &mut StdRng::seed_from_u64(5),
+            3e-4,
+        );
+        assert!(n_samples > 0);
+        assert!((rho[[0, 0]] + rho[[1, 1]] - 1f64).norm() < 1e-10);
+        let denominator = 1f64 + 2f64 * omega * omega;
+        assert!((rho[[1, 1]].re - omega * omega / denominator).abs() < 0.03);
+        assert!((rho[[0, 1]].norm() - omega / denominator).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_lindblad_steady_state_of_driven_damped_qubit() {
+        let omega = 1f64;
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex::from(0.5 * omega);
+        hamiltonian[[1, 0]] = Complex::from(0.5 * omega);
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian,
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[0] = Complex::from(1f64);
+        let mut initial_rho = Array2::zeros([2, 2]);
+        initial_rho[[0, 0]] = Complex::from(1f64);
+
+        let trajectory = LindbladSolver::solve(&initial_rho, &system, 100, 100, 5e-3);
+        for rho in trajectory.outer_iter() {
+            assert!((rho[[0, 0]] + rho[[1, 1]] - 1f64).norm() < 1e-10);
+            assert!((rho[[0, 1]] - rho[[1, 0]].conj()).norm() < 1e-10);
+        }
+        let lindblad = trajectory.index_axis(ndarray::Axis(0), 99);
+        let denominator = 1f64 + 2f64 * omega * omega;
+        assert!((lindblad[[1, 1]].re - omega * omega / denominator).abs() < 1e-6);
+        assert!((lindblad[[0, 1]].norm() - omega / denominator).abs() < 1e-6);
+
+        let (sse, _) = solve_steady_state::<EulerSolver, _, _>(
+            &initial_state,
+            &system,
+            5e-3,
+            &mut StdRng::seed_from_u64(5),
+            3e-4,
+        );
+        assert!((&sse - &lindblad).iter().all(|d| d.norm() < 0.03));
+    }
+
+    #[test]
+    fn test_measurement_record_mean_matches_lindblad() {
+        // H = 1 / 2 sigma_y and L = |0><1|, for which <L + L^dagger> = 2 Re(rho_10) is nonzero
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex { re: 0f64, im: -0.5 };
+        hamiltonian[[1, 0]] = Complex { re: 0f64, im: 0.5 };
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian,
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[0] = Complex::from(1f64);
+        let mut initial_rho = Array2::zeros([2, 2]);
+        initial_rho[[0, 0]] = Complex::from(1f64);
+
+        let (n, step, dt) = (5, 500, 2e-3);
+        let n_trajectories = 200;
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut mean = Array2::<f64>::zeros([n, 1]);
+        let mut mean_sqr = Array2::<f64>::zeros([n, 1]);
+        for _ in 0..n_trajectories {
+            let (states, record) = EulerSolver::solve_with_measurement_record(
+                &initial_state,
+                &system,
+                n,
+                step,
+                dt,
+                &mut rng,
+            );
+            assert_eq!(states.dim(), (n, 2));
+            mean += &record;
+            mean_sqr += &record.mapv(|q| q * q);
+        }
+        mean /= f64::from(n_trajectories);
+        mean_sqr /= f64::from(n_trajectories);
+
+        // Integrate <L + L^dagger> = 2 Re(rho_10) with the trapezoidal rule
+        let rho = LindbladSolver::solve(&initial_rho, &system, n * step + 1, 1, dt);
+        let mut expected = 0f64;
+        for (i, window) in rho.outer_iter().collect::<Vec<_>>().windows(2).enumerate() {
+            if i % step == 0 {
+                let (actual, variance) = (mean[[i / step, 0]], mean_sqr[[i / step, 0]]);
+                let standard_error =
+                    ((variance - actual * actual) / f64::from(n_trajectories)).sqrt();
+                assert!((actual - expected).abs() < 4f64 * standard_error + 1e-2);
+            }
+            expected += dt * (window[0][[1, 0]].re + window[1][[1, 0]].re);
+        }
+        assert!(expected.abs() > 1f64);
+    }
+
+    #[test]
+    fn test_measurement_record_noise_has_variance_dt() {
+        // For L = gamma I every state is an eigenstate of L, so the state is not changed
+        // and each increment of the record is 2 gamma step dt plus noise of variance step dt
+        let gamma = 0.5;
+        let operators = Array3::from_shape_fn([1, 2, 2], |(_, i, j)| {
+            Complex::from(if i == j { gamma } else { 0f64 })
+        });
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+
+        let (n, step, dt) = (11, 10, 1e-2);
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut increments = Vec::new();
+        for _ in 0..500 {
+            let (_, record) = EulerSolver::solve_with_measurement_record(
+                &initial_state,
+                &system,
+                n,
+                step,
+                dt,
+                &mut rng,
+            );
+            increments.extend(record.column(0).windows(2).into_iter().map(|w| w[1] - w[0]));
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let n_samples = increments.len() as f64;
+        let mean = increments.iter().sum::<f64>() / n_samples;
+        let variance = increments.iter().map(|q| (q - mean).powi(2)).sum::<f64>() / n_samples;
+
+        let block = f64::from(u32::try_from(step).unwrap()) * dt;
+        assert!((mean - 2f64 * gamma * block).abs() < 0.01);
+        assert!((variance / block - 1f64).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_renorm_every_reduces_bias() {
+        // The driven damped qubit of test_steady_state_of_driven_damped_qubit,
+        // with an excited population of 1 / 3 in the steady state
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex::from(0.5);
+        hamiltonian[[1, 0]] = Complex::from(0.5);
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian,
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[0] = Complex::from(1f64);
+
+        // Without renormalization the norm of the euler integration drifts,
+        // which biases the nonlinear terms of the SSE
+        let (n, step, dt) = (5000, 10, 0.02);
+        let bias = |renorm_every| {
+            let states = EulerSolver::solve_with_options(
+                &initial_state,
+                &system,
+                n,
+                step,
+                dt,
+                &SolveOptions {
+                    renormalize: false,
+                    renorm_every,
+                },
+                &mut StdRng::seed_from_u64(1),
+            );
+            let population = states
+                .outer_iter()
+                .skip(n / 10)
+                .map(|s| s[1].norm_sqr() / s.norm_l2().powi(2))
+                .collect::<Array1<f64>>()
+                .mean()
+                .unwrap();
+            (population - 1f64 / 3f64).abs()
+        };
+        let unnormalized = bias(0);
+        assert!(unnormalized > 0.01);
+        assert!(bias(100) < 0.5 * unnormalized);
+        assert!(bias(1) < 0.5 * unnormalized);
+    }
+
+    #[test]
+    fn test_cloned_system_is_independent() {
+        let mut operators = Array3::zeros([1, 3, 3]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        operators[[0, 1, 2]] = Complex::from(1f64);
+        let system = SSESystem {
+            noise: FullNoise::from_csr(&[CsrArray::from_dense(
+                &operators.index_axis(Axis(0), 0).to_owned(),
+            )]),
+            hamiltonian: Array2::from_diag(&Array1::from_elem([3], Complex::from(1f64))),
+        };
+        let mut initial_state = Array1::zeros([3]);
+        initial_state[2] = Complex::from(1f64);
+        let solve = |system: &SSESystem<_, _>| {
+            EulerSolver::solve_with_rng(
+                &initial_state,
+                system,
+                10,
+                10,
+                0.01,
+                &mut StdRng::seed_from_u64(1),
+            )
+        };
+        let expected = solve(&system);
+
+        let mut sweep = system.clone();
+        assert!(sweep == system);
+        sweep.hamiltonian[[0, 0]] = Complex::from(2f64);
+        assert!(sweep != system);
+        let swept = solve(&sweep);
+        assert!((&swept - &expected).iter().any(|d| d.norm() > 1e-6));
+
+        let actual = solve(&system);
+        assert!((&actual - &expected).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_two_time_correlation_of_damped_oscillator() {
+        // H = omega a^dagger a and L = sqrt(gamma) a, starting in |1>, for which
+        // <a^dagger(tau) a(0)> = e^{(i omega - gamma / 2) tau}
+        let (omega, gamma, n_states) = (2f64, 1f64, 3);
+        let mut annihilation = Array2::zeros([n_states, n_states]);
+        for i in 1..n_states {
+            annihilation[[i - 1, i]] = Complex::from(f64::from(u32::try_from(i).unwrap()).sqrt());
+        }
+        let creation = annihilation.t().map(Complex::conj);
+        let system = SSESystem {
+            hamiltonian: creation.dot(&annihilation) * Complex::from(omega),
+            noise: FullNoise::from_operators(
+                &(&annihilation * Complex::from(gamma.sqrt())).insert_axis(Axis(0)),
+            ),
+        };
+        let mut state = Array1::zeros([n_states]);
+        state[1] = Complex::from(1f64);
+
+        let (n, step, dt) = (11, 100, 2e-3);
+        let correlation = two_time_correlation::<EulerSolver, _, _, _, _>(
+            &system,
+            &state,
+            &creation,
+            &annihilation,
+            200,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(2),
+        );
+        for (k, actual) in correlation.iter().enumerate() {
+            let tau = f64::from(u32::try_from(k * step).unwrap()) * dt;
+            let expected = (Complex {
+                re: -0.5 * gamma,
+                im: omega,
+            } * tau)
+                .exp();
+            assert!((actual - expected).norm() < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_try_solve_zero_norm() {
+        // The non-hermitian H = -i / dt maps every state to zero in a single euler step
+        let dt = 0.01;
+        let system = SSESystem {
+            hamiltonian: Array2::from_diag(&Array1::from_elem(
+                [2],
+                Complex {
+                    re: 0f64,
+                    im: -1f64 / dt,
+                },
+            )),
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+        let options = SolveOptions {
+            renormalize: true,
+            ..SolveOptions::default()
+        };
+        let solve = |initial_state: &Array1<Complex<f64>>, options: &SolveOptions| {
+            EulerSolver::try_solve(
+                initial_state,
+                &system,
+                3,
+                5,
+                dt,
+                options,
+                &mut StdRng::seed_from_u64(1),
+            )
+        };
+
+        assert_eq!(
+            solve(&initial_state, &options),
+            Err(SolverError::ZeroNorm { step: 5 })
+        );
+        let every_step = SolveOptions {
+            renorm_every: 1,
+            ..options
+        };
+        assert_eq!(
+            solve(&initial_state, &every_step),
+            Err(SolverError::ZeroNorm { step: 1 })
+        );
+        assert_eq!(
+            solve(&Array1::zeros([2]), &SolveOptions::default()),
+            Err(SolverError::ZeroNorm { step: 0 })
+        );
+
+        // Without renormalization the zero state is returned
+        let states = solve(&initial_state, &SolveOptions::default()).unwrap();
+        assert!(states.row(2).iter().all(|s| s.norm() == 0f64));
+    }
+
+    #[test]
+    fn test_near_zero_norm_is_not_renormalized() {
+        // H = -i (1 - 1e-14) / dt leaves a state of norm 1e-14 after a single euler step
+        let dt = 0.01;
+        let system = SSESystem {
+            hamiltonian: Array2::from_diag(&Array1::from_elem(
+                [2],
+                Complex {
+                    re: 0f64,
+                    im: -(1f64 - 1e-14) / dt,
+                },
+            )),
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+        let result = EulerSolver::try_solve(
+            &initial_state,
+            &system,
+            3,
+            1,
+            dt,
+            &SolveOptions {
+                renormalize: true,
+                ..SolveOptions::default()
+            },
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert_eq!(result, Err(SolverError::ZeroNorm { step: 1 }));
+
+        // A single rk4 step of the no-jump evolution removes the excited state, when
+        // -i H_eff dt = z is a root of the rk4 polynomial 1 + z + z^2 / 2 + z^3 / 6 + z^4 / 24
+        let (dt, epsilon) = (0.1, 1e-3);
+        let root = Complex {
+            re: -1.729_444_231_067_705_6,
+            im: 0.888_974_376_121_865_7,
+        };
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[1, 1]] = Complex::<f64>::i() * (root + 0.5 * epsilon * epsilon * dt) / dt;
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(epsilon);
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&operators),
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[1] = Complex::from(1f64);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..10 {
+            let out = JumpSolver::step(&initial_state, &system, 0f64, dt, &mut rng);
+            assert!((out[0].norm() - 1f64).abs() < 1e-10);
+        }
+        let (states, record) =
+            JumpSolver::solve_with_jumps(&initial_state, &system, 2, 1, dt, &mut rng);
+        assert!(states.iter().all(|s| s.is_finite()));
+        assert_eq!(record.channels, vec![0]);
+        assert!((states[[1, 0]].norm() - 1f64).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_boxed_noise_mixes_representations() {
+        let n_states = 4;
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        operators
+            .index_axis_mut(Axis(0), 0)
+            .mapv_inplace(|_: Complex<f64>| {
+                rng.sample::<Complex<f64>, _>(StandardComplexNormal) * 0.2
+            });
+        for i in 1..n_states {
+            operators[[1, i - 1, i]] = Complex::from(f64::from(u32::try_from(i).unwrap()).sqrt());
+        }
+        let hamiltonian = Array2::from_diag(&Array1::from_elem([n_states], Complex::from(0.5)));
+
+        let dense = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let boxed = SSESystem {
+            hamiltonian,
+            noise: FullNoise::try_from_boxed(vec![
+                BoxedTensor::with_adjoint(operators.index_axis(Axis(0), 0).to_owned()),
+                BoxedTensor::with_adjoint(BandedArray::from_dense(
+                    &operators.index_axis(Axis(0), 1).to_owned(),
+                )),
+            ])
+            .unwrap(),
+        };
+        let initial_state = Array1::from_elem([n_states], Complex::from(0.5));
+
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &dense,
+            10,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(1),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &boxed,
+            10,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert!((&actual - &expected).iter().all(|d| d.norm() < 1e-10));
+    }
+
+    #[test]
+    fn test_scaled_noise_closed_system_limit() {
+        let n_states = 4;
+        let system = get_random_system(2, n_states);
+        let hamiltonian = &system.hamiltonian + &system.hamiltonian.adjoint();
+        let initial_state = Array1::from_elem([n_states], Complex::from(0.5));
+        let solve = |noise| {
+            let system = SSESystem {
+                hamiltonian: hamiltonian.clone(),
+                noise,
+            };
+            EulerSolver::solve_with_rng(
+                &initial_state,
+                &system,
+                10,
+                10,
+                1e-3,
+                &mut StdRng::seed_from_u64(1),
+            )
+        };
+        let closed = EulerSolver::solve_with_rng(
+            &initial_state,
+            &SSESystem {
+                hamiltonian: hamiltonian.clone(),
+                noise: FullNoise::from_operators(&Array3::zeros([0, n_states, n_states])),
+            },
+            10,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(1),
+        );
+
+        let zero = solve(system.noise.clone().scaled(0f64));
+        assert!((&zero - &closed).iter().all(|d| d.norm() == 0f64));
+        let weak = solve(system.noise.clone().scaled(1e-8));
+        assert!((&weak - &closed).iter().all(|d| d.norm() < 1e-3));
+        let strong = solve(system.noise.clone().scaled(1f64));
+        assert!((&strong - &closed).iter().any(|d| d.norm() > 1e-2));
+        let unscaled = EulerSolver::solve_with_rng(
+            &initial_state,
+            &SSESystem {
+                hamiltonian,
+                noise: system.noise,
+            },
+            10,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert!((&unscaled - &strong).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_integrate_until_population_threshold() {
+        // Starting in |1>, H = 1 / 2 sigma_x gives a ground state population sin^2(t / 2),
+        // which first crosses 0.5 at t = pi / 2
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex::from(0.5);
+        hamiltonian[[1, 0]] = Complex::from(0.5);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+            hamiltonian,
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[1] = Complex::from(1f64);
+        let mut ground = Array2::zeros([2, 2]);
+        ground[[0, 0]] = Complex::from(1f64);
+
+        let dt = 1e-3;
+        let (state, t) = HeunSolver::integrate_until(
+            &initial_state,
+            &system,
+            1f64,
+            dt,
+            &ground,
+            |p| p > 0.5,
+            10000,
+        );
+        assert!((t - 1f64 - std::f64::consts::FRAC_PI_2).abs() < 2f64 * dt);
+        assert!(state[0].norm_sqr() / state.norm_l2().powi(2) > 0.5);
+
+        let (_, t) = HeunSolver::integrate_until(
+            &initial_state,
+            &system,
+            0f64,
+            dt,
+            &ground,
+            |p| p > 2f64,
+            100,
+        );
+        assert!((t - 100f64 * dt).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solve_at_times_matches_integrate() {
+        let n_states = 4;
+        let system = get_random_system(2, n_states);
+        let initial_state = get_initial_state(n_states);
+        let (t_start, dt) = (0.5, 1e-3);
+        let times = [0.5, 0.501, 0.51, 0.6, 1.5];
+
+        let result = EulerSolver::solve_at_times_with_rng(
+            &initial_state,
+            &system,
+            t_start,
+            &times,
+            dt,
+            &mut StdRng::seed_from_u64(4),
         )
         .unwrap();
-        let banded = BandedArray::from_dense(&full);
+        assert_eq!(result.shape(), [times.len(), n_states]);
+        for (t, row) in times.iter().zip(result.outer_iter()) {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let n_step = ((t - t_start) / dt).round() as usize;
+            let mut current_t = t_start;
+            let expected = EulerSolver::integrate(
+                &initial_state,
+                &system,
+                &mut current_t,
+                n_step,
+                dt,
+                &mut StdRng::seed_from_u64(4),
+            );
+            assert!((&expected - &row).iter().all(|d| d.norm() < 1e-12));
+        }
 
-        let state = Array1::from_iter(
-            rng.clone()
-                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
-                .take(shape[1]),
+        let unsorted = EulerSolver::solve_at_times(&initial_state, &system, 0f64, &[0.2, 0.1], dt);
+        assert!(matches!(
+            unsorted,
+            Err(SolverError::InvalidConfiguration(_))
+        ));
+        let early = EulerSolver::solve_at_times(&initial_state, &system, 1f64, &[0.5], dt);
+        assert!(matches!(early, Err(SolverError::InvalidConfiguration(_))));
+    }
+
+    fn solve_at_times_is_invalid(times: &[f64], dt: f64) -> bool {
+        let n_states = 4;
+        matches!(
+            EulerSolver::solve_at_times(
+                &get_initial_state(n_states),
+                &get_random_system(2, n_states),
+                0f64,
+                times,
+                dt,
+            ),
+            Err(SolverError::InvalidConfiguration(_))
+        )
+    }
+
+    #[test]
+    fn test_solve_at_times_rejects_non_positive_dt() {
+        // A zero dt would never reach the first time, and a negative dt maps every time to the start
+        assert!(solve_at_times_is_invalid(&[0.1, 0.2], 0f64));
+        assert!(solve_at_times_is_invalid(&[0.1, 0.2], -1e-3));
+    }
+
+    #[test]
+    fn test_solve_at_times_rejects_non_finite_dt() {
+        assert!(solve_at_times_is_invalid(&[0.1, 0.2], f64::NAN));
+        assert!(solve_at_times_is_invalid(&[0.1, 0.2], f64::INFINITY));
+    }
+
+    #[test]
+    fn test_solve_at_times_rejects_non_finite_times() {
+        // A NaN time passes both the sort and the t_start comparison
+        assert!(solve_at_times_is_invalid(&[f64::NAN, 0.2], 1e-3));
+        assert!(solve_at_times_is_invalid(&[0.1, f64::NAN], 1e-3));
+        assert!(solve_at_times_is_invalid(&[0.1, f64::INFINITY], 1e-3));
+    }
+
+    #[test]
+    fn test_suggest_dt_for_stiff_system() {
+        // The amplification of the heun step is |1 - i E dt - (E dt)^2 / 2|^2 = 1 + (E dt)^4 / 4,
+        // which is close to 1 only if dt is small compared to the largest energy
+        let energies = Array1::from(vec![1f64, 1000f64]);
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+            hamiltonian: Array2::from_diag(&energies.map(|e| Complex::from(*e))),
+        };
+        let dt = system.suggest_dt(0.1);
+        assert!((dt - 1e-4).abs() < 1e-6);
+
+        let initial_state = Array1::from_elem([2], Complex::from(std::f64::consts::FRAC_1_SQRT_2));
+        let final_norm = |dt: f64| {
+            HeunSolver::solve(&initial_state, &system, 2, 1000, dt)
+                .row(1)
+                .iter()
+                .map(Complex::norm_sqr)
+                .sum::<f64>()
+        };
+        assert!(final_norm(dt) < 1.1);
+        assert!(final_norm(10f64 * dt) > 1e6);
+    }
+
+    #[test]
+    fn test_solve_ensemble_trajectory_reproduces_contribution() {
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+        let observables = [operators.slice(s![0, .., ..]).to_owned()];
+        let (n_trajectories, n, step, dt) = (50, 4, 10, 0.01);
+
+        let full = EulerSolver::solve_ensemble(
+            &initial_state,
+            &system,
+            &observables,
+            n_trajectories,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(4),
         );
+        let master_seed = StdRng::seed_from_u64(4).gen::<u64>();
+        let trajectory = |index| {
+            EulerSolver::solve_ensemble_trajectory(
+                &initial_state,
+                &system,
+                &observables,
+                master_seed,
+                index,
+                n,
+                step,
+                dt,
+            )
+        };
 
-        let expected = full.reversed_axes().dot(&state);
-        let actual = banded.transpose().dot(&state);
+        // The ensemble is the mean of each trajectory solved alone
+        let mut expected = Array2::<Complex<f64>>::zeros([n, 1]);
+        for index in 0..n_trajectories {
+            expected += &trajectory(index);
+        }
+        expected /= Complex::from(50f64);
+        assert!((full.mean() - &expected).iter().all(|d| d.norm() < 1e-12));
+        assert!((trajectory(41) - &trajectory(42))
+            .iter()
+            .any(|d| d.norm() > 1e-6));
+    }
 
-        for i in 0..shape[0] {
-            assert!((expected[i] - actual[i]).abs() < 1e-8);
+    #[test]
+    fn test_solve_ensemble_range_merge() {
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let initial_state = Array1::from_elem(
+            2,
+            Complex {
+                re: 0.5f64.sqrt(),
+                im: 0f64,
+            },
+        );
+        let observables = [operators.slice(s![0, .., ..]).to_owned()];
+
+        let solve = |range| {
+            EulerSolver::solve_ensemble_range(
+                &initial_state,
+                &system,
+                &observables,
+                range,
+                17,
+                4,
+                10,
+                0.01,
+            )
+        };
+        let full = solve(0..100);
+        let mut merged = solve(0..50);
+        merged.merge(&solve(50..100));
+        assert_eq!(merged.n_samples(), 100);
+        for (a, e) in merged.mean().iter().zip(full.mean()) {
+            assert!((a - e).norm() < 1e-12);
         }
-        assert_eq!(expected.len(), actual.len());
+        for (a, e) in merged.variance().iter().zip(full.variance().iter()) {
+            assert!((a - e).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_trajectory_rng_reproduces_ensemble_member() {
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+        let observable = operators.slice(s![0, .., ..]).to_owned();
+        let (n, step, dt) = (4, 10, 0.01);
+        let master_seed = StdRng::seed_from_u64(4).gen::<u64>();
+
+        // Trajectory 42 solved alone from its substream
+        let states = EulerSolver::solve_with_rng(
+            &initial_state,
+            &system,
+            n,
+            step,
+            dt,
+            &mut trajectory_rng(master_seed, 42),
+        );
+        let alone = EulerSolver::solve_ensemble_range(
+            &initial_state,
+            &system,
+            std::slice::from_ref(&observable),
+            42..43,
+            master_seed,
+            n,
+            step,
+            dt,
+        );
+        for (state, expected) in states.outer_iter().zip(alone.mean().iter()) {
+            assert!((observable.expectation(&state.to_owned()) - expected).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_solve_with_options_renormalize() {
+        // A purely dissipative system, H = -i/2 |1><1|
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[1, 1]] = Complex { re: 0f64, im: -0.5 };
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+        let solve = |renormalize| {
+            EulerSolver::solve_with_options(
+                &initial_state,
+                &system,
+                10,
+                10,
+                0.01,
+                &SolveOptions {
+                    renormalize,
+                    ..SolveOptions::default()
+                },
+                &mut rand::thread_rng(),
+            )
+        };
+
+        let norms = solve(false).map_axis(Axis(1), |s| s.norm_l2());
+        for pair in norms.windows(2) {
+            assert!(pair[1] < pair[0]);
+        }
+        assert!(norms[norms.len() - 1] < 1f64);
+        assert_eq!(
+            solve(false),
+            EulerSolver::solve(&initial_state, &system, 10, 10, 0.01)
+        );
+
+        let renormalized = solve(true);
+        for state in renormalized.outer_iter() {
+            assert!((state.norm_l2() - 1f64).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_solve_from_continues_time_dependent_run() {
+        let hamiltonian = OperatorFnHamiltonian(|t: f64| {
+            let coupling = Complex::from_polar(0.5, -3f64 * t);
+            Array2::from_shape_vec(
+                [2, 2],
+                vec![
+                    Complex::from(1.5),
+                    coupling,
+                    coupling.conj(),
+                    Complex::from(-1.5),
+                ],
+            )
+            .unwrap()
+        });
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(0.3);
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&operators),
+        };
+        let initial_state = Array1::from_vec(vec![Complex::from(1f64), Complex::default()]);
+
+        let dt = 0.01;
+        let mut current_t = 0f64;
+        let mut rng = StdRng::seed_from_u64(1);
+        let middle =
+            EulerSolver::integrate(&initial_state, &system, &mut current_t, 500, dt, &mut rng);
+        let continued = EulerSolver::integrate(
+            &middle,
+            &system,
+            &mut current_t,
+            100,
+            dt,
+            &mut StdRng::seed_from_u64(2),
+        );
+
+        let from_start = EulerSolver::solve_from_with_rng(
+            &middle,
+            &system,
+            5f64,
+            2,
+            100,
+            dt,
+            &mut StdRng::seed_from_u64(2),
+        );
+        assert!((&from_start.row(1) - &continued)
+            .iter()
+            .all(|d| d.norm() < 1e-8));
+
+        let from_zero = EulerSolver::solve_with_rng(
+            &middle,
+            &system,
+            2,
+            100,
+            dt,
+            &mut StdRng::seed_from_u64(2),
+        );
+        assert!((&from_zero.row(1) - &continued)
+            .iter()
+            .any(|d| d.norm() > 1e-4));
+    }
+
+    #[test]
+    fn test_solve_output_times() {
+        // H(t) = t sigma_z, so the phase of the upper state is -(t^2 - t_start^2) / 2
+        let hamiltonian = OperatorFnHamiltonian(|t: f64| {
+            Array2::from_diag(&Array1::from_vec(vec![Complex::from(t), Complex::from(-t)]))
+        });
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_vec(vec![Complex::from(0.6), Complex::from(0.8)]);
+
+        let (t_start, step, dt) = (1f64, 100, 1e-4);
+        for n in [0, 1, 5] {
+            let result =
+                NormalizedEulerSolver::solve_from(&initial_state, &system, t_start, n, step, dt);
+            assert_eq!(result.nrows(), n);
+            if n > 0 {
+                assert_eq!(result.row(0), initial_state);
+            }
+            for (k, state) in result.outer_iter().enumerate() {
+                #[allow(clippy::cast_precision_loss)]
+                let t = t_start + (k * step) as f64 * dt;
+                let expected = -0.5 * (t * t - t_start * t_start);
+                assert!((state[0].arg() - expected).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_with_callback() {
+        let system = SSESystem {
+            hamiltonian: Array2::<Complex<f64>>::eye(2),
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_vec(vec![Complex::from(0.6), Complex::from(0.8)]);
+
+        let (step, dt) = (10, 0.01);
+        let mut times = Vec::new();
+        let last =
+            EulerSolver::solve_with_callback(&initial_state, &system, 5, step, dt, |i, t, _| {
+                assert_eq!(i, times.len());
+                times.push(t);
+                ControlFlow::Continue(())
+            });
+        assert_eq!(times.len(), 5);
+        for (k, t) in times.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let expected = (k * step) as f64 * dt;
+            assert!((t - expected).abs() < 1e-12);
+        }
+        let expected = EulerSolver::solve(&initial_state, &system, 5, step, dt);
+        assert!((&last - &expected.row(4)).iter().all(|d| d.norm() < 1e-12));
+
+        let mut n_calls = 0;
+        let last =
+            EulerSolver::solve_with_callback(&initial_state, &system, 5, step, dt, |i, _, _| {
+                n_calls += 1;
+                if i == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+        assert_eq!(n_calls, 3);
+        assert!((&last - &expected.row(2)).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_solve_resumable_matches_uninterrupted() {
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(0.5);
+        let system = SSESystem {
+            hamiltonian: Array2::<Complex<f64>>::eye(2),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let initial = SolverState {
+            state: Array1::from_vec(vec![Complex::from(0.6), Complex::from(0.8)]),
+            t: 0f64,
+            rng: ChaCha12Rng::seed_from_u64(1),
+        };
+
+        let mut uninterrupted = Vec::new();
+        EulerSolver::solve_resumable(initial.clone(), &system, 10, 10, 0.01, |c| {
+            uninterrupted.push(c.state.clone());
+            ControlFlow::Continue(())
+        });
+
+        let mut resumed = Vec::new();
+        let interrupted = EulerSolver::solve_resumable(initial, &system, 10, 10, 0.01, |c| {
+            resumed.push(c.state.clone());
+            if resumed.len() == 4 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        #[cfg(feature = "serde")]
+        let interrupted: SolverState =
+            serde_json::from_str(&serde_json::to_string(&interrupted).unwrap()).unwrap();
+        EulerSolver::solve_resumable(interrupted, &system, 6, 10, 0.01, |c| {
+            resumed.push(c.state.clone());
+            ControlFlow::Continue(())
+        });
+
+        assert_eq!(resumed.len(), 10);
+        for (a, b) in uninterrupted.iter().zip(&resumed) {
+            assert!((a - b).iter().all(|d| d.norm() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn test_implicit_euler_stiff_system() {
+        // A strongly damped upper state, with decay rate 1000
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 0]] = Complex::from(1f64);
+        hamiltonian[[1, 1]] = Complex {
+            re: 0f64,
+            im: -500f64,
+        };
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        assert_eq!(system.drift_operator()[[1, 1]], Complex::from(-500f64));
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+
+        let (n, step, dt) = (10, 10, 0.01);
+        let explicit = EulerSolver::solve(&initial_state, &system, n, step, dt);
+        let explicit_norm = explicit.row(n - 1).norm_l2();
+        assert!(explicit_norm.is_nan() || explicit_norm > 1e10);
+
+        let implicit = ImplicitEulerSolver::solve(&initial_state, &system, n, step, dt);
+        for state in implicit.outer_iter() {
+            assert!(state.norm_l2() <= 1f64 + 1e-12);
+        }
+        assert!(implicit[[n - 1, 1]].norm() < 1e-12);
+        assert!((implicit[[n - 1, 0]].norm() - 0.5f64.sqrt()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_implicit_euler_matches_euler() {
+        let hamiltonian = get_random_system(0, 5).hamiltonian;
+        let mut noise = Array3::zeros([2, 5, 5]);
+        for i in 1..5 {
+            noise[[0, i - 1, i]] = Complex::from(0.5);
+            noise[[1, i, i]] = Complex::from(0.2);
+        }
+        let system = SSESystem {
+            hamiltonian: &hamiltonian + &hamiltonian.adjoint(),
+            noise: FullNoise::from_operators(&noise),
+        };
+        let initial_state = get_initial_state(5);
+
+        let (n, step, dt) = (5, 100, 1e-5);
+        let explicit = EulerSolver::solve_with_rng(
+            &initial_state,
+            &system,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(3),
+        );
+        let implicit = ImplicitEulerSolver::solve_with_rng(
+            &initial_state,
+            &system,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(3),
+        );
+        assert!((&explicit - &implicit).iter().all(|d| d.norm() < 1e-3));
+    }
+
+    #[test]
+    fn test_prepared_implicit_euler() {
+        let mut noise = Array3::zeros([1, 3, 3]);
+        noise[[0, 0, 1]] = Complex::from(2f64);
+        noise[[0, 1, 2]] = Complex::from(1f64);
+        let system = SSESystem {
+            hamiltonian: Array2::from_diag(&Array1::from_elem([3], Complex::from(1f64))),
+            noise: FullNoise::from_operators(&noise),
+        };
+        let initial_state = get_initial_state(3);
+
+        // The prepared solver takes the same steps as the solver
+        let (n_step, dt) = (20, 1e-2);
+        let prepared = ImplicitEulerSolver::prepare(&system, dt).unwrap();
+        let mut current_t = 0f64;
+        let expected = ImplicitEulerSolver::integrate(
+            &initial_state,
+            &system,
+            &mut current_t,
+            n_step,
+            dt,
+            &mut StdRng::seed_from_u64(1),
+        );
+        let mut prepared_t = 0f64;
+        let actual = prepared.integrate(
+            &initial_state,
+            &system,
+            &mut prepared_t,
+            n_step,
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert_eq!(actual, expected);
+        assert!((prepared_t - current_t).abs() < 1e-12);
+
+        // For H = 10i |0><0| the drift has an eigenvalue 1 / dt, so I - dt D is singular
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 0]] = Complex {
+            re: 0f64,
+            im: 10f64,
+        };
+        let singular = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        assert!(matches!(
+            ImplicitEulerSolver::prepare(&singular, 0.1),
+            Err(SolverError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_step_in_place_matches_step() {
+        let system = get_random_system(3, 10);
+        let initial_state = get_initial_state(10);
+        let mut workspace = StepWorkspace::default();
+
+        let (t, dt) = (0f64, 1e-4);
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut expected = initial_state.clone();
+        let mut actual = initial_state;
+        for _ in 0..10 {
+            expected = EulerSolver::step(&expected, &system, t, dt, &mut rng.clone());
+            EulerSolver::step_in_place(&mut actual, &system, t, dt, &mut rng, &mut workspace);
+        }
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_solve_batch_matches_separate_solves() {
+        let n_states = 6;
+        let system = get_random_system(2, n_states);
+        let mut initial_states = Array2::zeros([n_states, 2]);
+        initial_states[[0, 0]] = Complex { re: 1f64, im: 0f64 };
+        initial_states[[3, 1]] = Complex { re: 0f64, im: 1f64 };
+
+        let (n, step, dt) = (4, 10, 1e-3);
+        let batch = EulerSolver::solve_batch(
+            &initial_states,
+            &system,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(5),
+        );
+        assert_eq!(batch.shape(), [n, n_states, 2]);
+        for (column, initial_state) in initial_states.columns().into_iter().enumerate() {
+            let expected = EulerSolver::solve_with_rng(
+                &initial_state.to_owned(),
+                &system,
+                n,
+                step,
+                dt,
+                &mut StdRng::seed_from_u64(5),
+            );
+            let actual = batch.slice(s![.., .., column]);
+            assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-10));
+        }
+    }
+
+    #[test]
+    fn test_drift_operator_matches_step_without_noise() {
+        let n_states = 5;
+        let hamiltonian = get_random_system(0, n_states).hamiltonian;
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex { re: 0.5, im: 0.1 };
+            operators[[1, i - 1, i]] = Complex { re: 0f64, im: 0.3 };
+        }
+        let system = SSESystem {
+            hamiltonian: &hamiltonian + &hamiltonian.adjoint(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        // The lowering operators have <L> = 0 for a single basis state,
+        // so the non-linear terms of the drift vanish
+        let mut state = Array1::zeros([n_states]);
+        state[2] = Complex { re: 1f64, im: 0f64 };
+
+        let dt = 1e-3;
+        let step = SDEStep {
+            coherent: Complex { re: dt, im: 0f64 },
+            incoherent: vec![Complex::default(); 2],
+        };
+        let expected = system.get_step(&step, &state, 0f64);
+        let actual = system.drift_operator().dot(&state) * Complex { re: dt, im: 0f64 };
+        assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_drift_eigenvalues_of_diagonal_system() {
+        // H = diag(0, 1, 2) and L_k = sqrt(gamma_k) |k - 1><k|, for which the drift is
+        // diagonal with eigenvalues -i omega_k - gamma_k / 2
+        let omegas = [0f64, 1f64, 2f64];
+        let gammas = [0f64, 2f64, 4f64];
+        let mut operators = Array3::zeros([2, 3, 3]);
+        for k in 1..3 {
+            operators[[k - 1, k - 1, k]] = Complex::from(gammas[k].sqrt());
+        }
+        let system = SSESystem {
+            hamiltonian: Array2::from_diag(
+                &omegas
+                    .iter()
+                    .map(|w| Complex::from(*w))
+                    .collect::<Array1<_>>(),
+            ),
+            noise: FullNoise::from_operators(&operators),
+        };
+
+        let eigenvalues = system.drift_eigenvalues();
+        assert_eq!(eigenvalues.len(), 3);
+        for (omega, gamma) in omegas.iter().zip(gammas) {
+            let expected = Complex {
+                re: -0.5 * gamma,
+                im: -omega,
+            };
+            assert!(eigenvalues.iter().any(|e| (e - expected).norm() < 1e-10));
+        }
+    }
+
+    #[test]
+    fn test_cached_l_dagger_l_matches_uncached() {
+        let n_states = 6;
+        let hamiltonian = get_random_system(0, n_states).hamiltonian;
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex::from(0.5);
+            operators[[1, i, i - 1]] = Complex { re: 0f64, im: 0.3 };
+        }
+        let system = SSESystem {
+            hamiltonian: &hamiltonian + &hamiltonian.adjoint(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let cached = SSESystem {
+            hamiltonian: system.hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators).with_cached_l_dagger_l(),
+        };
+        let initial_state = get_initial_state(n_states);
+
+        let (n, step, dt) = (5, 100, 1e-4);
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &system,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(4),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &cached,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(4),
+        );
+        assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-10));
+
+        let difference = system.drift_operator() - cached.drift_operator();
+        assert!(difference.iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_parallel_noise_matches_serial() {
+        use crate::sse_system::ParallelNoise;
+
+        let n_states = 8;
+        let hamiltonian = get_random_system(0, n_states).hamiltonian;
+        let operators = Array3::from_shape_fn([6, n_states, n_states], |(i, j, k)| Complex {
+            re: ((i + 2 * j + 3 * k) % 5) as f64 * 0.1,
+            im: ((3 * i + j + k) % 7) as f64 * 0.05,
+        });
+        let serial = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let parallel = SSESystem {
+            hamiltonian,
+            noise: ParallelNoise(FullNoise::from_operators(&operators)),
+        };
+        let initial_state = get_initial_state(n_states);
+
+        let (n, step, dt) = (5, 20, 1e-4);
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &serial,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(7),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &parallel,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_midpoint_solver_norm_drift() {
+        let n_states = 4;
+        let mut hamiltonian = Array2::zeros([n_states, n_states]);
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            hamiltonian[[i - 1, i]] = Complex::from(1f64);
+            hamiltonian[[i, i - 1]] = Complex::from(1f64);
+            operators[[0, i - 1, i]] = Complex::from(0.5);
+            operators[[1, i, i]] = Complex { re: 0f64, im: 0.3 };
+        }
+        let initial_state = Array1::from_elem([n_states], Complex::from(0.5));
+        let max_drift = |result: Array2<Complex<f64>>| {
+            result
+                .outer_iter()
+                .map(|state| (state.iter().map(Complex::norm_sqr).sum::<f64>() - 1f64).abs())
+                .fold(0f64, f64::max)
+        };
+
+        let ito = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let euler = max_drift(EulerSolver::solve_with_rng(
+            &initial_state,
+            &ito,
+            500,
+            1,
+            1e-3,
+            &mut StdRng::seed_from_u64(2),
+        ));
+        let stratonovich = SSESystem {
+            hamiltonian,
+            noise: StratonovichNoise(FullNoise::from_operators(&operators)),
+        };
+        let midpoint = max_drift(MidpointSolver::solve_with_rng(
+            &initial_state,
+            &stratonovich,
+            500,
+            1,
+            1e-3,
+            &mut StdRng::seed_from_u64(2),
+        ));
+        assert!(midpoint < 0.1 * euler);
+    }
+
+    #[test]
+    fn test_stratonovich_matches_ito() {
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[0, 1]] = Complex::from(0.5);
+        hamiltonian[[1, 0]] = Complex::from(0.5);
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let initial_state = Array1::from_vec(vec![Complex::default(), Complex::from(1f64)]);
+        let identity = Array2::<Complex<f64>>::eye(2);
+        let mut excited = Array2::zeros([2, 2]);
+        excited[[1, 1]] = Complex::from(1f64);
+        let observables = [identity, excited];
+
+        let (n_trajectories, n, step, dt) = (200, 5, 40, 0.005);
+        let ito = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let expected = EulerSolver::solve_ensemble(
+            &initial_state,
+            &ito,
+            &observables,
+            n_trajectories,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(6),
+        );
+
+        assert_eq!(
+            <StratonovichHeunSolver as Solver<SSESystem<Array2<Complex<f64>>, DenseNoise>>>::CONVENTION,
+            NoiseConvention::Stratonovich
+        );
+        let stratonovich = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: StratonovichNoise(FullNoise::from_operators(&operators)),
+        };
+        let actual = StratonovichHeunSolver::solve_ensemble(
+            &initial_state,
+            &stratonovich,
+            &observables,
+            n_trajectories,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(7),
+        );
+        for i in 0..n {
+            assert!((actual.mean()[[i, 0]] - 1f64).norm() < 1e-2);
+            let error =
+                4f64 * (expected.standard_error()[[i, 1]] + actual.standard_error()[[i, 1]]);
+            assert!((actual.mean()[[i, 1]] - expected.mean()[[i, 1]]).norm() < error + 1e-2);
+        }
+
+        // Without the correction the norm of the state decays
+        let uncorrected = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&operators),
+        };
+        let uncorrected = StratonovichHeunSolver::solve_ensemble(
+            &initial_state,
+            &uncorrected,
+            &observables,
+            n_trajectories,
+            n,
+            step,
+            dt,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert!(uncorrected.mean()[[n - 1, 0]].re < 0.9);
+    }
+
+    #[test]
+    fn test_imaginary_time_ground_state() {
+        let energies = [3f64, -1f64, 2f64, 0.5f64];
+        let system = SSESystem {
+            hamiltonian: Array2::from_diag(&Array1::from_iter(energies.map(Complex::from))),
+            noise: FullNoise::from_operators(&Array3::zeros([0, 4, 4])),
+        };
+        let initial_state = Array1::from_elem([4], Complex::from(0.5));
+
+        let (state, energy) =
+            ImaginaryTimeSolver::ground_state(&initial_state, &system, 1000, 0.05);
+        assert!((energy + 1f64).abs() < 1e-8);
+        assert!((state[1].norm() - 1f64).abs() < 1e-8);
+        assert!((state.norm_l2() - 1f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solve_with_norms() {
+        let mut hamiltonian = Array2::zeros([2, 2]);
+        hamiltonian[[1, 1]] = Complex { re: 0f64, im: -0.5 };
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_elem([2], Complex::from(0.5f64.sqrt()));
+
+        let (states, norms) = EulerSolver::solve_with_norms(
+            &initial_state,
+            &system,
+            10,
+            10,
+            0.01,
+            &SolveOptions::default(),
+            &mut rand::thread_rng(),
+        );
+        assert_eq!(states.map_axis(Axis(1), |s| s.norm_l2()), norms);
+
+        let (renormalized, block_norms) = EulerSolver::solve_with_norms(
+            &initial_state,
+            &system,
+            10,
+            10,
+            0.01,
+            &SolveOptions {
+                renormalize: true,
+                ..SolveOptions::default()
+            },
+            &mut rand::thread_rng(),
+        );
+        // The product of the norm lost in each block is the total norm lost
+        for i in 1..10 {
+            let product = block_norms.slice(s![..=i]).product();
+            assert!((product - norms[i]).abs() < 1e-12);
+            assert!(block_norms[i] < 1f64);
+            assert!((renormalized.row(i).norm_l2() - 1f64).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_jump_solver_decay() {
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let system = SSESystem {
+            hamiltonian: Array2::zeros([2, 2]),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[1] = Complex::from(1f64);
+
+        let (n_trajectories, n_out, step, dt) = (200usize, 11, 10, 0.02);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut with_jumps = Array1::<f64>::zeros([n_out]);
+        let mut stepped = Array1::<f64>::zeros([n_out]);
+        for _ in 0..n_trajectories {
+            let (states, record) =
+                JumpSolver::solve_with_jumps(&initial_state, &system, n_out, step, dt, &mut rng);
+            assert!(record.times.len() <= 1);
+            assert!(record.channels.iter().all(|c| *c == 0));
+            with_jumps += &states.column(1).map(Complex::norm_sqr);
+
+            let states =
+                JumpSolver::solve_with_rng(&initial_state, &system, n_out, step, dt, &mut rng);
+            stepped += &states.column(1).map(Complex::norm_sqr);
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        for (i, (a, b)) in with_jumps.iter().zip(stepped.iter()).enumerate() {
+            let expected = (-((i * step) as f64) * dt).exp();
+            assert!((a / n_trajectories as f64 - expected).abs() < 0.08);
+            assert!((b / n_trajectories as f64 - expected).abs() < 0.08);
+        }
+    }
+
+    #[test]
+    fn test_jump_solver_step_of_zero_state() {
+        let zero = Array1::<Complex<f64>>::zeros([2]);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // Without any sources there is no channel to jump through
+        let closed = SSESystem {
+            hamiltonian: Array2::<Complex<f64>>::zeros([2, 2]),
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        assert_eq!(JumpSolver::step(&zero, &closed, 0f64, 0.1, &mut rng), zero);
+
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(1f64);
+        let open = SSESystem {
+            hamiltonian: Array2::<Complex<f64>>::zeros([2, 2]),
+            noise: FullNoise::from_operators(&operators),
+        };
+        assert_eq!(JumpSolver::step(&zero, &open, 0f64, 0.1, &mut rng), zero);
+    }
+
+    #[test]
+    fn test_next_jump_time_decay() {
+        // A decaying atom L = sqrt(gamma) |0><1| has ||\psi(t)||^2 = exp(-gamma t)
+        // without a jump, so the emission time is exponentially distributed
+        let gamma = 2f64;
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 1]] = Complex::from(gamma.sqrt());
+        let system = SSESystem {
+            hamiltonian: Array2::zeros([2, 2]),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let mut initial_state = Array1::zeros([2]);
+        initial_state[1] = Complex::from(1f64);
+
+        let dt = 0.05;
+        for u in [0.9, 0.5, 0.1, 1e-3] {
+            let time = JumpSolver::next_jump_time(&initial_state, &system, 0f64, u, dt, 10f64);
+            let expected = -u.ln() / gamma;
+            assert!((time.unwrap() - expected).abs() < 1e-6);
+        }
+        let ground = Array1::from(vec![Complex::from(1f64), Complex::default()]);
+        assert_eq!(
+            JumpSolver::next_jump_time(&ground, &system, 0f64, 0.5, dt, 10f64),
+            None
+        );
+
+        // The fraction of emissions before each time matches 1 - exp(-gamma t)
+        let mut rng = StdRng::seed_from_u64(3);
+        let times = (0..2000)
+            .map(|_| {
+                let u = 1f64 - rng.gen::<f64>();
+                JumpSolver::next_jump_time(&initial_state, &system, 0f64, u, dt, 100f64).unwrap()
+            })
+            .collect::<Vec<_>>();
+        for t in [0.1, 0.5, 1.0, 2.0] {
+            #[allow(clippy::cast_precision_loss)]
+            let fraction = times.iter().filter(|s| **s < t).count() as f64 / times.len() as f64;
+            assert!((fraction - (1f64 - (-gamma * t).exp())).abs() < 0.04);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_single_precision_matches_double() {
+        let n_states = 4;
+        let mut operators = Array3::zeros([1, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex::from(0.5);
+        }
+        let hamiltonian = Array2::from_shape_fn([n_states, n_states], |(i, j)| {
+            Complex::from(1f64 / (1 + i + j) as f64)
+        });
+        let mut initial_state = Array1::zeros([n_states]);
+        initial_state[n_states - 1] = Complex::from(1f64);
+
+        let double = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let single = SSESystem {
+            hamiltonian: hamiltonian.mapv(Complex::<f32>::from_c64),
+            noise: FullNoise::from_operators(&operators.mapv(Complex::<f32>::from_c64)),
+        };
+
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &double,
+            20,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(3),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state.mapv(Complex::<f32>::from_c64),
+            &single,
+            20,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(3),
+        );
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a.to_c64() - e).norm() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_solve_flat_matches_solve() {
+        let n_states = 10;
+        let system = get_diagonal_system(0, n_states);
+        let initial_state = get_initial_state(n_states);
+
+        let n_out = 5;
+        let expected = EulerSolver::solve(&initial_state, &system, n_out, 10, 0.01);
+        let (flat, shape) = EulerSolver::solve_flat(&initial_state, &system, n_out, 10, 0.01);
+
+        assert_eq!(shape, [n_out, n_states]);
+        assert_eq!(Array2::from_shape_vec(shape, flat).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_solve_monitored_detects_unstable_dt() {
+        // For a closed system with H |0> = |0>, each euler step multiplies
+        // the norm by |1 - i dt| = sqrt(2) when dt = 1
+        let n_states = 2;
+        let system = SSESystem {
+            noise: get_random_noise(0, n_states),
+            hamiltonian: Array2::from_diag(&Array1::from_elem(
+                n_states,
+                Complex { re: 1f64, im: 0f64 },
+            )),
+        };
+        let initial_state = get_initial_state(n_states);
+
+        let result = EulerSolver::solve_monitored(&initial_state, &system, 10, 10, 1f64, 1e6);
+        // 2^(39 / 2) < 1e6 < 2^(40 / 2)
+        match result {
+            Err(SolverError::Unstable { step, norm }) => {
+                assert_eq!(step, 40);
+                assert!((norm - 2f64.powi(20)).abs() < 1e-6);
+            }
+            _ => panic!("Expected solve to be unstable"),
+        }
+
+        let stable = EulerSolver::solve_monitored(&initial_state, &system, 10, 10, 1e-3, 1e6);
+        assert!(stable.is_ok());
+    }
+
+    #[test]
+    fn test_solve_with_energy_closed_system() {
+        // For H = |1><1| each euler step multiplies the amplitude of |1> by
+        // |1 - i dt| = sqrt(1 + dt^2), so starting from an equal superposition
+        // the energy drifts by approximately dt * t / 4
+        let n_states = 2;
+        let system = SSESystem {
+            noise: get_random_noise(0, n_states),
+            hamiltonian: Array2::from_diag(&Array1::from(vec![
+                Complex::default(),
+                Complex { re: 1f64, im: 0f64 },
+            ])),
+        };
+        let amplitude = Complex {
+            re: std::f64::consts::FRAC_1_SQRT_2,
+            im: 0f64,
+        };
+        let initial_state = Array1::from(vec![amplitude, amplitude]);
+
+        let dt = 1e-3;
+        let (states, energies) =
+            solve_with_energy::<EulerSolver, _, _>(&initial_state, &system, 11, 100, dt, dt)
+                .unwrap();
+        assert_eq!(states.nrows(), 11);
+        assert!((energies[0] - 0.5).abs() < 1e-12);
+        let drift = energies[10] - energies[0];
+        assert!((drift - dt / 4f64).abs() < 0.1 * dt);
+
+        match solve_with_energy::<EulerSolver, _, _>(
+            &initial_state,
+            &system,
+            11,
+            100,
+            dt,
+            dt / 10f64,
+        ) {
+            Err(SolverError::EnergyDrift { step, drift }) => {
+                assert_eq!(step, 500);
+                assert!(drift > dt / 10f64);
+            }
+            _ => panic!("Expected the energy to drift"),
+        }
+
+        let noisy = get_random_system(1, n_states);
+        assert!(matches!(
+            solve_with_energy::<EulerSolver, _, _>(&initial_state, &noisy, 11, 100, dt, dt),
+            Err(SolverError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_contractive_detects_flipped_sign() {
+        // A decay H = -i gamma / 2 |1><1| is contractive, but the flipped
+        // sign H = +i gamma / 2 |1><1| describes gain, and the norm grows
+        let n_states = 2;
+        let decay = Array2::from_diag(&Array1::from(vec![
+            Complex::default(),
+            Complex { re: 0f64, im: -0.5 },
+        ]));
+        let gain = decay.map(Complex::conj);
+        let amplitude = Complex {
+            re: std::f64::consts::FRAC_1_SQRT_2,
+            im: 0f64,
+        };
+        let initial_state = Array1::from(vec![amplitude, amplitude]);
+
+        let system = SSESystem {
+            noise: get_random_noise(0, n_states),
+            hamiltonian: decay,
+        };
+        assert!(check_contractive(&initial_state, &system, 10, 0.01).is_ok());
+
+        let system = SSESystem {
+            noise: get_random_noise(0, n_states),
+            hamiltonian: gain,
+        };
+        match check_contractive(&initial_state, &system, 10, 0.01) {
+            Err(SolverError::NonContractive { step, rate }) => {
+                assert_eq!(step, 0);
+                // d ln||psi||^2 / dt = gamma |psi_1|^2
+                assert!((rate - 0.5).abs() < 1e-12);
+            }
+            _ => panic!("Expected gain to be detected"),
+        }
+
+        // Decay through a collapse operator L = sqrt(gamma) |0><1| is also contractive
+        let mut lowering = Array3::zeros([1, n_states, n_states]);
+        lowering[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&lowering),
+            hamiltonian: Array2::zeros([n_states, n_states]),
+        };
+        assert!(check_contractive(&initial_state, &system, 10, 0.01).is_ok());
+    }
+
+    #[test]
+    fn test_solve_with_events_population_crossing() {
+        // Rabi oscillation H = sigma_x, for which P_1 = sin^2(t)
+        // crosses 1/2 from below at t = pi / 4 and t = 5 pi / 4
+        let one = Complex { re: 1f64, im: 0f64 };
+        let hamiltonian = Array2::from_shape_vec(
+            [2, 2],
+            vec![Complex::default(), one, one, Complex::default()],
+        )
+        .unwrap();
+        let system = SSESystem {
+            noise: get_random_noise(0, 2),
+            hamiltonian,
+        };
+        let initial_state = get_initial_state(2);
+
+        let (times, states) = EulerSolver::solve_with_events(
+            &initial_state,
+            &system,
+            |state, _t| {
+                state[1].norm_sqr() / state.iter().map(Complex::norm_sqr).sum::<f64>() > 0.5
+            },
+            40_000,
+            1e-4,
+        );
+
+        assert_eq!(times.len(), 2);
+        assert_eq!(states.nrows(), 2);
+        assert!((times[0] - std::f64::consts::FRAC_PI_4).abs() < 1e-3);
+        assert!((times[1] - 5f64 * std::f64::consts::FRAC_PI_4).abs() < 1e-3);
+        for state in states.outer_iter() {
+            let population = state[1].norm_sqr() / state.iter().map(Complex::norm_sqr).sum::<f64>();
+            assert!((population - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_validate_only() {
+        let n_states = 4;
+        let mut lowering = Array3::zeros([1, n_states, n_states]);
+        lowering[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&lowering),
+            hamiltonian: Array2::from_diag(&Array1::from_elem(
+                n_states,
+                Complex { re: 1f64, im: 0f64 },
+            )),
+        };
+        let initial_state = get_initial_state(n_states);
+
+        let report = validate_only(&initial_state, &system, 100, 10, 0.01).unwrap();
+        assert_eq!(report.n_states, n_states);
+        assert_eq!(report.n_incoherent, 1);
+        assert_eq!(report.n_steps, 99 * 10);
+        assert_eq!(report.output_bytes, 100 * n_states * 16);
+        assert!(report.estimated_seconds() >= 0f64);
+
+        let mut invalid_state = initial_state.clone();
+        invalid_state[1] = Complex {
+            re: f64::NAN,
+            im: 0f64,
+        };
+        assert_eq!(
+            validate_only(&invalid_state, &system, 100, 10, 0.01),
+            Err(SolverError::InvalidConfiguration(
+                "initial state is not finite"
+            ))
+        );
+        assert!(matches!(
+            validate_only(&initial_state, &system, 100, 10, -0.01),
+            Err(SolverError::InvalidConfiguration(_))
+        ));
+
+        let gain = SSESystem {
+            noise: FullNoise::from_operators(&Array3::zeros([0, n_states, n_states])),
+            hamiltonian: Array2::from_diag(&Array1::from_elem(
+                n_states,
+                Complex { re: 0f64, im: 1f64 },
+            )),
+        };
+        assert!(matches!(
+            validate_only(&initial_state, &gain, 100, 10, 0.01),
+            Err(SolverError::NonContractive { .. })
+        ));
+    }
+
+    #[test]
+    fn test_srk_solver_convergence_order() {
+        // Without noise the weak error is the error of the deterministic evolution,
+        // for which SrkSolver is second order and EulerSolver first order
+        let t_end = 1f64;
+        let (system, initial_state, expected) = diagonal_closed_system(t_end);
+        let error = |result: Array2<Complex<f64>>| l2_error(result.row(1), &expected);
+
+        let (coarse, fine) = (20, 40);
+        #[allow(clippy::cast_precision_loss)]
+        let solve_srk = |n_step: usize| {
+            error(SrkSolver::solve(
+                &initial_state,
+                &system,
+                2,
+                n_step,
+                t_end / n_step as f64,
+            ))
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let solve_euler = |n_step: usize| {
+            error(EulerSolver::solve(
+                &initial_state,
+                &system,
+                2,
+                n_step,
+                t_end / n_step as f64,
+            ))
+        };
+
+        let srk_ratio = solve_srk(coarse) / solve_srk(fine);
+        let euler_ratio = solve_euler(coarse) / solve_euler(fine);
+        assert!((srk_ratio - 4f64).abs() < 0.5);
+        assert!((euler_ratio - 2f64).abs() < 0.25);
+        assert!(solve_srk(coarse) < 0.1 * solve_euler(coarse));
+    }
+
+    #[test]
+    fn test_weak_order_2_solver_convergence_order() {
+        // Dephasing L = sqrt(gamma) sigma_z decays the average coherence <sigma_x>
+        // as exp(-2 gamma t). The error in the ensemble average is O(dt^2) for
+        // WeakOrder2Solver, and O(dt) for EulerSolver
+        let mut operators = Array3::zeros([1, 2, 2]);
+        operators[[0, 0, 0]] = Complex { re: 0.5, im: 0f64 };
+        operators[[0, 1, 1]] = Complex { re: -0.5, im: 0f64 };
+        let system = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: Array2::zeros([2, 2]),
+        };
+        let amplitude = Complex {
+            re: std::f64::consts::FRAC_1_SQRT_2,
+            im: 0f64,
+        };
+        let initial_state = Array1::from(vec![amplitude, amplitude]);
+        let expected = (-0.5f64).exp();
+
+        let n_trajectories = 20000;
+        let error = |solve: &dyn Fn(&mut StdRng) -> Array2<Complex<f64>>| {
+            let mut rng = StdRng::seed_from_u64(3);
+            let mut total = 0f64;
+            for _ in 0..n_trajectories {
+                let state = solve(&mut rng);
+                let state = state.row(1);
+                total += 2f64 * (state[0].conj() * state[1]).re
+                    / (state[0].norm_sqr() + state[1].norm_sqr());
+            }
+            (total / f64::from(n_trajectories) - expected).abs()
+        };
+        let weak = |n_step: usize| {
+            #[allow(clippy::cast_precision_loss)]
+            let dt = 1f64 / n_step as f64;
+            error(&|rng| {
+                WeakOrder2Solver::solve_with_rng(&initial_state, &system, 2, n_step, dt, rng)
+            })
+        };
+        let euler = |n_step: usize| {
+            #[allow(clippy::cast_precision_loss)]
+            let dt = 1f64 / n_step as f64;
+            error(&|rng| EulerSolver::solve_with_rng(&initial_state, &system, 2, n_step, dt, rng))
+        };
+
+        let (weak_coarse, weak_fine) = (weak(1), weak(2));
+        let (euler_coarse, euler_fine) = (euler(1), euler(2));
+        assert!(weak_coarse / weak_fine > 3f64);
+        assert!(euler_coarse / euler_fine < 3f64);
+    }
+
+    #[test]
+    fn test_heun_solver_convergence_order() {
+        // For H |n> = E_n |n> the euler step (1 - i E dt) has an error in the norm of O(dt),
+        // which the heun step (1 - i E dt - (E dt)^2 / 2) reduces to O(dt^2)
+        let (system, initial_state, expected) = diagonal_closed_system(1f64);
+        let error = |result: Array2<Complex<f64>>| l2_error(result.row(1), &expected);
+        #[allow(clippy::cast_precision_loss)]
+        let heun = |n_step: usize| {
+            error(HeunSolver::solve(
+                &initial_state,
+                &system,
+                2,
+                n_step,
+                1f64 / n_step as f64,
+            ))
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let euler = |n_step: usize| {
+            error(EulerSolver::solve(
+                &initial_state,
+                &system,
+                2,
+                n_step,
+                1f64 / n_step as f64,
+            ))
+        };
+
+        assert!((heun(20) / heun(40) - 4f64).abs() < 0.5);
+        assert!((euler(20) / euler(40) - 2f64).abs() < 0.25);
+        assert!(heun(20) < 0.1 * euler(20));
+    }
+
+    #[test]
+    fn test_richardson_solver_convergence_order() {
+        let (system, initial_state, expected) = diagonal_closed_system(1f64);
+
+        #[allow(clippy::cast_precision_loss)]
+        let error = |n_step: usize| {
+            let result = RichardsonSolver::<EulerSolver>::solve(
+                &initial_state,
+                &system,
+                2,
+                n_step,
+                1f64 / n_step as f64,
+            );
+            l2_error(result.row(1), &expected)
+        };
+
+        assert!((error(20) / error(40) - 4f64).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_solver_orders_are_positive() {
+        fn orders<V: Solver<T>, T: SDESystem>(_system: &T) -> [f64; 3] {
+            [f64::from(V::ORDER), V::STRONG_ORDER, V::WEAK_ORDER]
+        }
+        let system = get_random_system(2, 4);
+
+        let all_orders = [
+            orders::<EulerSolver, _>(&system),
+            orders::<NormalizedEulerSolver, _>(&system),
+            orders::<ImaginaryTimeSolver, _>(&system),
+            orders::<JumpSolver, _>(&system),
+            orders::<HeunSolver, _>(&system),
+            orders::<StratonovichHeunSolver, _>(&system),
+            orders::<RichardsonSolver<EulerSolver>, _>(&system),
+            orders::<MilstenSolver, _>(&system),
+            orders::<SrkSolver, _>(&system),
+            orders::<WeakOrder2Solver, _>(&system),
+            orders::<Order2ExplicitWeakSolver, _>(&system),
+        ];
+        assert!(all_orders.iter().flatten().all(|order| *order > 0f64));
+        let is_close = |actual: [f64; 3], expected: [f64; 3]| {
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 1e-12)
+        };
+        assert!(is_close(all_orders[0], [1.0, 0.5, 1.0]));
+        assert!(is_close(all_orders[6], [2.0, 0.5, 1.0]));
+        // The weak order of Order2ExplicitWeakSolver is not yet verified by a convergence test
+        assert!(is_close(all_orders[10], [1.0, 0.5, 1.0]));
+    }
+
+    #[test]
+    fn test_integrate_adaptive() {
+        let t_end = 2f64;
+        let (system, initial_state, expected) = diagonal_closed_system(t_end);
+        let n_states = initial_state.len();
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let (loose, n_loose) =
+            EulerSolver::integrate_adaptive(&initial_state, &system, 0f64, t_end, 1e-4, &mut rng);
+        let (tight, n_tight) =
+            EulerSolver::integrate_adaptive(&initial_state, &system, 0f64, t_end, 1e-6, &mut rng);
+
+        let error = |state: &Array1<Complex<f64>>| l2_error(state.view(), &expected);
+        assert!(n_tight > n_loose);
+        assert!(error(&tight) < error(&loose));
+        assert!(error(&tight) < 1e-2);
+
+        // With noise the step is refined until the error is below the tolerance
+        let mut operators = Array3::zeros([1, n_states, n_states]);
+        operators[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        let noisy = SSESystem {
+            noise: FullNoise::from_operators(&operators),
+            hamiltonian: system.hamiltonian.clone(),
+        };
+        let (state, n_steps) =
+            EulerSolver::integrate_adaptive(&initial_state, &noisy, 0f64, t_end, 1e-4, &mut rng);
+        assert!(n_steps > n_loose);
+        assert!(state.iter().all(|s| s.is_finite()));
+
+        // The end of the interval is reached exactly
+        let (state, n_steps) =
+            EulerSolver::integrate_adaptive(&initial_state, &system, 1f64, 1f64, 1e-4, &mut rng);
+        assert_eq!(state, initial_state);
+        assert_eq!(n_steps, 0);
+    }
+
+    #[test]
+    fn test_solve_with_constant_system_fn() {
+        let n_states = 10;
+        let system = get_diagonal_system(0, n_states);
+        let initial_state = get_initial_state(n_states);
+
+        let n_out = 5;
+        let expected = EulerSolver::solve(&initial_state, &system, n_out, 10, 0.01);
+        let actual = EulerSolver::solve_with_system_fn(
+            &initial_state,
+            |_t, _state| SSESystem {
+                noise: FullNoise::from_operators(&Array3::zeros([0, n_states, n_states])),
+                hamiltonian: system.hamiltonian.clone(),
+            },
+            n_out,
+            10,
+            0.01,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_solve_with_state_dependent_system_fn() {
+        // A self-phase modulation H = g |psi_0|^2 |0><0|, for which
+        // psi_0(t) = psi_0(0) exp(-i g |psi_0(0)|^2 t)
+        let coupling = 2f64;
+        let amplitude = Complex {
+            re: std::f64::consts::FRAC_1_SQRT_2,
+            im: 0f64,
+        };
+        let initial_state = Array1::from(vec![amplitude, amplitude]);
+
+        let (n, step, dt) = (11, 100, 1e-3);
+        let result = EulerSolver::solve_with_system_fn(
+            &initial_state,
+            |_t, state| {
+                let mut hamiltonian = Array2::zeros([2, 2]);
+                hamiltonian[[0, 0]] = Complex {
+                    re: coupling * state[0].norm_sqr(),
+                    im: 0f64,
+                };
+                SSESystem {
+                    noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+                    hamiltonian,
+                }
+            },
+            n,
+            step,
+            dt,
+        );
+
+        for (i, state) in result.outer_iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let t = (i * step) as f64 * dt;
+            let expected = -coupling * amplitude.norm_sqr() * t;
+            assert!((state[0].arg() - expected).abs() < 1e-2);
+            assert_eq!(state[1], amplitude);
+        }
+    }
+
+    #[test]
+    fn test_banded_dot_product() {
+        let rng = rand::thread_rng();
+        let shape = [10, 100];
+
+        let full = Array2::from_shape_vec(
+            shape,
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[0] * shape[1])
+                .collect(),
+        )
+        .unwrap();
+        let banded = BandedArray::from_dense(&full);
+
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[1]),
+        );
+
+        let expected = full.dot(&state);
+        let actual = banded.dot(&state);
+        for i in 0..shape[0] {
+            assert!((expected[i] - actual[i]).abs() < 1e-8);
+        }
+        assert_eq!(expected.len(), actual.len());
+    }
+
+    #[test]
+    fn test_csr_dot_product() {
+        let rng = rand::thread_rng();
+        let shape = [10, 100];
+
+        // Keep roughly one in four elements
+        let full = Array2::from_shape_fn(shape, |_| {
+            if rng.clone().gen_bool(0.25) {
+                rng.clone().sample(StandardComplexNormal)
+            } else {
+                Complex::default()
+            }
+        });
+        let csr = CsrArray::from_dense(&full);
+
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[1]),
+        );
+        let expected = full.dot(&state);
+        let actual = csr.dot(&state);
+        assert_eq!(expected.len(), actual.len());
+        for i in 0..shape[0] {
+            assert!((expected[i] - actual[i]).abs() < 1e-10);
+        }
+
+        let transposed_state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[0]),
+        );
+        let expected = full.t().dot(&transposed_state);
+        let actual = csr.transpose().dot(&transposed_state);
+        assert_eq!(expected.len(), actual.len());
+        for i in 0..shape[1] {
+            assert!((expected[i] - actual[i]).abs() < 1e-10);
+        }
+
+        let expected = full.adjoint().dot(&transposed_state);
+        let actual = csr.adjoint().dot(&transposed_state);
+        for i in 0..shape[1] {
+            assert!((expected[i] - actual[i]).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_diagonal_system_matches_dense() {
+        let n_states = 6;
+        let energies = Array1::linspace(-1f64, 1f64, n_states).mapv(Complex::from);
+        let dephasing = Array1::from_shape_fn(n_states, |i| Complex {
+            re: 0.1 * i as f64,
+            im: 0.05,
+        });
+        let mut dense_operators = Array3::zeros([1, n_states, n_states]);
+        dense_operators
+            .index_axis_mut(Axis(0), 0)
+            .diag_mut()
+            .assign(&dephasing);
+
+        let dense = SSESystem {
+            hamiltonian: Array2::from_diag(&energies),
+            noise: FullNoise::from_operators(&dense_operators),
+        };
+        let diagonal = SSESystem {
+            hamiltonian: DiagonalArray::from_diagonal(energies),
+            noise: FullNoise::from_diagonal(&[DiagonalArray::from_diagonal(dephasing)]),
+        };
+        let initial_state =
+            Array1::from_elem([n_states], Complex::from((n_states as f64).sqrt().recip()));
+
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &dense,
+            10,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(5),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &diagonal,
+            10,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(5),
+        );
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_rectangular_banded_noise() {
+        // A projection C^3 -> C^2, discarding the last state
+        let projection = BandedArray::from_diagonals(
+            [2, 3],
+            &[0],
+            vec![Array1::from_elem([2], Complex::from(1f64))],
+        )
+        .unwrap();
+        assert_eq!(projection.operator_shape(), [2, 3]);
+        let state = Array1::from_vec(vec![
+            Complex::from(1f64),
+            Complex::from(2f64),
+            Complex::from(3f64),
+        ]);
+        assert_eq!(projection.dot(&state), state.slice(s![..2]));
+
+        let embedded = projection.embed([3, 3]);
+        assert_eq!(embedded.operator_shape(), [3, 3]);
+        let projected = embedded.dot(&state);
+        assert_eq!(projected.slice(s![..2]), state.slice(s![..2]));
+        assert_eq!(projected[2], Complex::default());
+
+        assert!(matches!(
+            FullNoise::try_from_banded(std::slice::from_ref(&projection)),
+            Err(crate::SseError::NonSquareOperator { .. })
+        ));
+        let noise = FullNoise::from_rectangular_banded(&[projection], 3);
+        assert_eq!(noise.n_states(), Some(3));
+        // The drift -1/2 L^\dagger L is a projector onto the first two states
+        let l_dagger_l = noise.l_dagger_l(3);
+        let mut expected = Array2::zeros([3, 3]);
+        expected[[0, 0]] = Complex::from(1f64);
+        expected[[1, 1]] = Complex::from(1f64);
+        assert_eq!(l_dagger_l, expected);
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_banded_from_dense_with_tolerance() {
+        let n_states = 8;
+        // A tridiagonal matrix, with noise of 1e-9 off the band
+        let dense = Array2::from_shape_fn([n_states, n_states], |(i, j)| match i.abs_diff(j) {
+            0 => Complex::from(i as f64),
+            1 => Complex { re: 1f64, im: 0.5 },
+            _ => Complex {
+                re: 1e-9 * ((i * j) % 3) as f64,
+                im: -1e-9,
+            },
+        });
+        let tolerance = 1e-6;
+
+        let (banded, bandwidth) = BandedArray::from_dense_with_tolerance(&dense, tolerance);
+        assert_eq!(bandwidth, 1);
+        let (exact, full_bandwidth) = BandedArray::from_dense_with_tolerance(&dense, 0f64);
+        assert_eq!(full_bandwidth, n_states - 1);
+
+        let state = Array1::from_iter(
+            rand::thread_rng()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states),
+        );
+        let expected = dense.dot(&state);
+        for (a, e) in banded.dot(&state).iter().zip(expected.iter()) {
+            assert!((a - e).norm() < 10f64 * tolerance);
+        }
+        for (a, e) in exact.dot(&state).iter().zip(expected.iter()) {
+            assert!((a - e).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_banded_from_diagonals() {
+        let n_states = 6;
+        let lower = Array1::from_elem([n_states - 1], Complex { re: 1f64, im: 0.5 });
+        let main = Array1::linspace(0f64, 1f64, n_states).mapv(Complex::from);
+        let upper = Array1::from_elem([n_states - 1], Complex { re: 2f64, im: 0f64 });
+
+        let mut dense = Array2::from_diag(&main);
+        for i in 1..n_states {
+            dense[[i, i - 1]] = lower[i - 1];
+            dense[[i - 1, i]] = upper[i - 1];
+        }
+        let banded = BandedArray::from_diagonals(
+            [n_states, n_states],
+            &[-1, 0, 1],
+            vec![lower.clone(), main.clone(), upper],
+        )
+        .unwrap();
+
+        let state = Array1::from_iter(
+            rand::thread_rng()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states),
+        );
+        let expected = dense.dot(&state);
+        let actual = banded.dot(&state);
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).norm() < 1e-10);
+        }
+
+        let mismatched =
+            BandedArray::from_diagonals([n_states, n_states], &[-1, 0], vec![main.clone(), main]);
+        assert_eq!(
+            mismatched.err().map(|e| e.kind()),
+            Some(ndarray::ErrorKind::IncompatibleShape)
+        );
+        let out_of_bounds = BandedArray::from_diagonals([n_states, n_states], &[-6], vec![lower]);
+        assert_eq!(
+            out_of_bounds.err().map(|e| e.kind()),
+            Some(ndarray::ErrorKind::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_factorized_sum_is_a_single_source() {
+        let n_states = 3;
+        let basis = |i: usize| {
+            let mut state = Array1::zeros([n_states]);
+            state[i] = Complex::from(1f64);
+            state
+        };
+        // L = |0><1| + |0><2|, for which (|1> - |2>) / sqrt(2) is dark
+        let terms =
+            [1, 2].map(|i| FactorizedArray::from_bra_ket(Complex::from(1f64), basis(i), basis(0)));
+        let sum = FactorizedSum::from_terms(terms.to_vec());
+
+        let mut dense = Array2::zeros([n_states, n_states]);
+        dense[[0, 1]] = Complex::from(1f64);
+        dense[[0, 2]] = Complex::from(1f64);
+        let state = Array1::from_iter(
+            rand::thread_rng()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states),
+        );
+        assert!((sum.dot(&state) - dense.dot(&state))
+            .iter()
+            .all(|d| d.norm() < 1e-10));
+        assert!((sum.adjoint().dot(&state) - dense.adjoint().dot(&state))
+            .iter()
+            .all(|d| d.norm() < 1e-10));
+
+        let dark = (basis(1) - basis(2)) * Complex::from(0.5f64.sqrt());
+        let single = SSESystem {
+            hamiltonian: Array2::zeros([n_states, n_states]),
+            noise: FullNoise::from_factorized_sum(&[sum]),
+        };
+        let independent = SSESystem {
+            hamiltonian: Array2::zeros([n_states, n_states]),
+            noise: FullNoise::from_operators(&Array3::from_shape_fn(
+                [2, n_states, n_states],
+                |(n, i, j)| {
+                    if i == 0 && j == n + 1 {
+                        Complex::from(1f64)
+                    } else {
+                        Complex::default()
+                    }
+                },
+            )),
+        };
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut independent_population = 0f64;
+        for _ in 0..20 {
+            let result =
+                NormalizedEulerSolver::solve_with_rng(&dark, &single, 2, 200, 0.01, &mut rng);
+            assert!((&result.row(1) - &dark).iter().all(|d| d.norm() < 1e-10));
+
+            let result =
+                NormalizedEulerSolver::solve_with_rng(&dark, &independent, 2, 200, 0.01, &mut rng);
+            independent_population += result[[1, 0]].norm_sqr() / 20f64;
+        }
+        // With independent sources |1> and |2> each decay into |0> at rate 1, so <P_0> = 1 - e^{-t}
+        assert!((independent_population - (1f64 - (-2f64).exp())).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_banded_transposed_dot_product() {
+        let rng = rand::thread_rng();
+        let shape = [100, 10];
+
+        let full = Array2::from_shape_vec(
+            [shape[1], shape[0]],
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[0] * shape[1])
+                .collect(),
+        )
+        .unwrap();
+        let banded = BandedArray::from_dense(&full);
+
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[1]),
+        );
+
+        let expected = full.reversed_axes().dot(&state);
+        let actual = banded.transpose().dot(&state);
+
+        for i in 0..shape[0] {
+            assert!((expected[i] - actual[i]).abs() < 1e-8);
+        }
+        assert_eq!(expected.len(), actual.len());
+    }
+
+    #[test]
+    fn test_banded_adjoint_dot_product() {
+        let rng = rand::thread_rng();
+        let shape = [10, 30];
+
+        let full = Array2::from_shape_vec(
+            shape,
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[0] * shape[1])
+                .collect(),
+        )
+        .unwrap();
+        let banded = BandedArray::from_dense(&full);
+
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[0]),
+        );
+
+        let expected = full.adjoint().dot(&state);
+        let actual = banded.adjoint().dot(&state);
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert!((e - a).abs() < 1e-8);
+        }
+        assert_eq!(expected.len(), actual.len());
+
+        // The adjoint of the adjoint is the original operator
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(shape[1]),
+        );
+        let expected = full.dot(&state);
+        let actual = banded.adjoint().adjoint().dot(&state);
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert!((e - a).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_factorized_jump_rate() {
+        let rng = rand::thread_rng();
+        let n_states = 12;
+        let sample = || {
+            Array1::from_iter(
+                rng.clone()
+                    .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                    .take(n_states),
+            )
+        };
+        let (bra, ket, state) = (sample(), sample(), sample());
+        let amplitude = Complex { re: 0.3, im: -1.1 };
+        let operator = FactorizedArray::from_bra_ket(amplitude, bra.clone(), ket);
+
+        let expected = bra.dot(&state);
+        assert!((operator.bra_overlap(&state) - expected).abs() < 1e-10);
+
+        let jumped = operator.dot(&state);
+        let expected = jumped.iter().map(Complex::norm_sqr).sum::<f64>();
+        assert!((operator.jump_rate(&state) - expected).abs() < 1e-10 * expected);
+    }
+
+    #[test]
+    fn test_factorized_adjoint_dot_product() {
+        let rng = rand::thread_rng();
+        let n_states = 7;
+        let sample = || {
+            Array1::from_iter(
+                rng.clone()
+                    .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                    .take(n_states),
+            )
+        };
+        let (bra, ket, state) = (sample(), sample(), sample());
+        let amplitude = Complex { re: 0.3, im: -1.1 };
+        let operator = FactorizedArray::from_bra_ket(amplitude, bra.clone(), ket.clone());
+
+        // A |ket><bra|, where the bra is stored without conjugation
+        let dense =
+            Array2::from_shape_fn([n_states, n_states], |(i, j)| amplitude * ket[i] * bra[j]);
+        let dense_adjoint = dense.t().map(Complex::conj);
+
+        let expected = dense_adjoint.dot(&state);
+        for actual in [
+            operator.adjoint().dot(&state),
+            operator.conj().transpose().dot(&state),
+        ] {
+            assert!((&actual - &expected).iter().all(|d| d.norm() < 1e-10));
+        }
+
+        // The noise built from bra and ket matches the dense operators
+        let factorized = SSESystem {
+            hamiltonian: Array2::zeros([n_states, n_states]),
+            noise: FullNoise::from_bra_ket(
+                Array1::from_elem(1, amplitude),
+                &bra.insert_axis(Axis(0)),
+                &ket.insert_axis(Axis(0)),
+            ),
+        };
+        let dense = SSESystem {
+            hamiltonian: Array2::zeros([n_states, n_states]),
+            noise: FullNoise::from_operators(&dense.insert_axis(Axis(0))),
+        };
+        let initial_state = &state / Complex::from(state.norm_l2());
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &dense,
+            3,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(2),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &factorized,
+            3,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(2),
+        );
+        assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-10));
     }
 }
@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use memmap2::Mmap;
+use ndarray::{linalg::Dot, Array1};
+use num_complex::Complex;
+
+use crate::sse_system::{Adjoint, OperatorShape};
+
+/// The number of bytes used to store each element of a band
+const ELEMENT_SIZE: usize = 16;
+
+/// Write the `diagonals` of a banded array to the file at `path`, in the layout read
+/// by [`MmapBandedArray::open`]. Each diagonal is written as it is yielded,
+/// so the array is never held in memory.
+///
+/// Each element is stored as the little endian bytes of its real then imaginary part.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be created or written to
+pub fn write_bands<P: AsRef<Path>, D: AsRef<[Complex<f64>]>>(
+    path: P,
+    diagonals: impl IntoIterator<Item = D>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for diagonal in diagonals {
+        for value in diagonal.as_ref() {
+            writer.write_all(&value.re.to_le_bytes())?;
+            writer.write_all(&value.im.to_le_bytes())?;
+        }
+    }
+    writer.flush()
+}
+
+/// A [`crate::sparse::BandedArray`] whose diagonals are stored in a memory mapped file.
+///
+/// The diagonals are read from the file as the array is applied,
+/// so only the pages in use are held in memory. The adjoint shares the same map.
+#[derive(Clone)]
+pub struct MmapBandedArray {
+    data: Arc<Mmap>,
+    offsets: Vec<usize>,
+    // The shape of the array stored in the file
+    shape: [usize; 2],
+    is_adjoint: bool,
+}
+
+impl MmapBandedArray {
+    /// Map the diagonals written by [`write_bands`] at `path`, where diagonal `k` stores the
+    /// elements `M_{i + offsets[k] % N_0, i}` as in [`crate::sparse::BandedArray`].
+    ///
+    /// The file must not be modified while the array is in use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be mapped, or if its length does not
+    /// match the `offsets` and `shape`
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        offsets: Vec<usize>,
+        shape: [usize; 2],
+    ) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read, and the file is required not to change while mapped
+        let data = unsafe { Mmap::map(&file)? };
+        let expected = offsets.len() * shape[1] * ELEMENT_SIZE;
+        if data.len() != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "file has {} bytes, but {} diagonals of length {} require {expected}",
+                    data.len(),
+                    offsets.len(),
+                    shape[1]
+                ),
+            ));
+        }
+        Ok(Self {
+            data: Arc::new(data),
+            offsets,
+            shape,
+            is_adjoint: false,
+        })
+    }
+
+    /// The elements of diagonal `k`
+    fn diagonal(&self, k: usize) -> impl Iterator<Item = Complex<f64>> + '_ {
+        let length = self.shape[1] * ELEMENT_SIZE;
+        self.data[k * length..(k + 1) * length]
+            .chunks_exact(ELEMENT_SIZE)
+            .map(|bytes| {
+                let (re, im) = bytes.split_at(ELEMENT_SIZE / 2);
+                Complex {
+                    re: f64::from_le_bytes(re.try_into().unwrap()),
+                    im: f64::from_le_bytes(im.try_into().unwrap()),
+                }
+            })
+    }
+}
+
+impl OperatorShape for MmapBandedArray {
+    fn operator_shape(&self) -> [usize; 2] {
+        if self.is_adjoint {
+            [self.shape[1], self.shape[0]]
+        } else {
+            self.shape
+        }
+    }
+}
+
+impl Adjoint for MmapBandedArray {
+    type Output = MmapBandedArray;
+
+    fn adjoint(&self) -> Self::Output {
+        Self {
+            data: Arc::clone(&self.data),
+            offsets: self.offsets.clone(),
+            shape: self.shape,
+            is_adjoint: !self.is_adjoint,
+        }
+    }
+}
+
+impl Dot<Array1<Complex<f64>>> for MmapBandedArray {
+    type Output = Array1<Complex<f64>>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<Complex<f64>>) -> Self::Output {
+        let [n_rows, n_columns] = self.operator_shape();
+        assert_eq!(n_columns, rhs.len());
+        let mut out = Array1::zeros(n_rows);
+
+        for (k, offset) in self.offsets.iter().enumerate() {
+            // The element j of the diagonal is M_{(j + offset) % N_0, j}
+            for (j, value) in self.diagonal(k).enumerate() {
+                let i = (j + offset) % self.shape[0];
+                if self.is_adjoint {
+                    out[j] += value.conj() * rhs[i];
+                } else {
+                    out[i] += value * rhs[j];
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{linalg::Dot, Array1};
+    use num_complex::Complex;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        distribution::StandardComplexNormal,
+        sparse::BandedArray,
+        sse_system::{Adjoint, FullNoise},
+    };
+
+    use super::{write_bands, MmapBandedArray};
+
+    #[test]
+    fn test_mmap_banded_matches_banded() {
+        let n_states = 7;
+        let mut rng = StdRng::seed_from_u64(3);
+        let offsets = vec![0, 1, 5];
+        let diagonals = offsets
+            .iter()
+            .map(|_| {
+                (0..n_states)
+                    .map(|_| rng.sample(StandardComplexNormal))
+                    .collect::<Vec<Complex<f64>>>()
+            })
+            .collect::<Vec<_>>();
+        let banded = BandedArray::from_sparse(&diagonals, &offsets, &[n_states, n_states]);
+
+        let path = std::env::temp_dir().join("sse_solver_test_mmap_banded_matches_banded.bin");
+        write_bands(&path, &diagonals).unwrap();
+        let mapped = MmapBandedArray::open(&path, offsets.clone(), [n_states, n_states]).unwrap();
+        assert!(MmapBandedArray::open(&path, offsets, [n_states + 1, n_states + 1]).is_err());
+
+        let state = Array1::from_shape_fn([n_states], |_| rng.sample(StandardComplexNormal));
+        let expected = banded.dot(&state);
+        assert!((&expected - &mapped.dot(&state))
+            .iter()
+            .all(|d| d.norm() < 1e-12));
+        let expected = banded.adjoint().dot(&state);
+        assert!((&expected - &mapped.adjoint().dot(&state))
+            .iter()
+            .all(|d| d.norm() < 1e-12));
+
+        let noise = FullNoise::from_mmap_banded(&[mapped]);
+        assert_eq!(noise.n_states(), Some(n_states));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
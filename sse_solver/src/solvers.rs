@@ -1,92 +1,2312 @@
-use ndarray::{Array1, Array2};
-use ndarray_linalg::Norm;
+use std::{
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    ops::{ControlFlow, Range},
+};
+
+use ndarray::{linalg::Dot, Array1, Array2, Array3, Ix2, OwnedRepr};
+use ndarray_linalg::{FactorizeInto, LUFactorized, Norm, Solve};
 use num_complex::Complex;
 use rand::Rng;
+use rand_chacha::ChaCha12Rng;
+use rand_distr::StandardNormal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    distribution::{StandardComplexNormal, VMatrix},
+    ensemble::{trajectory_rng, EnsembleAccumulator, ENSEMBLE_CHUNK_SIZE},
+    sse_system::{
+        FullNoise, HybridNoise, Noise, NoiseConvention, SSESystem, Tensor, TimeDependentHamiltonian,
+    },
+    system::{ComplexScalar, SDEStep, SDESystem},
+};
+
+/// An error encountered while integrating a system
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum SolverError {
+    /// The norm of the state grew beyond the allowed maximum after `step` steps.
+    /// This usually indicates `dt` is too large for the system.
+    Unstable { step: usize, norm: f64 },
+    /// The norm of the state grows at `rate` after `step` steps of the deterministic evolution.
+    /// This usually indicates a sign or convention error in the operators.
+    NonContractive { step: usize, rate: f64 },
+    /// The configuration of the solve is invalid, for the given reason
+    InvalidConfiguration(&'static str),
+    /// The energy `<H>` of a closed system drifted by `drift` after `step` steps.
+    /// This usually indicates `dt` is too large for the system.
+    EnergyDrift { step: usize, drift: f64 },
+    /// The state had (near) zero norm after `step` steps, so it could not be renormalized.
+    /// See [`ZERO_NORM_TOLERANCE`].
+    ZeroNorm { step: usize },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::Unstable { step, norm } => write!(
+                f,
+                "state norm {norm} exceeded the maximum after {step} steps, try reducing dt"
+            ),
+            SolverError::NonContractive { step, rate } => write!(
+                f,
+                "state norm grows at rate {rate} after {step} steps without noise, \
+                check the sign of the hamiltonian and the L -> iL convention of the noise"
+            ),
+            SolverError::InvalidConfiguration(reason) => {
+                write!(f, "invalid configuration: {reason}")
+            }
+            SolverError::EnergyDrift { step, drift } => write!(
+                f,
+                "energy of the closed system drifted by {drift} after {step} steps, try reducing dt"
+            ),
+            SolverError::ZeroNorm { step } => {
+                write!(f, "state has zero norm after {step} steps")
+            }
+        }
+    }
+}
+
+impl Error for SolverError {}
+
+/// Check that the deterministic (noise-off) evolution of `initial_state` does not
+/// increase the norm of the state over the first `n_step` steps.
+///
+/// For a hermitian hamiltonian the norm is non-increasing, so growth usually indicates
+/// a sign error in an effective (non-hermitian) hamiltonian or collapse operator.
+/// This is opt-in, as a system with gain can legitimately increase the norm.
+/// The instantaneous rate `d ln||psi||^2 / dt = 2 Re <psi|a(psi)> / <psi|psi>` is checked
+/// at each step, so the check is unaffected by the norm error of the euler integration.
+///
+/// # Errors
+///
+/// Returns [`SolverError::NonContractive`] if the rate exceeds `1e-10`
+pub fn check_contractive<T: SDESystem>(
+    initial_state: &Array1<Complex<f64>>,
+    system: &T,
+    n_step: usize,
+    dt: f64,
+) -> Result<(), SolverError> {
+    let mut current = initial_state.to_owned();
+    let mut current_t = 0f64;
+    for step in 0..n_step {
+        let drift = system.get_coherent_step(Complex { re: 1f64, im: 0f64 }, &current, current_t);
+        let mut overlap = Complex::<f64>::default();
+        for (psi, a) in current.iter().zip(drift.iter()) {
+            overlap += psi.conj() * a;
+        }
+        let rate = 2f64 * overlap.re / current.norm_l2().powi(2);
+        if rate > 1e-10 {
+            return Err(SolverError::NonContractive { step, rate });
+        }
+
+        current = &current + &(drift * Complex { re: dt, im: 0f64 });
+        current_t += dt;
+    }
+    Ok(())
+}
+
+/// Solve a closed system (with no noise) using the solver `V`, also returning
+/// the energy `<H>` of the normalized state at each output time.
+///
+/// Without noise the dynamics is pure Schrödinger evolution, and the energy of a
+/// constant hamiltonian is conserved. The integration error of the solver causes
+/// the energy to drift, so this returns [`SolverError::EnergyDrift`] as soon as
+/// `|<H>(t) - <H>(0)|` exceeds `max_drift`.
+///
+/// # Errors
+///
+/// Returns [`SolverError::InvalidConfiguration`] if the system has any noise,
+/// or [`SolverError::EnergyDrift`] if the energy drifts by more than `max_drift` (or is NaN)
+///
+/// # Panics
+///
+/// Will panic if the hamiltonian cannot be applied to a state of this length
+pub fn solve_with_energy<V: Solver<SSESystem<H, N>>, H: Tensor, N: Noise>(
+    initial_state: &Array1<Complex<f64>>,
+    system: &SSESystem<H, N>,
+    n: usize,
+    step: usize,
+    dt: f64,
+    max_drift: f64,
+) -> Result<(Array2<Complex<f64>>, Array1<f64>), SolverError> {
+    if !system.noise.is_empty() {
+        return Err(SolverError::InvalidConfiguration(
+            "energy is only conserved for a system without noise",
+        ));
+    }
+    let energy = |state: &Array1<Complex<f64>>| {
+        system.hamiltonian.expectation(state).re / state.norm_l2().powi(2)
+    };
+
+    let mut out = Array2::zeros([0, initial_state.len()]);
+    let mut energies = Array1::zeros([n]);
+    let mut current = initial_state.to_owned();
+    let mut current_t = 0f64;
+    let initial_energy = energy(initial_state);
+    let mut rng = rand::thread_rng();
+    for step_n in 0..n {
+        if step_n > 0 {
+            current = V::integrate(&current, system, &mut current_t, step, dt, &mut rng);
+        }
+        energies[step_n] = energy(&current);
+        let drift = (energies[step_n] - initial_energy).abs();
+        if drift.is_nan() || drift > max_drift {
+            return Err(SolverError::EnergyDrift {
+                step: step_n * step,
+                drift,
+            });
+        }
+        out.push_row(current.view()).unwrap();
+    }
+
+    Ok((out, energies))
+}
+
+/// States with a smaller L2 norm are treated as zero when renormalizing,
+/// as dividing by the norm would amplify their rounding error to order one.
+pub const ZERO_NORM_TOLERANCE: f64 = 1e-12;
+
+/// The number of steps in each window of [`solve_steady_state`]
+const STEADY_STATE_WINDOW: usize = 1000;
+
+/// Estimate the steady state density matrix of `system` by averaging `|\psi><\psi|`
+/// over a long trajectory solved using the solver `V`.
+///
+/// The trajectory is split into windows of 1000 steps, and the state is normalized
+/// after each step. The first window is discarded to remove the initial transient,
+/// and the average is accumulated until adding a window changes every element by less
+/// than `tol`. This relies on the time average of a single trajectory being equal to
+/// the ensemble average, which holds if the steady state is unique.
+///
+/// Returns the steady state and the number of states it was averaged over.
+///
+/// # Panics
+///
+/// Will panic if `tol` is not positive
+#[allow(clippy::cast_precision_loss)]
+pub fn solve_steady_state<V: Solver<T>, T: SDESystem, R: Rng + ?Sized>(
+    initial_state: &Array1<Complex<f64>>,
+    system: &T,
+    dt: f64,
+    rng: &mut R,
+    tol: f64,
+) -> (Array2<Complex<f64>>, usize) {
+    assert!(tol > 0f64);
+    let n_states = initial_state.len();
+    let mut current = initial_state / Complex::from(initial_state.norm_l2());
+    let mut current_t = 0f64;
+    let mut integrate_window = |current: &mut Array1<Complex<f64>>, rho: &mut Array2<_>| {
+        for _n in 0..STEADY_STATE_WINDOW {
+            *current = V::step(current, system, current_t, dt, rng);
+            *current /= Complex::from(current.norm_l2());
+            current_t += dt;
+
+            let column = current.view().insert_axis(ndarray::Axis(1));
+            let row = current.map(Complex::conj).insert_axis(ndarray::Axis(0));
+            *rho += &column.dot(&row);
+        }
+    };
+
+    let mut window = Array2::zeros([n_states, n_states]);
+    integrate_window(&mut current, &mut window);
+
+    let mut total = Array2::<Complex<f64>>::zeros([n_states, n_states]);
+    let mut n_samples = 0;
+    loop {
+        window.fill(Complex::default());
+        integrate_window(&mut current, &mut window);
+        let previous = &total / Complex::from(n_samples.max(1) as f64);
+        total += &window;
+        n_samples += STEADY_STATE_WINDOW;
+
+        let average = &total / Complex::from(n_samples as f64);
+        let change = (&average - &previous)
+            .iter()
+            .map(|d| d.norm())
+            .fold(0f64, f64::max);
+        if n_samples > STEADY_STATE_WINDOW && change < tol {
+            return (average, n_samples);
+        }
+    }
+}
+
+/// Calculate the two time correlation `<A(t + \tau) B(t)>` at `\tau = k * step * dt`,
+/// for `k` in `0..n`, where `state` is the (pure) state `|\psi(t)>` of the system.
+///
+/// By the quantum regression theorem this is `Tr[A e^{\mathcal{L} \tau}(B |\psi><\psi|)]`.
+/// The operator `B |\psi><\psi|` is split into the four pure states
+/// `|\chi_k> = B |\psi> + i^k |\psi>` using `|x><y| = 1/4 \sum_k i^k |x + i^k y><x + i^k y|`,
+/// and each is evolved with [`Solver::solve_ensemble`] using `n_trajectories` trajectories.
+/// The correlation is only recovered by the ensemble average, so the error falls
+/// as `1 / \sqrt{n_trajectories}`, and the system must be time independent.
+#[allow(clippy::too_many_arguments)]
+pub fn two_time_correlation<V: Solver<T>, T: SDESystem, A: Tensor, B: Tensor, R: Rng + ?Sized>(
+    system: &T,
+    state: &Array1<Complex<f64>>,
+    a: &A,
+    b: &B,
+    n_trajectories: usize,
+    n: usize,
+    step: usize,
+    dt: f64,
+    rng: &mut R,
+) -> Array1<Complex<f64>> {
+    let state = state / Complex::from(state.norm_l2());
+    let b_state = b.dot(&state);
+
+    let mut out = Array1::zeros([n]);
+    let mut phase = Complex::from(1f64);
+    for _k in 0..4 {
+        let chi = &b_state + &(&state * phase);
+        let norm = chi.norm_l2();
+        if norm > 0f64 {
+            let ensemble = V::solve_ensemble(
+                &(chi / Complex::from(norm)),
+                system,
+                std::slice::from_ref(a),
+                n_trajectories,
+                n,
+                step,
+                dt,
+                rng,
+            );
+            out.scaled_add(phase * (0.25 * norm * norm), &ensemble.mean().column(0));
+        }
+        phase *= Complex::i();
+    }
+    out
+}
+
+/// An estimate of the resources required by a solve, as returned by [`validate_only`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValidationReport {
+    pub n_states: usize,
+    pub n_incoherent: usize,
+    /// The total number of steps taken by the solve
+    pub n_steps: usize,
+    /// The size of the output of `solve`, in bytes
+    pub output_bytes: usize,
+    /// The time taken to evaluate a single step of the system, in seconds.
+    /// Note this is a single (noisy) measurement, and a solver may evaluate
+    /// the system several times per step.
+    pub seconds_per_step: f64,
+}
+
+impl ValidationReport {
+    /// A (rough) estimate of the runtime of the solve, in seconds
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn estimated_seconds(&self) -> f64 {
+        self.n_steps as f64 * self.seconds_per_step
+    }
+}
+
+/// Options controlling the output of [`Solver::solve_with_options`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SolveOptions {
+    /// Divide the state by its L2 norm after each output block.
+    ///
+    /// This only corrects the norm at the output times, the state is integrated
+    /// unnormalized between outputs so the trajectory depends on `step`.
+    /// With renormalization off the natural norm drift of the integration is kept,
+    /// which for the linear SSE (or a large `dt`) can underflow or overflow over long times.
+    /// Defaults to `false`, matching [`Solver::solve`].
+    pub renormalize: bool,
+    /// Divide the state by its L2 norm every `renorm_every` steps, independent of the output.
+    ///
+    /// Frequent renormalization stops the norm drifting far from one between corrections,
+    /// which biases the nonlinear SSE, at the cost of an extra norm per renormalization.
+    /// The count continues across output blocks, so the trajectory does not depend on `step`.
+    /// Defaults to `0`, which disables renormalization between outputs.
+    pub renorm_every: usize,
+}
+
+/// Validate the configuration of a solve, without integrating the system.
+///
+/// This checks that `dt` and the initial state are finite, that the system can be applied
+/// to the initial state and gives a finite step, and that the deterministic evolution is contractive
+/// (see [`check_contractive`]). The output array is not allocated.
+///
+/// # Errors
+///
+/// Returns [`SolverError::InvalidConfiguration`] describing the first problem found,
+/// or [`SolverError::NonContractive`] if the norm of the state grows without noise
+///
+/// # Panics
+///
+/// Will panic if the operators of the system cannot be applied to a state of this length
+pub fn validate_only<T: SDESystem>(
+    initial_state: &Array1<Complex<f64>>,
+    system: &T,
+    n: usize,
+    step: usize,
+    dt: f64,
+) -> Result<ValidationReport, SolverError> {
+    if !(dt.is_finite() && dt >= 0f64) {
+        return Err(SolverError::InvalidConfiguration(
+            "dt must be finite and non-negative",
+        ));
+    }
+    if initial_state.is_empty() {
+        return Err(SolverError::InvalidConfiguration("initial state is empty"));
+    }
+    if !initial_state.iter().all(|s| s.is_finite()) {
+        return Err(SolverError::InvalidConfiguration(
+            "initial state is not finite",
+        ));
+    }
+    if initial_state.norm_l2() == 0f64 {
+        return Err(SolverError::InvalidConfiguration("initial state is zero"));
+    }
+
+    let n_incoherent = system.n_incoherent();
+    let probe = SDEStep {
+        coherent: Complex { re: dt, im: 0f64 },
+        incoherent: vec![
+            Complex {
+                re: dt.sqrt(),
+                im: 0f64
+            };
+            n_incoherent
+        ],
+    };
+    let start = std::time::Instant::now();
+    let probe_step = system.get_step(&probe, initial_state, 0f64);
+    let seconds_per_step = start.elapsed().as_secs_f64();
+
+    if probe_step.len() != initial_state.len() {
+        return Err(SolverError::InvalidConfiguration(
+            "system does not preserve the dimension of the state",
+        ));
+    }
+    if !probe_step.iter().all(|s| s.is_finite()) {
+        return Err(SolverError::InvalidConfiguration(
+            "system gives a step which is not finite",
+        ));
+    }
+    check_contractive(initial_state, system, 1, dt)?;
+
+    Ok(ValidationReport {
+        n_states: initial_state.len(),
+        n_incoherent,
+        n_steps: n.saturating_sub(1) * step,
+        output_bytes: n * initial_state.len() * std::mem::size_of::<Complex<f64>>(),
+        seconds_per_step,
+    })
+}
+
+/// A checkpoint of a solve, from which the integration can be resumed
+/// using [`Solver::solve_resumable`].
+///
+/// The rng is stored alongside the state, so a resumed solve draws the same
+/// random increments as an uninterrupted one. The default [`ChaCha12Rng`]
+/// can be serialized with the `serde` feature.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SolverState<S = Complex<f64>, R = ChaCha12Rng> {
+    pub state: Array1<S>,
+    pub t: f64,
+    pub rng: R,
+}
+
+/// Scratch buffers which are reused between steps by [`Solver::integrate_with_workspace`],
+/// to avoid allocating the random increments and the output of each step.
+pub struct StepWorkspace<S = Complex<f64>> {
+    step: SDEStep<S>,
+    out: Array1<S>,
+}
+
+impl<S: ComplexScalar> Default for StepWorkspace<S> {
+    fn default() -> Self {
+        Self {
+            step: SDEStep {
+                coherent: S::zero(),
+                incoherent: Vec::new(),
+            },
+            out: Array1::zeros(0),
+        }
+    }
+}
+
+pub trait Solver<T: SDESystem<S>, S: ComplexScalar = Complex<f64>> {
+    /// The convention of the SDE which the solver converges to as `dt -> 0`
+    const CONVENTION: NoiseConvention = NoiseConvention::Ito;
+
+    /// The order of the global error of the deterministic (noise free) evolution.
+    ///
+    /// This is used by [`RichardsonSolver`] to choose the extrapolation coefficient.
+    /// The coarse and fine steps of the extrapolation draw independent noise, so only
+    /// the deterministic error cancels, and the coefficient must use this order rather
+    /// than [`Solver::STRONG_ORDER`] or [`Solver::WEAK_ORDER`].
+    const ORDER: u32 = 1;
+
+    /// The expected strong order of convergence, the slope of the error of
+    /// individual trajectories against `dt` on a log-log plot.
+    ///
+    /// For a system without noise the error is that of the deterministic evolution,
+    /// so the observed slope is [`Solver::ORDER`]. This is not used by the solvers.
+    /// In particular [`Solver::integrate_adaptive`] always takes euler steps,
+    /// so its error estimate does not depend on the order of `Self`.
+    const STRONG_ORDER: f64 = 0.5;
+
+    /// The expected weak order of convergence, the slope of the error of
+    /// averaged observables against `dt` on a log-log plot.
+    ///
+    /// As for [`Solver::STRONG_ORDER`] this reduces to [`Solver::ORDER`] for a system without noise,
+    /// and is not used by the solvers.
+    const WEAK_ORDER: f64 = 1.0;
+
+    /// Take a single step, drawing any random increments from `rng`
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S>;
+
+    fn integrate<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        current_t: &mut f64,
+        n_step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        let mut out = state.clone();
+        for _n in 0..n_step {
+            out = Self::step(&out, system, *current_t, dt, rng);
+            *current_t += dt;
+        }
+        out
+    }
+
+    /// As [`Solver::integrate`], reusing the buffers in `workspace` between steps.
+    ///
+    /// Solvers without an allocation-free step ignore the workspace.
+    fn integrate_with_workspace<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        current_t: &mut f64,
+        n_step: usize,
+        dt: f64,
+        rng: &mut R,
+        workspace: &mut StepWorkspace<S>,
+    ) -> Array1<S> {
+        let _ = workspace;
+        Self::integrate(state, system, current_t, n_step, dt, rng)
+    }
+
+    /// Solve the system, returning the state at `n` output times.
+    ///
+    /// The output has exactly `n` rows, where row `k` is the state at `t = k * step * dt`.
+    /// Row 0 is always `initial_state`, which is not normalized.
+    fn solve(initial_state: &Array1<S>, system: &T, n: usize, step: usize, dt: f64) -> Array2<S> {
+        Self::solve_with_rng(initial_state, system, n, step, dt, &mut rand::thread_rng())
+    }
+
+    /// Solve the system, drawing all random increments from `rng`.
+    ///
+    /// Using a seeded rng (ie `StdRng::seed_from_u64`) the same seed gives an identical trajectory.
+    fn solve_with_rng<R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array2<S> {
+        Self::solve_from_with_rng(initial_state, system, 0f64, n, step, dt, rng)
+    }
+
+    /// Solve the system as in [`Solver::solve`], where `initial_state` is the state at `t_start`.
+    ///
+    /// This only matters for a time dependent system, for example to continue a previous solve.
+    /// Row `k` of the output is the state at `t = t_start + k * step * dt`.
+    fn solve_from(
+        initial_state: &Array1<S>,
+        system: &T,
+        t_start: f64,
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> Array2<S> {
+        Self::solve_from_with_rng(
+            initial_state,
+            system,
+            t_start,
+            n,
+            step,
+            dt,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Solve the system as in [`Solver::solve_with_rng`], where `initial_state` is the state at `t_start`
+    fn solve_from_with_rng<R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        t_start: f64,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array2<S> {
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = t_start;
+        for step_n in 0..n {
+            if step_n > 0 {
+                current = Self::integrate(&current, system, &mut current_t, step, dt, rng);
+            }
+            out.push_row(current.view()).unwrap();
+        }
+        debug_assert_eq!(out.nrows(), n);
+
+        out
+    }
+
+    /// Solve the system from `t_start`, storing the state only at the given output `times`.
+    ///
+    /// The system is integrated with a fixed `dt`, and row `k` of the output is the state
+    /// at the step nearest to `times[k]`. This allows for a non-uniform (ie logarithmic)
+    /// sampling of a long solve.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolverError::InvalidConfiguration`] if `dt` is not positive and finite,
+    /// if any time is not finite, if `times` is not sorted, or if a time is before `t_start`
+    fn solve_at_times(
+        initial_state: &Array1<S>,
+        system: &T,
+        t_start: f64,
+        times: &[f64],
+        dt: f64,
+    ) -> Result<Array2<S>, SolverError> {
+        Self::solve_at_times_with_rng(
+            initial_state,
+            system,
+            t_start,
+            times,
+            dt,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Solve the system as in [`Solver::solve_at_times`], drawing all random increments from `rng`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolverError::InvalidConfiguration`] if `dt` is not positive and finite,
+    /// if any time is not finite, if `times` is not sorted, or if a time is before `t_start`
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn solve_at_times_with_rng<R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        t_start: f64,
+        times: &[f64],
+        dt: f64,
+        rng: &mut R,
+    ) -> Result<Array2<S>, SolverError> {
+        if !dt.is_finite() || dt <= 0f64 {
+            return Err(SolverError::InvalidConfiguration(
+                "dt must be positive and finite",
+            ));
+        }
+        if !t_start.is_finite() || times.iter().any(|t| !t.is_finite()) {
+            return Err(SolverError::InvalidConfiguration("times must be finite"));
+        }
+        if times.windows(2).any(|w| w[1] < w[0]) {
+            return Err(SolverError::InvalidConfiguration("times must be sorted"));
+        }
+        if times.first().is_some_and(|t| *t < t_start) {
+            return Err(SolverError::InvalidConfiguration(
+                "times must not be before t_start",
+            ));
+        }
+
+        let mut out = Array2::zeros([times.len(), initial_state.len()]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = t_start;
+        let mut current_step = 0;
+        for (t, mut row) in times.iter().zip(out.outer_iter_mut()) {
+            let target_step = ((t - t_start) / dt).round() as usize;
+            current = Self::integrate(
+                &current,
+                system,
+                &mut current_t,
+                target_step - current_step,
+                dt,
+                rng,
+            );
+            current_step = target_step;
+            row.assign(&current);
+        }
+        Ok(out)
+    }
+
+    /// Solve the system as in [`Solver::solve`], passing the index, time and state at
+    /// each output point to `callback` rather than storing the states.
+    ///
+    /// Returning [`ControlFlow::Break`] from the callback ends the solve early.
+    /// The final state passed to the callback is returned.
+    fn solve_with_callback<F: FnMut(usize, f64, &Array1<S>) -> ControlFlow<()>>(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        mut callback: F,
+    ) -> Array1<S> {
+        let mut rng = rand::thread_rng();
+        let mut current = initial_state.to_owned();
+        let mut current_t = 0f64;
+        for step_n in 0..n {
+            if step_n > 0 {
+                current = Self::integrate(&current, system, &mut current_t, step, dt, &mut rng);
+            }
+            if callback(step_n, current_t, &current).is_break() {
+                break;
+            }
+        }
+        current
+    }
+
+    /// Integrate the system for `n` blocks of `step` steps from `checkpoint`,
+    /// passing the checkpoint at the end of each block to `callback`.
+    ///
+    /// The states passed to the callback are at `t = checkpoint.t + (k + 1) * step * dt`,
+    /// so the initial state is not included. Returning [`ControlFlow::Break`] from the
+    /// callback ends the solve early. The final checkpoint is returned.
+    fn solve_resumable<R: Rng, F: FnMut(&SolverState<S, R>) -> ControlFlow<()>>(
+        mut checkpoint: SolverState<S, R>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        mut callback: F,
+    ) -> SolverState<S, R> {
+        for _step_n in 0..n {
+            checkpoint.state = Self::integrate(
+                &checkpoint.state,
+                system,
+                &mut checkpoint.t,
+                step,
+                dt,
+                &mut checkpoint.rng,
+            );
+            if callback(&checkpoint).is_break() {
+                break;
+            }
+        }
+        checkpoint
+    }
+
+    /// Solve the system as in [`Solver::solve_with_rng`], configured by `options`
+    fn solve_with_options<R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        options: &SolveOptions,
+        rng: &mut R,
+    ) -> Array2<S> {
+        Self::solve_with_norms(initial_state, system, n, step, dt, options, rng).0
+    }
+
+    /// Solve the system as in [`Solver::solve_with_options`], returning an error rather than
+    /// panicking or producing a `NaN` state.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolverError::ZeroNorm`] if the initial state, or a state which is
+    /// renormalized during the solve, has a norm below [`ZERO_NORM_TOLERANCE`],
+    /// and [`SolverError::Unstable`] if the norm of a renormalized state is not finite.
+    /// Returns [`SolverError::InvalidConfiguration`] if a step of the system changes
+    /// the length of the state.
+    fn try_solve<R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        options: &SolveOptions,
+        rng: &mut R,
+    ) -> Result<Array2<S>, SolverError> {
+        let renormalize = |current: &mut Array1<S>, n_steps: usize| {
+            let norm = current.norm_l2();
+            let norm_f64: f64 = norm.into();
+            if !norm_f64.is_finite() {
+                return Err(SolverError::Unstable {
+                    step: n_steps,
+                    norm: norm_f64,
+                });
+            }
+            if norm_f64 < ZERO_NORM_TOLERANCE {
+                return Err(SolverError::ZeroNorm { step: n_steps });
+            }
+            *current /= S::from_real(norm);
+            Ok(())
+        };
+
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut current = initial_state.to_owned();
+        if Into::<f64>::into(current.norm_l2()) < ZERO_NORM_TOLERANCE {
+            return Err(SolverError::ZeroNorm { step: 0 });
+        }
+        let mut current_t = 0f64;
+        let mut n_steps = 0;
+        // The number of steps since the last renormalization
+        let mut n_since_renorm = 0;
+        for step_n in 0..n {
+            if step_n > 0 {
+                let mut remaining = step;
+                while remaining > 0 {
+                    let n_step = if options.renorm_every > 0 {
+                        remaining.min(options.renorm_every - n_since_renorm)
+                    } else {
+                        remaining
+                    };
+                    current = Self::integrate(&current, system, &mut current_t, n_step, dt, rng);
+                    remaining -= n_step;
+                    n_steps += n_step;
+                    n_since_renorm += n_step;
+                    if n_since_renorm == options.renorm_every {
+                        renormalize(&mut current, n_steps)?;
+                        n_since_renorm = 0;
+                    }
+                }
+                if options.renormalize {
+                    renormalize(&mut current, n_steps)?;
+                }
+            }
+            out.push_row(current.view()).map_err(|_| {
+                SolverError::InvalidConfiguration("a step changed the length of the state")
+            })?;
+        }
+
+        Ok(out)
+    }
+
+    /// Solve the system as in [`Solver::solve_with_options`], also returning the L2 norm
+    /// of the state at each output time.
+    ///
+    /// The norm is taken before the state is renormalized, so with renormalization on each
+    /// norm is the change in norm over a single output block. For the linear SSE this relates
+    /// to the weight of the trajectory, and a large change indicates `dt` is too large.
+    fn solve_with_norms<R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        options: &SolveOptions,
+        rng: &mut R,
+    ) -> (Array2<S>, Array1<S::Real>) {
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut norms = Array1::zeros([n]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = 0f64;
+        // The number of steps since the last renormalization
+        let mut n_since_renorm = 0;
+        for step_n in 0..n {
+            if step_n > 0 && options.renorm_every > 0 {
+                let mut remaining = step;
+                while remaining > 0 {
+                    let n_step = remaining.min(options.renorm_every - n_since_renorm);
+                    current = Self::integrate(&current, system, &mut current_t, n_step, dt, rng);
+                    remaining -= n_step;
+                    n_since_renorm += n_step;
+                    if n_since_renorm == options.renorm_every {
+                        current /= S::from_real(current.norm_l2());
+                        n_since_renorm = 0;
+                    }
+                }
+            } else if step_n > 0 {
+                current = Self::integrate(&current, system, &mut current_t, step, dt, rng);
+            }
+            let norm = current.norm_l2();
+            norms[step_n] = norm;
+            // The initial state is returned as given
+            if options.renormalize && step_n > 0 {
+                current /= S::from_real(norm);
+            }
+            out.push_row(current.view()).unwrap();
+        }
+
+        (out, norms)
+    }
+
+    /// Solve the system, storing only the expectation `<\psi|O|\psi>` of each observable
+    /// at each time, rather than the full state.
+    ///
+    /// The output has shape `[n, observables.len()]`. Note the state is not normalized.
+    fn solve_observables<O: Tensor<S>>(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> Array2<S> {
+        Self::solve_observables_with_rng(
+            initial_state,
+            system,
+            observables,
+            n,
+            step,
+            dt,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Solve the system as in [`Solver::solve_observables`], drawing all random increments from `rng`
+    fn solve_observables_with_rng<O: Tensor<S>, R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array2<S> {
+        let mut out = Array2::zeros([n, observables.len()]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = 0f64;
+        for (step_n, mut row) in out.outer_iter_mut().enumerate() {
+            if step_n > 0 {
+                current = Self::integrate(&current, system, &mut current_t, step, dt, rng);
+            }
+            for (o, observable) in row.iter_mut().zip(observables) {
+                *o = observable.expectation(&current);
+            }
+        }
+        out
+    }
+
+    /// Solve an ensemble of `n_trajectories` trajectories, accumulating the expectation
+    /// of each observable as in [`Solver::solve_observables`] without storing each trajectory.
+    ///
+    /// The accumulator holds the mean and standard error of the observables,
+    /// with shape `[n, observables.len()]`. Trajectory `i` is solved using
+    /// [`trajectory_rng`] with index `i`, and a master seed drawn from `rng`.
+    /// The master seed is the first `u64` drawn from `rng`, so the contribution of
+    /// a single trajectory can be reproduced using [`Solver::solve_ensemble_trajectory`].
+    #[allow(clippy::too_many_arguments)]
+    fn solve_ensemble<O: Tensor<S>, R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        n_trajectories: usize,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> EnsembleAccumulator<Ix2> {
+        let master_seed = rng.gen::<u64>();
+        // Trajectories are accumulated in chunks, to match the reduction of par_solve_ensemble
+        let mut out = EnsembleAccumulator::new([n, observables.len()]);
+        for start in (0..n_trajectories).step_by(ENSEMBLE_CHUNK_SIZE) {
+            out.merge(&Self::solve_ensemble_range(
+                initial_state,
+                system,
+                observables,
+                start..(start + ENSEMBLE_CHUNK_SIZE).min(n_trajectories),
+                master_seed,
+                n,
+                step,
+                dt,
+            ));
+        }
+        out
+    }
+
+    /// Solve an ensemble as in [`Solver::solve_ensemble`], distributing the
+    /// trajectories across the rayon thread pool.
+    ///
+    /// Each chunk of trajectories is accumulated separately, and the chunks are
+    /// then merged in order, so the result is identical to [`Solver::solve_ensemble`]
+    /// for the same `rng` regardless of the number of threads.
+    #[cfg(feature = "rayon")]
+    #[allow(clippy::too_many_arguments)]
+    fn par_solve_ensemble<O: Tensor<S> + Sync, R: Rng + ?Sized>(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        n_trajectories: usize,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> EnsembleAccumulator<Ix2>
+    where
+        T: Sync,
+        S: Sync,
+    {
+        use rayon::prelude::*;
+
+        let master_seed = rng.gen::<u64>();
+        let chunks = (0..n_trajectories)
+            .step_by(ENSEMBLE_CHUNK_SIZE)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|start| {
+                Self::solve_ensemble_range(
+                    initial_state,
+                    system,
+                    observables,
+                    start..(start + ENSEMBLE_CHUNK_SIZE).min(n_trajectories),
+                    master_seed,
+                    n,
+                    step,
+                    dt,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = EnsembleAccumulator::new([n, observables.len()]);
+        for chunk in &chunks {
+            out.merge(chunk);
+        }
+        out
+    }
+
+    /// Solve trajectory `index` of an ensemble with the given `master_seed`, returning its
+    /// contribution to [`Solver::solve_ensemble`] without solving any other trajectory.
+    ///
+    /// This allows an outlier found in a large ensemble to be reproduced exactly.
+    /// The full states of the trajectory are given by [`Solver::solve_with_rng`]
+    /// using [`trajectory_rng`].
+    #[allow(clippy::too_many_arguments)]
+    fn solve_ensemble_trajectory<O: Tensor<S>>(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        master_seed: u64,
+        index: usize,
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> Array2<S> {
+        Self::solve_observables_with_rng(
+            initial_state,
+            system,
+            observables,
+            n,
+            step,
+            dt,
+            &mut trajectory_rng(master_seed, index as u64),
+        )
+    }
+
+    /// Solve only the trajectories of an ensemble with index in `trajectory_range`,
+    /// where trajectory `i` is solved using [`trajectory_rng`] with `master_seed`.
+    ///
+    /// The accumulators of disjoint ranges can be combined using [`EnsembleAccumulator::merge`].
+    #[allow(clippy::too_many_arguments)]
+    fn solve_ensemble_range<O: Tensor<S>>(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        trajectory_range: Range<usize>,
+        master_seed: u64,
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> EnsembleAccumulator<Ix2> {
+        let mut out = EnsembleAccumulator::new([n, observables.len()]);
+        for index in trajectory_range {
+            out.push(&Self::solve_ensemble_trajectory(
+                initial_state,
+                system,
+                observables,
+                master_seed,
+                index,
+                n,
+                step,
+                dt,
+            ));
+        }
+        out
+    }
+
+    /// Integrate the system from `t_start` to `t_end` with an adaptive step size,
+    /// returning the final state and the number of steps taken.
+    ///
+    /// Each step is compared to two half steps, and the step is accepted only if the
+    /// difference between the full step and half step results is below `tolerance`.
+    /// When a step is accepted the result of the two half steps is kept, and `dt` is
+    /// grown or shrunk from the difference to keep the error per step below `tolerance`.
+    ///
+    /// To compare steps of different size the increments of the noise must be controlled,
+    /// so the steps are taken with the euler scheme (`Y_n+1 = Y_n + a dt + \sum_k b_k dW`)
+    /// rather than `Self::step`. When a step is rejected the noise increment is subdivided
+    /// using a brownian bridge, so the noise of the trajectory is independent of how
+    /// the interval is divided. Note the increments of a rejected step are never discarded,
+    /// so the step size only grows once the interval of a rejected step is complete.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `t_end < t_start`, or if `tolerance` is not positive
+    fn integrate_adaptive<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t_start: f64,
+        t_end: f64,
+        tolerance: f64,
+        rng: &mut R,
+    ) -> (Array1<S>, usize) {
+        assert!(t_end >= t_start);
+        assert!(tolerance > 0f64);
+        let euler_step = |state: &Array1<S>, t: f64, dt: f64, noise: &[S]| {
+            let step = SDEStep {
+                coherent: S::complex(dt, 0f64),
+                incoherent: noise.to_vec(),
+            };
+            state + system.get_step(&step, state, t)
+        };
+        let sample_noise = |rng: &mut R, dt: f64| {
+            rng.sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .map(|d| S::from_c64(d * dt.sqrt()))
+                .take(system.n_incoherent())
+                .collect::<Vec<_>>()
+        };
+        // Steps smaller than this are always accepted
+        let min_dt = 1e-12 * (t_end - t_start).max(1f64);
+
+        let mut current = state.to_owned();
+        let mut current_t = t_start;
+        let mut n_steps = 0;
+        let mut dt = (t_end - t_start) / 16f64;
+        // The (dt, noise) of the intervals which have already been sampled, the next interval is last
+        let mut pending = Vec::<(f64, Vec<S>)>::new();
+        while current_t < t_end {
+            let (step_dt, noise) = pending.pop().unwrap_or_else(|| {
+                let step_dt = dt.min(t_end - current_t);
+                (step_dt, sample_noise(rng, step_dt))
+            });
+
+            // Split the increment with a brownian bridge, W(t + dt / 2) = W(dt) / 2 + sqrt(dt / 4) Z
+            let first_noise = noise
+                .iter()
+                .zip(sample_noise(rng, 0.25 * step_dt))
+                .map(|(dw, z)| dw.mul_real(S::real(0.5)) + z)
+                .collect::<Vec<_>>();
+            let second_noise = noise
+                .iter()
+                .zip(&first_noise)
+                .map(|(dw, first)| *dw - *first)
+                .collect::<Vec<_>>();
+
+            let full = euler_step(&current, current_t, step_dt, &noise);
+            let half = euler_step(&current, current_t, 0.5 * step_dt, &first_noise);
+            let half = euler_step(
+                &half,
+                current_t + 0.5 * step_dt,
+                0.5 * step_dt,
+                &second_noise,
+            );
+
+            let error: f64 = (&full - &half).norm_l2().into();
+            if error <= tolerance || step_dt <= min_dt {
+                current = half;
+                current_t += step_dt;
+                n_steps += 1;
+                if pending.is_empty() {
+                    // The local error of the euler scheme is O(dt)
+                    let factor = if error == 0f64 {
+                        2f64
+                    } else {
+                        (0.9 * tolerance / error).clamp(0.2, 2f64)
+                    };
+                    dt = step_dt * factor;
+                }
+            } else {
+                pending.push((0.5 * step_dt, second_noise));
+                pending.push((0.5 * step_dt, first_noise));
+                dt = 0.5 * step_dt;
+            }
+        }
+        (current, n_steps)
+    }
+
+    /// Solve the system, returning the result as a flat, row-major buffer
+    /// alongside its shape `[n, n_states]`.
+    /// This is useful when passing the result across a FFI boundary.
+    fn solve_flat(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> (Vec<S>, [usize; 2]) {
+        let out = Self::solve(initial_state, system, n, step, dt);
+        let shape = [out.nrows(), out.ncols()];
+        // Only copies if the output is not already in row-major order
+        (out.as_standard_layout().into_owned().into_raw_vec(), shape)
+    }
+
+    /// Solve a system which is rebuilt before every step by calling `system_fn(t, state)`.
+    ///
+    /// This allows for fully general time dependence, and for systems
+    /// which depend on the current state (ie mean-field or self-consistent dynamics).
+    /// Since the system is rebuilt each step, `system_fn` returns an owned system.
+    fn solve_with_system_fn<F: Fn(f64, &Array1<S>) -> T>(
+        initial_state: &Array1<S>,
+        system_fn: F,
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> Array2<S> {
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = 0f64;
+        let mut rng = rand::thread_rng();
+        for step_n in 0..n {
+            if step_n > 0 {
+                for _n in 0..step {
+                    let system = system_fn(current_t, &current);
+                    current = Self::step(&current, &system, current_t, dt, &mut rng);
+                    current_t += dt;
+                }
+            }
+            out.push_row(current.view()).unwrap();
+        }
+
+        out
+    }
+
+    /// Integrate the system for `n_step` steps, saving the state whenever `trigger(state, t)`
+    /// changes from `false` to `true`, returning the times and states of each event.
+    ///
+    /// The time of each event is located within the step by bisecting the linear interpolation
+    /// between the states at either end of the step, so the error in the event time is `O(dt^2)`
+    /// for a smooth trajectory. At most one event is recorded per step, so a trigger which
+    /// fires and resets within a single step is missed.
+    fn solve_with_events<F: Fn(&Array1<S>, f64) -> bool>(
+        initial_state: &Array1<S>,
+        system: &T,
+        trigger: F,
+        n_step: usize,
+        dt: f64,
+    ) -> (Vec<f64>, Array2<S>) {
+        let mut times = Vec::new();
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = 0f64;
+        let mut was_triggered = trigger(&current, current_t);
+        let mut rng = rand::thread_rng();
+        for _n in 0..n_step {
+            let next = Self::step(&current, system, current_t, dt, &mut rng);
+            let is_triggered = trigger(&next, current_t + dt);
+
+            if is_triggered && !was_triggered {
+                let interpolate =
+                    |fraction: f64| &current + &((&next - &current) * S::complex(fraction, 0f64));
+                let (mut lower, mut upper) = (0f64, 1f64);
+                for _i in 0..32 {
+                    let middle = 0.5 * (lower + upper);
+                    if trigger(&interpolate(middle), current_t + middle * dt) {
+                        upper = middle;
+                    } else {
+                        lower = middle;
+                    }
+                }
+                times.push(current_t + upper * dt);
+                out.push_row(interpolate(upper).view()).unwrap();
+            }
+
+            was_triggered = is_triggered;
+            current = next;
+            current_t += dt;
+        }
+
+        (times, out)
+    }
+
+    /// Integrate the system from `t_start` until `predicate(<O>)` is true, returning the state
+    /// and the time at which the predicate was first satisfied.
+    ///
+    /// The expectation `<O>` is calculated for the normalized state after each step.
+    /// If the predicate is not satisfied within `max_steps` steps, the state and time
+    /// after the final step are returned. This is useful for first passage time studies.
+    fn integrate_until<O: Tensor<S>, F: Fn(f64) -> bool>(
+        state: &Array1<S>,
+        system: &T,
+        t_start: f64,
+        dt: f64,
+        observable: &O,
+        predicate: F,
+        max_steps: usize,
+    ) -> (Array1<S>, f64) {
+        let expectation = |state: &Array1<S>| {
+            let norm: f64 = state.norm_l2().into();
+            observable.expectation(state).re().into() / (norm * norm)
+        };
+
+        let mut current = state.to_owned();
+        let mut current_t = t_start;
+        let mut rng = rand::thread_rng();
+        for _n in 0..max_steps {
+            if predicate(expectation(&current)) {
+                break;
+            }
+            current = Self::step(&current, system, current_t, dt, &mut rng);
+            current_t += dt;
+        }
+        (current, current_t)
+    }
+
+    /// Solve the system, checking the norm of the state after every step.
+    ///
+    /// An unstable `dt` causes the norm to grow exponentially long before it overflows,
+    /// so this returns [`SolverError::Unstable`] as soon as the norm exceeds `max_norm`.
+    /// Note the norm of the linear (unnormalized) SSE can legitimately grow,
+    /// so `max_norm` should be chosen with the system in mind.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolverError::Unstable`] if the norm exceeds `max_norm` (or is NaN)
+    fn solve_monitored(
+        initial_state: &Array1<S>,
+        system: &T,
+        n: usize,
+        step: usize,
+        dt: f64,
+        max_norm: f64,
+    ) -> Result<Array2<S>, SolverError> {
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut current = initial_state.to_owned();
+        let mut current_t = 0f64;
+        let mut n_steps = 0;
+        let mut rng = rand::thread_rng();
+        for step_n in 0..n {
+            // The initial state is the first output, before any steps are taken
+            let n_block = if step_n == 0 { 0 } else { step };
+            for _n in 0..n_block {
+                current = Self::step(&current, system, current_t, dt, &mut rng);
+                current_t += dt;
+                n_steps += 1;
+
+                let norm: f64 = current.norm_l2().into();
+                if norm.is_nan() || norm > max_norm {
+                    return Err(SolverError::Unstable {
+                        step: n_steps,
+                        norm,
+                    });
+                }
+            }
+            out.push_row(current.view()).unwrap();
+        }
+
+        Ok(out)
+    }
+}
+
+pub struct EulerSolver {}
+
+impl EulerSolver {
+    /// Take a single euler step `Y_n+1 = Y_n + a dt + \sum_k b_k dW_k`,
+    /// using the given `increments` `dW_k` rather than drawing them from a rng.
+    ///
+    /// The increments should satisfy `<dW_k^* dW_k'> = \delta_kk' dt`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the number of increments does not match the number of incoherent terms
+    pub fn step_with_increments<S: ComplexScalar, T: SDESystem<S>>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        increments: &[S],
+    ) -> Array1<S> {
+        assert_eq!(increments.len(), system.n_incoherent());
+        let step = SDEStep {
+            coherent: S::complex(dt, 0f64),
+            incoherent: increments.to_vec(),
+        };
+        state + system.get_step(&step, state, t)
+    }
+
+    /// Solve an ensemble of `n_pairs` pairs of antithetic trajectories, where
+    /// the second trajectory of each pair uses the increments `-dW` of the first.
+    ///
+    /// Each sample of the accumulator is the average of the observables over a pair,
+    /// so the standard error accounts for the correlation between the trajectories.
+    /// Pair `i` is solved using [`trajectory_rng`] with index `i`, and a master seed drawn from `rng`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn solve_ensemble_antithetic<
+        S: ComplexScalar,
+        T: SDESystem<S>,
+        O: Tensor<S>,
+        R: Rng + ?Sized,
+    >(
+        initial_state: &Array1<S>,
+        system: &T,
+        observables: &[O],
+        n_pairs: usize,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> EnsembleAccumulator<Ix2> {
+        let master_seed = rng.gen::<u64>();
+        let sqrt_dt = dt.sqrt();
+        let half = S::from_real(S::real(0.5));
+
+        let mut out = EnsembleAccumulator::new([n, observables.len()]);
+        let mut sample = Array2::<S>::zeros([n, observables.len()]);
+        for index in 0..n_pairs {
+            let mut pair_rng = trajectory_rng(master_seed, index as u64);
+            let mut current = [initial_state.to_owned(), initial_state.to_owned()];
+            let mut current_t = 0f64;
+            for (step_n, mut row) in sample.outer_iter_mut().enumerate() {
+                if step_n > 0 {
+                    for _n in 0..step {
+                        let increments = (&mut pair_rng)
+                            .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                            .map(|d| S::from_c64(d * sqrt_dt))
+                            .take(system.n_incoherent())
+                            .collect::<Vec<_>>();
+                        let negated = increments.iter().map(|d| -*d).collect::<Vec<_>>();
+                        current = [
+                            Self::step_with_increments(
+                                &current[0],
+                                system,
+                                current_t,
+                                dt,
+                                &increments,
+                            ),
+                            Self::step_with_increments(
+                                &current[1],
+                                system,
+                                current_t,
+                                dt,
+                                &negated,
+                            ),
+                        ];
+                        current_t += dt;
+                    }
+                }
+                for (o, observable) in row.iter_mut().zip(observables) {
+                    *o = (observable.expectation(&current[0])
+                        + observable.expectation(&current[1]))
+                        * half;
+                }
+            }
+            out.push(&sample);
+        }
+        out
+    }
+
+    /// Solve the system as in [`Solver::solve_with_rng`], also returning the
+    /// continuous homodyne measurement record of each operator.
+    ///
+    /// The record of source `k` is `dQ_k = <L_k + L_k^\dagger> dt + dw_k`, where `L_k` includes
+    /// the measurement phase and `dw_k = sqrt(2) Re(dW_k)` is a real wiener increment with
+    /// variance `dt`, taken from the complex increment `dW_k` of the step. The record has shape `[n, n_operators]`,
+    /// where `record[i]` holds `Q_k` accumulated from `t = 0` to `t = i * step * dt`.
+    pub fn solve_with_measurement_record<H: Tensor, T: Tensor, U: Tensor, R: Rng + ?Sized>(
+        initial_state: &Array1<Complex<f64>>,
+        system: &SSESystem<H, FullNoise<T, U>>,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> (Array2<Complex<f64>>, Array2<f64>) {
+        let sqrt_dt = dt.sqrt();
+        let n_operators = system.n_incoherent();
+        let mut out = Array2::zeros([n, initial_state.len()]);
+        let mut record = Array2::zeros([n, n_operators]);
+        let mut current = initial_state.to_owned();
+        let mut accumulated = Array1::<f64>::zeros([n_operators]);
+        let mut current_t = 0f64;
+        for (step_n, (mut out, mut record)) in out
+            .outer_iter_mut()
+            .zip(record.outer_iter_mut())
+            .enumerate()
+        {
+            if step_n > 0 {
+                for _n in 0..step {
+                    let increments = (&mut *rng)
+                        .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                        .map(|d| d * sqrt_dt)
+                        .take(n_operators)
+                        .collect::<Vec<_>>();
+                    let expectations = system.noise.expectations(&current);
+                    for ((q, expectation), dw) in
+                        accumulated.iter_mut().zip(&expectations).zip(&increments)
+                    {
+                        *q += 2f64 * expectation.re * dt + std::f64::consts::SQRT_2 * dw.re;
+                    }
+                    current =
+                        Self::step_with_increments(&current, system, current_t, dt, &increments);
+                    current_t += dt;
+                }
+            }
+            out.assign(&current);
+            record.assign(&accumulated);
+        }
+        (out, record)
+    }
+
+    /// Solve each column of `initial_states` as in [`Solver::solve_with_rng`],
+    /// with every column sharing the same noise realization.
+    ///
+    /// The hamiltonian is applied to all columns with a single matrix product,
+    /// which is much faster than solving each state separately. The output has
+    /// shape `[n, n_states, n_columns]`, where `out[k]` holds the states at `t = k * step * dt`.
+    pub fn solve_batch<
+        S: ComplexScalar,
+        H: TimeDependentHamiltonian<S> + Dot<Array2<S>, Output = Array2<S>>,
+        N: Noise<S>,
+        R: Rng + ?Sized,
+    >(
+        initial_states: &Array2<S>,
+        system: &SSESystem<H, N>,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array3<S> {
+        let sqt_dt = dt.sqrt();
+        let shape = initial_states.shape();
+        let mut out = Array3::zeros([n, shape[0], shape[1]]);
+        let mut current = initial_states.to_owned();
+        let mut current_t = 0f64;
+        for (step_n, mut row) in out.outer_iter_mut().enumerate() {
+            if step_n > 0 {
+                for _n in 0..step {
+                    let increments = SDEStep {
+                        coherent: S::complex(dt, 0f64),
+                        incoherent: rng
+                            .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                            .map(|d| S::from_c64(d * sqt_dt))
+                            .take(system.n_incoherent())
+                            .collect(),
+                    };
+                    current = &current + &system.get_step_batch(&increments, &current, current_t);
+                    current_t += dt;
+                }
+            }
+            row.assign(&current);
+        }
+        out
+    }
+
+    /// Take a single euler step, updating `state` in place.
+    ///
+    /// The result is identical to [`Solver::step`], but the increments and the
+    /// output are written into the buffers of `workspace`.
+    pub fn step_in_place<S: ComplexScalar, T: SDESystem<S>, R: Rng + ?Sized>(
+        state: &mut Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+        workspace: &mut StepWorkspace<S>,
+    ) {
+        let sqt_dt = dt.sqrt();
+        workspace.step.coherent = S::complex(dt, 0f64);
+        workspace.step.incoherent.clear();
+        workspace.step.incoherent.extend(
+            rng.sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .map(|d| S::from_c64(d * sqt_dt))
+                .take(system.n_incoherent()),
+        );
+
+        let parts = system.get_parts(state, t);
+        T::get_step_from_parts_into(&parts, &workspace.step, &mut workspace.out);
+        drop(parts);
+
+        workspace.out += &*state;
+        std::mem::swap(state, &mut workspace.out);
+    }
+}
+
+impl<S: ComplexScalar, T: SDESystem<S>> Solver<T, S> for EulerSolver {
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        // The basic euler method
+        // Y_n+1 = Y_n + a dt + \sum_k b_k dW
+        // where dW are normalized gaussian random variables,  <dW_k* dW_k'> = dt
+
+        // Without noise only the coherent evolution is required
+        if system.n_incoherent() == 0 {
+            return state + system.get_coherent_step(S::complex(dt, 0f64), state, t);
+        }
+
+        let sqt_dt = dt.sqrt();
+        let step = SDEStep {
+            coherent: S::complex(dt, 0f64),
+            incoherent: rng
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .map(|d| S::from_c64(d * sqt_dt))
+                .take(system.n_incoherent())
+                .collect(),
+        };
+
+        state + system.get_step(&step, state, t)
+    }
+
+    fn integrate<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        current_t: &mut f64,
+        n_step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        Self::integrate_with_workspace(
+            state,
+            system,
+            current_t,
+            n_step,
+            dt,
+            rng,
+            &mut StepWorkspace::default(),
+        )
+    }
+
+    fn integrate_with_workspace<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        current_t: &mut f64,
+        n_step: usize,
+        dt: f64,
+        rng: &mut R,
+        workspace: &mut StepWorkspace<S>,
+    ) -> Array1<S> {
+        let mut out = state.clone();
+        for _n in 0..n_step {
+            Self::step_in_place(&mut out, system, *current_t, dt, rng, workspace);
+            *current_t += dt;
+        }
+        out
+    }
+}
+
+pub struct NormalizedEulerSolver {}
+
+impl<S: ComplexScalar, T: SDESystem<S>> Solver<T, S> for NormalizedEulerSolver {
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        let mut out = EulerSolver::step(state, system, t, dt, rng);
+        // Normalize the state
+        out /= S::from_real(out.norm_l2());
+        out
+    }
+}
+
+/// Evolves a system in imaginary time `dt -> -i dt`, ie `|\psi> -> exp(-H dt) |\psi>`,
+/// normalizing the state after each step.
+///
+/// Components of the state decay at a rate given by their energy, so the state
+/// relaxes to the ground state of the hamiltonian. The noise of the system is not used,
+/// so the system should have no incoherent terms.
+pub struct ImaginaryTimeSolver {}
+
+impl ImaginaryTimeSolver {
+    /// Relax `initial_state` for `n_step` steps of size `dt`, returning the
+    /// final (normalized) state and its energy `<\psi|H|\psi>`.
+    ///
+    /// The initial state must overlap with the ground state.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the system has any incoherent terms
+    pub fn ground_state<T: SDESystem>(
+        initial_state: &Array1<Complex<f64>>,
+        system: &T,
+        n_step: usize,
+        dt: f64,
+    ) -> (Array1<Complex<f64>>, f64) {
+        let mut current_t = 0f64;
+        let state = Self::integrate(
+            initial_state,
+            system,
+            &mut current_t,
+            n_step,
+            dt,
+            &mut rand::thread_rng(),
+        );
+        // -i (i) H |\psi> = H |\psi>
+        let h_state = system.get_coherent_step(Complex { re: 0f64, im: 1f64 }, &state, current_t);
+        let energy = state
+            .iter()
+            .zip(h_state.iter())
+            .map(|(s, h)| s.conj() * h)
+            .sum::<Complex<f64>>();
+        (state, energy.re)
+    }
+}
+
+impl<S: ComplexScalar, T: SDESystem<S>> Solver<T, S> for ImaginaryTimeSolver {
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        _rng: &mut R,
+    ) -> Array1<S> {
+        assert_eq!(
+            system.n_incoherent(),
+            0,
+            "Imaginary time evolution requires no noise"
+        );
+        // -i (-i dt) H |\psi> = -dt H |\psi>
+        let mut out = state + &system.get_coherent_step(S::complex(0f64, -dt), state, t);
+        out /= S::from_real(out.norm_l2());
+        out
+    }
+}
 
-use crate::{
-    distribution::{StandardComplexNormal, VMatrix},
-    system::{SDEStep, SDESystem},
-};
+/// A semi-implicit euler solver for systems with strong dissipation.
+///
+/// The linear drift `D` (see [`SSESystem::drift_operator`]) is treated implicitly, solving
+/// `(I - dt D) |\psi_{n+1}> = |\psi_n> + \Delta_n - dt D |\psi_n>` where `\Delta_n` is the
+/// increment of an [`EulerSolver`] step. The remaining terms are treated explicitly.
+///
+/// Since the system is time independent the LU factorization of `I - dt D` can be reused
+/// for every step. [`Solver::integrate`] and [`Solver::solve`] factorize once per call,
+/// however [`Solver::step`] must factorize on every call, which is `O(N^3)`. When stepping
+/// repeatedly, for example from a custom integration loop, use [`ImplicitEulerSolver::prepare`].
+///
+/// # Panics
+///
+/// The [`Solver`] methods panic if `I - dt D` is singular, which is possible for a
+/// non-hermitian hamiltonian or a large `dt`. Use [`ImplicitEulerSolver::prepare`]
+/// to handle this as an error.
+pub struct ImplicitEulerSolver {}
+
+type DenseSSESystem =
+    SSESystem<Array2<Complex<f64>>, FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>>>;
 
-pub trait Solver<T: SDESystem> {
-    fn step(state: &Array1<Complex<f64>>, system: &T, t: f64, dt: f64) -> Array1<Complex<f64>>;
+/// An [`ImplicitEulerSolver`] for a fixed system and `dt`, which holds the drift
+/// and the LU factorization of `I - dt D` so they are computed only once.
+pub struct PreparedImplicitEuler {
+    drift: Array2<Complex<f64>>,
+    factorized: LUFactorized<OwnedRepr<Complex<f64>>>,
+    dt: f64,
+}
 
-    fn integrate(
+impl PreparedImplicitEuler {
+    /// Take a single step of the solver.
+    ///
+    /// `system` must be the system this solver was prepared for.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the length of `state` does not match the system
+    pub fn step<R: Rng + ?Sized>(
+        &self,
         state: &Array1<Complex<f64>>,
-        system: &T,
+        system: &DenseSSESystem,
+        t: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        let dt = self.dt;
+        let sqt_dt = dt.sqrt();
+        let step = SDEStep {
+            coherent: Complex { re: dt, im: 0f64 },
+            incoherent: rng
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .map(|d| d * sqt_dt)
+                .take(system.n_incoherent())
+                .collect(),
+        };
+
+        let explicit = state + &system.get_step(&step, state, t) - &(self.drift.dot(state) * dt);
+        self.factorized
+            .solve_into(explicit)
+            .expect("I - dt D has already been factorized")
+    }
+
+    /// Take `n_step` steps of the solver, as in [`Solver::integrate`].
+    ///
+    /// `system` must be the system this solver was prepared for.
+    pub fn integrate<R: Rng + ?Sized>(
+        &self,
+        state: &Array1<Complex<f64>>,
+        system: &DenseSSESystem,
         current_t: &mut f64,
         n_step: usize,
-        dt: f64,
+        rng: &mut R,
     ) -> Array1<Complex<f64>> {
         let mut out = state.clone();
         for _n in 0..n_step {
-            out = Self::step(&out, system, *current_t, dt);
-            *current_t += dt;
+            out = self.step(&out, system, *current_t, rng);
+            *current_t += self.dt;
         }
         out
     }
-    #[allow(clippy::cast_precision_loss)]
-    fn solve(
+}
+
+impl ImplicitEulerSolver {
+    /// Assemble the drift of `system`, and factorize `I - dt D` for a step of `dt`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SolverError::InvalidConfiguration`] if `I - dt D` is singular
+    pub fn prepare(system: &DenseSSESystem, dt: f64) -> Result<PreparedImplicitEuler, SolverError> {
+        let drift = system.drift_operator();
+        let implicit = Array2::eye(drift.nrows()) - &drift * dt;
+        let factorized = implicit
+            .factorize_into()
+            .map_err(|_| SolverError::InvalidConfiguration("I - dt D is singular"))?;
+        Ok(PreparedImplicitEuler {
+            drift,
+            factorized,
+            dt,
+        })
+    }
+
+    fn prepare_or_panic(system: &DenseSSESystem, dt: f64) -> PreparedImplicitEuler {
+        Self::prepare(system, dt).expect("I - dt D should not be singular")
+    }
+}
+
+impl Solver<DenseSSESystem> for ImplicitEulerSolver {
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &DenseSSESystem,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        Self::prepare_or_panic(system, dt).step(state, system, t, rng)
+    }
+
+    fn integrate<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &DenseSSESystem,
+        current_t: &mut f64,
+        n_step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        Self::prepare_or_panic(system, dt).integrate(state, system, current_t, n_step, rng)
+    }
+
+    fn solve_from_with_rng<R: Rng + ?Sized>(
         initial_state: &Array1<Complex<f64>>,
-        system: &T,
+        system: &DenseSSESystem,
+        t_start: f64,
         n: usize,
         step: usize,
         dt: f64,
+        rng: &mut R,
     ) -> Array2<Complex<f64>> {
-        let mut out = Array2::zeros([0, initial_state.len()]);
+        // Factorize once for the whole solve, rather than once per output block
+        let prepared = Self::prepare_or_panic(system, dt);
+        let mut out = Array2::zeros([n, initial_state.len()]);
         let mut current = initial_state.to_owned();
+        let mut current_t = t_start;
+        for (step_n, mut row) in out.outer_iter_mut().enumerate() {
+            if step_n > 0 {
+                current = prepared.integrate(&current, system, &mut current_t, step, rng);
+            }
+            row.assign(&current);
+        }
+        out
+    }
+}
+
+/// Solves a system with a [`HybridNoise`], where some sources are unraveled by jumps.
+///
+/// Each step applies a [`NormalizedEulerSolver`] step for the hamiltonian and diffusive sources,
+/// followed by the jump sources, so the state remains normalized.
+pub struct HybridSolver {}
+
+impl<H: TimeDependentHamiltonian, T: Tensor, U: Tensor> Solver<SSESystem<H, HybridNoise<T, U>>>
+    for HybridSolver
+{
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &SSESystem<H, HybridNoise<T, U>>,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        let out = NormalizedEulerSolver::step(state, system, t, dt, rng);
+        system.noise.apply_jumps(&out, dt, rng)
+    }
+}
+
+/// Solves a [`SSESystem`] using the quantum jump (monte carlo wavefunction) unraveling.
+///
+/// Between jumps the state evolves under the non-hermitian `H_eff = H - i/2 \sum_i L_i^\dagger L_i`,
+/// and jumps `|\psi> -> L_i |\psi>` occur with a source chosen in proportion to its rate `||L_i |\psi>||^2`.
+/// As a [`Solver`] each step jumps with probability `dt \sum_i ||L_i |\psi>||^2`, returning the
+/// normalized state. [`JumpSolver::solve_with_jumps`] instead uses the waiting time of each jump,
+/// and records the jumps which occur.
+pub struct JumpSolver {}
+
+/// The jumps of a trajectory, as returned by [`JumpSolver::solve_with_jumps`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JumpRecord {
+    /// The time of each jump
+    pub times: Vec<f64>,
+    /// The index of the source of each jump
+    pub channels: Vec<usize>,
+}
+
+impl JumpSolver {
+    /// Choose a channel with probability proportional to its rate.
+    ///
+    /// Returns `None` if there is no channel with a positive rate.
+    fn choose_channel<R: Rng + ?Sized>(rates: &[f64], rng: &mut R) -> Option<usize> {
+        let total = rates.iter().sum::<f64>();
+        if total.is_nan() || total <= 0f64 {
+            return None;
+        }
+        let mut sample = rng.gen::<f64>() * total;
+        // Fall back to the last source with a positive rate to guard against rounding in the sum of the rates
+        rates
+            .iter()
+            .position(|rate| {
+                sample -= rate;
+                sample < 0f64
+            })
+            .or_else(|| rates.iter().rposition(|rate| *rate > 0f64))
+    }
+
+    /// Apply a single (rk4) step of the no-jump evolution, which does not preserve the norm
+    fn no_jump_step<H: TimeDependentHamiltonian, N: Noise>(
+        state: &Array1<Complex<f64>>,
+        system: &SSESystem<H, N>,
+        t: f64,
+        dt: f64,
+    ) -> Array1<Complex<f64>> {
+        let half_dt = Complex::from(0.5 * dt);
+        let k1 = system.get_jump_parts(state, t).0;
+        let k2 = system
+            .get_jump_parts(&(state + &(half_dt * &k1)), t + 0.5 * dt)
+            .0;
+        let k3 = system
+            .get_jump_parts(&(state + &(half_dt * &k2)), t + 0.5 * dt)
+            .0;
+        let k4 = system
+            .get_jump_parts(&(state + &(Complex::from(dt) * &k3)), t + dt)
+            .0;
+        state + &(Complex::from(dt / 6f64) * (k1 + (k2 + k3) * Complex::from(2f64) + k4))
+    }
+
+    /// Solve the system, recording the time and source of each jump.
+    ///
+    /// The unnormalized state evolves under `H_eff` until its squared norm falls below
+    /// a threshold drawn uniformly from `[0, 1)`, at which point a jump occurs and a new threshold
+    /// is drawn. Jumps occur at the end of the step in which the threshold is crossed,
+    /// so the jump times have an error of order `dt`. The returned states are normalized.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the initial state has zero norm
+    pub fn solve_with_jumps<H: TimeDependentHamiltonian, N: Noise, R: Rng + ?Sized>(
+        initial_state: &Array1<Complex<f64>>,
+        system: &SSESystem<H, N>,
+        n: usize,
+        step: usize,
+        dt: f64,
+        rng: &mut R,
+    ) -> (Array2<Complex<f64>>, JumpRecord) {
+        let mut out = Array2::zeros([0, initial_state.len()]);
+        let mut record = JumpRecord::default();
+        let initial_norm = initial_state.norm_l2();
+        assert!(initial_norm > 0f64);
+        let mut current = initial_state / Complex::from(initial_norm);
         let mut current_t = 0f64;
-        for _step_n in 1..n {
-            out.push_row(current.view()).unwrap();
-            current = Self::integrate(&current, system, &mut current_t, step, dt);
+        let mut threshold = rng.gen::<f64>();
+        for step_n in 0..n {
+            if step_n > 0 {
+                for _n in 0..step {
+                    let next = Self::no_jump_step(&current, system, current_t, dt);
+                    current_t += dt;
+
+                    if system.noise.is_empty() || next.norm_l2().powi(2) >= threshold {
+                        current = next;
+                    } else {
+                        // If the no-jump evolution removed the entire state (ie for a large dt),
+                        // the jump is taken from the state at the start of the step
+                        if next.norm_l2() >= ZERO_NORM_TOLERANCE {
+                            current = next;
+                        }
+                        let (_, l_states) = system.get_jump_parts(&current, current_t);
+                        let rates = l_states
+                            .iter()
+                            .map(|l| l.norm_l2().powi(2))
+                            .collect::<Vec<_>>();
+                        // Without a channel to jump through the state is left unchanged
+                        if let Some(channel) = Self::choose_channel(&rates, rng) {
+                            current = l_states.into_iter().nth(channel).unwrap();
+                            current /= Complex::from(current.norm_l2());
+                            record.times.push(current_t);
+                            record.channels.push(channel);
+                            threshold = rng.gen::<f64>();
+                        }
+                    }
+                }
+            }
+            out.push_row((&current / Complex::from(current.norm_l2())).view())
+                .unwrap();
         }
-        out.push_row(current.view()).unwrap();
 
+        (out, record)
+    }
+
+    /// Sample the time of the next jump of `state` at time `t`, given a uniform draw `u` in `(0, 1]`.
+    ///
+    /// The no-jump evolution is integrated in (rk4) steps of `dt` until the squared norm,
+    /// relative to that of `state`, falls below `u`. The crossing is then found by bisection
+    /// within the final step, so unlike [`JumpSolver::solve_with_jumps`] the jump time has no
+    /// error of order `dt`, only the error of the rk4 integration.
+    ///
+    /// Returns `None` if the squared norm stays above `u` until `t_max`, for example for a dark state.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the state has zero norm, or `dt` is not positive
+    pub fn next_jump_time<H: TimeDependentHamiltonian, N: Noise>(
+        state: &Array1<Complex<f64>>,
+        system: &SSESystem<H, N>,
+        t: f64,
+        u: f64,
+        dt: f64,
+        t_max: f64,
+    ) -> Option<f64> {
+        let initial_norm_sqr = state.norm_l2().powi(2);
+        assert!(initial_norm_sqr > 0f64);
+        assert!(dt > 0f64);
+        let threshold = u * initial_norm_sqr;
+
+        let mut current = state.to_owned();
+        let mut current_t = t;
+        while current_t < t_max {
+            let step_dt = dt.min(t_max - current_t);
+            let next = Self::no_jump_step(&current, system, current_t, step_dt);
+            if next.norm_l2().powi(2) < threshold {
+                // The no-jump evolution decreases the norm, so the crossing is unique
+                let (mut lower, mut upper) = (0f64, step_dt);
+                while upper - lower > f64::EPSILON * (current_t.abs() + upper) {
+                    let middle = 0.5 * (lower + upper);
+                    let norm_sqr = Self::no_jump_step(&current, system, current_t, middle)
+                        .norm_l2()
+                        .powi(2);
+                    if norm_sqr < threshold {
+                        upper = middle;
+                    } else {
+                        lower = middle;
+                    }
+                }
+                return Some(current_t + 0.5 * (lower + upper));
+            }
+            current = next;
+            current_t += step_dt;
+        }
+        None
+    }
+}
+
+impl<H: TimeDependentHamiltonian, N: Noise> Solver<SSESystem<H, N>> for JumpSolver {
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &SSESystem<H, N>,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        let norm = state.norm_l2();
+        // A vanishing state has no well defined rates, and cannot be renormalized
+        if norm < ZERO_NORM_TOLERANCE {
+            return state.to_owned();
+        }
+        let (_, l_states) = system.get_jump_parts(state, t);
+        let rates = l_states
+            .iter()
+            .map(|l| (l.norm_l2() / norm).powi(2))
+            .collect::<Vec<_>>();
+
+        let no_jump = if rng.gen::<f64>() < dt * rates.iter().sum::<f64>() {
+            None
+        } else {
+            Some(Self::no_jump_step(state, system, t, dt))
+        };
+        // If the no-jump evolution removes the entire state (ie for a large dt),
+        // the step is treated as a jump
+        let channel = match &no_jump {
+            Some(out) if out.norm_l2() >= ZERO_NORM_TOLERANCE => None,
+            _ => Self::choose_channel(&rates, rng),
+        };
+        let mut out = match (channel, no_jump) {
+            (Some(channel), _) => l_states.into_iter().nth(channel).unwrap(),
+            (None, Some(out)) => out,
+            (None, None) => state.to_owned(),
+        };
+        let out_norm = out.norm_l2();
+        if out_norm >= ZERO_NORM_TOLERANCE {
+            out /= Complex::from(out_norm);
+        }
         out
     }
 }
 
-pub struct EulerSolver {}
+/// A predictor-corrector (heun) scheme for the coherent part of the step,
+/// which is second order in the deterministic evolution.
+/// The stochastic increment is the same as for the [`EulerSolver`].
+pub struct HeunSolver {}
 
-impl<T: SDESystem> Solver<T> for EulerSolver {
-    fn step(state: &Array1<Complex<f64>>, system: &T, t: f64, dt: f64) -> Array1<Complex<f64>> {
-        // The basic euler method
-        // Y_n+1 = Y_n + a dt + \sum_k b_k dW
-        // where dW are normalized gaussian random variables,  <dW_k* dW_k'> = dt
+impl<S: ComplexScalar, T: SDESystem<S>> Solver<T, S> for HeunSolver {
+    const ORDER: u32 = 2;
 
-        let rng = rand::thread_rng();
-        let sqt_dt = dt.sqrt();
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        // \bar{Y} = Y_n + a(Y_n) dt + \sum_k b_k(Y_n) dW
+        // Y_n+1 = Y_n + 1/2 (a(Y_n) + a(\bar{Y})) dt + \sum_k b_k(Y_n) dW
+        let sqrt_dt = dt.sqrt();
+        let noise = rng
+            .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+            .map(|d| S::from_c64(d * sqrt_dt))
+            .take(system.n_incoherent())
+            .collect::<Vec<_>>();
+
+        let parts = system.get_parts(state, t);
+        let predicted = state
+            + T::get_step_from_parts(
+                &parts,
+                &SDEStep {
+                    coherent: S::complex(dt, 0f64),
+                    incoherent: noise.clone(),
+                },
+            );
+
+        let half_dt = S::complex(0.5 * dt, 0f64);
+        let mut out = state
+            + T::get_step_from_parts(
+                &parts,
+                &SDEStep {
+                    coherent: half_dt,
+                    incoherent: noise,
+                },
+            );
+        out += &system.get_coherent_step(half_dt, &predicted, t + dt);
+        out
+    }
+}
+
+/// A heun scheme for both the coherent and stochastic parts of the step,
+/// which converges to the solution of the [`NoiseConvention::Stratonovich`] SDE.
+///
+/// To integrate an ito SSE, wrap the noise in a [`crate::sse_system::StratonovichNoise`].
+pub struct StratonovichHeunSolver {}
+
+impl<S: ComplexScalar, T: SDESystem<S>> Solver<T, S> for StratonovichHeunSolver {
+    const CONVENTION: NoiseConvention = NoiseConvention::Stratonovich;
+    const ORDER: u32 = 2;
+
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        // \bar{Y} = Y_n + a(Y_n) dt + \sum_k b_k(Y_n) dW
+        // Y_n+1 = Y_n + 1/2 (a(Y_n) + a(\bar{Y})) dt + 1/2 \sum_k (b_k(Y_n) + b_k(\bar{Y})) dW
+        let sqrt_dt = dt.sqrt();
+        let noise = rng
+            .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+            .map(|d| S::from_c64(d * sqrt_dt))
+            .take(system.n_incoherent())
+            .collect::<Vec<_>>();
+
+        let parts = system.get_parts(state, t);
+        let predicted = state
+            + T::get_step_from_parts(
+                &parts,
+                &SDEStep {
+                    coherent: S::complex(dt, 0f64),
+                    incoherent: noise.clone(),
+                },
+            );
+
+        let half_step = SDEStep {
+            coherent: S::complex(0.5 * dt, 0f64),
+            incoherent: noise.iter().map(|d| d.mul_real(S::real(0.5))).collect(),
+        };
+        let mut out = state + T::get_step_from_parts(&parts, &half_step);
+        out += &system.get_step(&half_step, &predicted, t + dt);
+        out
+    }
+}
+
+/// The implicit midpoint scheme `Y_n+1 = Y_n + a(\bar{Y}) dt + \sum_k b_k(\bar{Y}) dW`,
+/// where `\bar{Y} = (Y_n + Y_n+1) / 2` is found by fixed point iteration.
+///
+/// The midpoint rule preserves the norm of a closed system, and greatly reduces the drift
+/// in the norm of the SSE compared to the [`EulerSolver`].
+/// This converges to the solution of the [`NoiseConvention::Stratonovich`] SDE,
+/// so to integrate an ito SSE wrap the noise in a [`crate::sse_system::StratonovichNoise`].
+pub struct MidpointSolver {}
+
+impl MidpointSolver {
+    /// The number of fixed point iterations used to find the midpoint
+    const N_ITERATIONS: usize = 3;
+}
+
+impl<S: ComplexScalar, T: SDESystem<S>> Solver<T, S> for MidpointSolver {
+    const CONVENTION: NoiseConvention = NoiseConvention::Stratonovich;
+    const ORDER: u32 = 2;
+
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        let sqrt_dt = dt.sqrt();
         let step = SDEStep {
-            coherent: Complex { re: dt, im: 0f64 },
+            coherent: S::complex(dt, 0f64),
             incoherent: rng
-                .sample_iter::<Complex<_>, _>(StandardComplexNormal)
-                .map(|d| d * sqt_dt)
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .map(|d| S::from_c64(d * sqrt_dt))
                 .take(system.n_incoherent())
-                .collect(),
+                .collect::<Vec<_>>(),
         };
 
-        state + system.get_step(&step, state, t)
+        let half = S::from_real(S::real(0.5));
+        let mut out = state + system.get_step(&step, state, t);
+        for _ in 1..Self::N_ITERATIONS {
+            let midpoint = (state + &out) * half;
+            out = state + system.get_step(&step, &midpoint, t + 0.5 * dt);
+        }
+        out
     }
 }
 
-pub struct NormalizedEulerSolver {}
+/// A deterministic solver for the lindblad master equation of a [`SSESystem`].
+///
+/// This evolves the density matrix averaged over all trajectories of the SSE,
+/// using the same hamiltonian and operators, with a fourth order runge-kutta scheme.
+/// The cost of a step is `O(N)` times that of the SSE, so this is only practical
+/// for small systems.
+pub struct LindbladSolver {}
 
-impl<T: SDESystem> Solver<T> for NormalizedEulerSolver {
-    fn step(state: &Array1<Complex<f64>>, system: &T, t: f64, dt: f64) -> Array1<Complex<f64>> {
-        let mut out = EulerSolver::step(state, system, t, dt);
-        // Normalize the state
-        out /= Complex {
-            re: out.norm_l2(),
-            im: 0f64,
-        };
+impl LindbladSolver {
+    /// Solve the lindblad master equation, returning the density matrix every `step` steps
+    /// as an array of shape `[n, n_states, n_states]`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `initial_rho` is not square
+    #[must_use]
+    pub fn solve<H: Tensor, T: Tensor, U: Tensor>(
+        initial_rho: &Array2<Complex<f64>>,
+        system: &SSESystem<H, FullNoise<T, U>>,
+        n: usize,
+        step: usize,
+        dt: f64,
+    ) -> Array3<Complex<f64>> {
+        let n_states = initial_rho.nrows();
+        assert_eq!(initial_rho.ncols(), n_states);
+
+        let mut out = Array3::zeros([n, n_states, n_states]);
+        let mut current = initial_rho.to_owned();
+        for (step_n, mut out) in out.outer_iter_mut().enumerate() {
+            if step_n > 0 {
+                for _n in 0..step {
+                    current = Self::step(&current, system, dt);
+                }
+            }
+            out.assign(&current);
+        }
         out
     }
+
+    /// Take a single runge-kutta step of the lindblad master equation
+    fn step<H: Tensor, T: Tensor, U: Tensor>(
+        rho: &Array2<Complex<f64>>,
+        system: &SSESystem<H, FullNoise<T, U>>,
+        dt: f64,
+    ) -> Array2<Complex<f64>> {
+        let half_dt = Complex::from(0.5 * dt);
+        let k1 = system.lindblad_derivative(rho);
+        let k2 = system.lindblad_derivative(&(rho + &k1 * half_dt));
+        let k3 = system.lindblad_derivative(&(rho + &k2 * half_dt));
+        let k4 = system.lindblad_derivative(&(rho + &k3 * Complex::from(dt)));
+        rho + (k1 + k2 * Complex::from(2f64) + k3 * Complex::from(2f64) + k4)
+            * Complex::from(dt / 6f64)
+    }
+}
+
+/// Richardson extrapolation of the solver `V`, combining a step of `dt` with two steps of `dt / 2`.
+///
+/// For a solver of order `p` the extrapolated step `y_{dt/2} + (y_{dt/2} - y_{dt}) / (2^p - 1)`
+/// cancels the leading deterministic error, so the scheme has order `p + 1`.
+/// The coarse and fine steps draw independent noise, so this is only useful
+/// for closed systems or systems with weak noise.
+pub struct RichardsonSolver<V>(PhantomData<V>);
+
+impl<S: ComplexScalar, T: SDESystem<S>, V: Solver<T, S>> Solver<T, S> for RichardsonSolver<V> {
+    const CONVENTION: NoiseConvention = V::CONVENTION;
+    const ORDER: u32 = V::ORDER + 1;
+    const STRONG_ORDER: f64 = V::STRONG_ORDER;
+    const WEAK_ORDER: f64 = V::WEAK_ORDER;
+
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<S>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<S> {
+        let coarse = V::step(state, system, t, dt, rng);
+        let half = V::step(state, system, t, 0.5 * dt, rng);
+        let mut fine = V::step(&half, system, t + 0.5 * dt, 0.5 * dt, rng);
+
+        let coefficient = 1f64 / (2f64.powi(V::ORDER.try_into().unwrap()) - 1f64);
+        fine.scaled_add(S::complex(coefficient, 0f64), &(&fine - &coarse));
+        fine
+    }
 }
 
 pub struct MilstenSolver {}
 
 impl<T: SDESystem> Solver<T> for MilstenSolver {
-    fn step(state: &Array1<Complex<f64>>, system: &T, t: f64, dt: f64) -> Array1<Complex<f64>> {
+    const STRONG_ORDER: f64 = 1.0;
+
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
         // The explicit milsten scheme for commuting noise
         // Y_k(n+1) = Y_k(n) + \underline{a}_k dt + \frac{1}{2} \sum_j (b^j(t, \bar{Y}(n))_k + b^j(t, Y(n))_k)dW^j
         // where dW are normalized gaussian random variables,  <dW_k* dW_k'> = dt
@@ -94,7 +2314,6 @@ impl<T: SDESystem> Solver<T> for MilstenSolver {
         // Pre-compute the system parts, since we use them twice (for supporting value and actual step)
         let parts = system.get_parts(state, t);
 
-        let rng = rand::thread_rng();
         let sqrt_dt = dt.sqrt();
 
         let noise = rng
@@ -177,10 +2396,15 @@ impl<T: SDESystem> Solver<T> for MilstenSolver {
 pub struct Order2ExplicitWeakSolver {}
 
 impl<T: SDESystem> Solver<T> for Order2ExplicitWeakSolver {
-    fn step(state: &Array1<Complex<f64>>, system: &T, t: f64, dt: f64) -> Array1<Complex<f64>> {
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
         let sqrt_dt = dt.sqrt();
 
-        let mut rng = rand::thread_rng();
         let v = &rng.sample(VMatrix {
             dt,
             n: system.n_incoherent(),
@@ -298,12 +2522,198 @@ impl<T: SDESystem> Solver<T> for Order2ExplicitWeakSolver {
     }
 }
 
+/// The stochastic runge-kutta scheme SRA1 of Rößler, <https://doi.org/10.1137/09076636X>
+///
+/// This has weak order 2 for additive noise. Since the noise of the SSE depends on the state
+/// the diffusion is evaluated only at the start of the step, so for multiplicative noise
+/// the scheme instead improves the accuracy of the drift.
+pub struct SrkSolver {}
+
+impl<T: SDESystem> Solver<T> for SrkSolver {
+    const ORDER: u32 = 2;
+
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        // The SRA1 tableau
+        // c0 = (0, 3/4), A0 = ((0, 0), (3/4, 0)), B0 = ((0, 0), (3/2, 0))
+        // alpha = (1/3, 2/3), beta1 = (1, 0), beta2 = (-1, 1)
+        // Y_n+1 = Y_n + \sum_i alpha_i a(H_i) dt + \sum_j b^j dW^j
+        // H_1 = Y_n, H_2 = Y_n + 3/4 a(Y_n) dt + 3/2 \sum_j b^j I_(j,0) / dt
+        let sqrt_dt = dt.sqrt();
+        let n_incoherent = system.n_incoherent();
+
+        let noise = (&mut *rng)
+            .sample_iter::<Complex<_>, _>(StandardComplexNormal)
+            .map(|d| d * sqrt_dt)
+            .take(n_incoherent)
+            .collect::<Vec<_>>();
+        // The auxiliary variable required to sample I_(j,0) = 1/2 dt (dW^j + dZ^j / sqrt(3))
+        let auxiliary = rng
+            .sample_iter::<Complex<_>, _>(StandardComplexNormal)
+            .map(|d| d * sqrt_dt)
+            .take(n_incoherent)
+            .collect::<Vec<_>>();
+
+        let parts = system.get_parts(state, t);
+
+        // Y_n + 1/3 a(Y_n) dt + \sum_j b^j dW^j
+        let mut out = state
+            + T::get_step_from_parts(
+                &parts,
+                &SDEStep {
+                    coherent: Complex {
+                        re: dt / 3f64,
+                        im: 0f64,
+                    },
+                    incoherent: noise.clone(),
+                },
+            );
+
+        let supporting_step = SDEStep {
+            coherent: Complex {
+                re: 0.75 * dt,
+                im: 0f64,
+            },
+            incoherent: noise
+                .iter()
+                .zip(&auxiliary)
+                .map(|(dw, dz)| 0.75 * (dw + dz / 3f64.sqrt()))
+                .collect(),
+        };
+        let supporting_state = state + T::get_step_from_parts(&parts, &supporting_step);
+
+        // 2/3 a(H_2) dt
+        out += &system.get_coherent_step(
+            Complex {
+                re: 2f64 * dt / 3f64,
+                im: 0f64,
+            },
+            &supporting_state,
+            t + 0.75 * dt,
+        );
+        out
+    }
+}
+
+/// A derivative free weak order 2.0 scheme, see 15.1.3.
+///
+/// Each complex increment `dW_k = (dX_k + i dY_k) / sqrt(2)` is treated as a pair of
+/// real increments, with `b^{2k} = b_k / sqrt(2)` and `b^{2k + 1} = i b_k / sqrt(2)`,
+/// so the supporting states reproduce the moments of the complex noise.
+/// The error in averaged observables is O(dt^2), but each step evaluates the system
+/// at O(`n_incoherent`) supporting states.
+pub struct WeakOrder2Solver {}
+
+impl<T: SDESystem> Solver<T> for WeakOrder2Solver {
+    const ORDER: u32 = 2;
+    const WEAK_ORDER: f64 = 2.0;
+
+    fn step<R: Rng + ?Sized>(
+        state: &Array1<Complex<f64>>,
+        system: &T,
+        t: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        let sqrt_dt = dt.sqrt();
+        let n_sources = system.n_incoherent();
+        let n_real = 2 * n_sources;
+        let factor = |j: usize| {
+            if j.is_multiple_of(2) {
+                Complex {
+                    re: std::f64::consts::FRAC_1_SQRT_2,
+                    im: 0f64,
+                }
+            } else {
+                Complex {
+                    re: 0f64,
+                    im: std::f64::consts::FRAC_1_SQRT_2,
+                }
+            }
+        };
+        // \sum_j b^j w_j = \sum_k b_k (w_{2k} + i w_{2k + 1}) / sqrt(2)
+        let to_complex = |w: &[f64]| {
+            (0..n_sources)
+                .map(|k| factor(2 * k) * w[2 * k] + factor(2 * k + 1) * w[2 * k + 1])
+                .collect::<Vec<_>>()
+        };
+
+        let noise = rng
+            .sample_iter::<f64, _>(StandardNormal)
+            .map(|d| d * sqrt_dt)
+            .take(n_real)
+            .collect::<Vec<_>>();
+        let v = rng.sample(VMatrix { dt, n: n_real });
+
+        // a dt and \sum_j b^j dw^j
+        let drift = system.get_coherent_step(Complex { re: dt, im: 0f64 }, state, t);
+        let diffusion = system.get_incoherent_steps(&to_complex(&noise), state, t);
+
+        // 1/2 (a(\bar{Y}) + a(Y)) dt
+        let supporting_state = state + &drift + &diffusion;
+        let mut out = state
+            + &((&drift
+                + &system.get_coherent_step(Complex { re: dt, im: 0f64 }, &supporting_state, t))
+                * Complex { re: 0.5, im: 0f64 });
+
+        // The b^j(Y) terms of both sums combine to (1 - n_real / 2) \sum_j b^j dw^j
+        #[allow(clippy::cast_precision_loss)]
+        let remainder = 1f64 - 0.5 * n_real as f64;
+        out += &(diffusion
+            * Complex {
+                re: remainder,
+                im: 0f64,
+            });
+
+        for (j, dwj) in noise.iter().enumerate() {
+            let b_j = system.get_incoherent_step(j / 2, factor(j) * sqrt_dt, state, t);
+
+            // 1/4 b^j(R_\pm^j) (dw^j \pm (dw^j dw^j - dt) / sqrt(dt))
+            let correction = (dwj * dwj - dt) / sqrt_dt;
+            for (sign, weight) in [(1f64, dwj + correction), (-1f64, dwj - correction)] {
+                let r = state + &drift + &(&b_j * Complex { re: sign, im: 0f64 });
+                out += &system.get_incoherent_step(j / 2, factor(j) * (0.25 * weight), &r, t);
+            }
+
+            // 1/4 \sum_{r \neq j} b^r(U_\pm^j) (dw^r \pm (dw^r dw^j + V_{jr}) / sqrt(dt))
+            for sign in [1f64, -1f64] {
+                let u = state + &(&b_j * Complex { re: sign, im: 0f64 });
+                let weights = noise
+                    .iter()
+                    .enumerate()
+                    .map(|(r, dwr)| {
+                        if r == j {
+                            0f64
+                        } else {
+                            0.25 * (dwr + sign * (dwr * dwj + v[[j, r]]) / sqrt_dt)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                out += &system.get_incoherent_steps(&to_complex(&weights), &u, t);
+            }
+        }
+
+        out
+    }
+}
+
 /// See 15.4.13
 pub struct Order2ImplicitWeakSolver {}
 
 impl<T: SDESystem> Solver<T> for Order2ImplicitWeakSolver {
     #[allow(clippy::too_many_lines)]
-    fn step(_state: &Array1<Complex<f64>>, _system: &T, _t: f64, _dt: f64) -> Array1<Complex<f64>> {
+    fn step<R: Rng + ?Sized>(
+        _state: &Array1<Complex<f64>>,
+        _system: &T,
+        _t: f64,
+        _dt: f64,
+        _rng: &mut R,
+    ) -> Array1<Complex<f64>> {
         todo!()
     }
 }
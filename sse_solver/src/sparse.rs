@@ -1,14 +1,21 @@
-use ndarray::{linalg::Dot, Array1, Array2};
+use std::sync::Arc;
+
+use ndarray::{linalg::Dot, Array1, Array2, ErrorKind, ShapeError, Zip};
 use num_complex::Complex;
 use rand_distr::num_traits;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    sse_system::{Adjoint, HermitianCheck, OperatorShape, Tensor},
+    system::ComplexScalar,
+};
+
 /// Represents an array, stored as a series of (offset) diagonals
 /// Each diagonal stores elements M_{i+offset % `N_0`, i}
 /// length of diagonals is shape[1], with a total of shape[0] offsets
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BandedArray<T> {
     diagonals: Vec<Vec<T>>,
@@ -54,6 +61,102 @@ impl<T: Copy> BandedArray<T> {
         }
     }
 
+    /// Build the array from bands `data[k]`, each holding the elements `M_{i, i + offsets[k]}`
+    /// in order of increasing `i`, such that an offset of zero is the main diagonal,
+    /// positive offsets are above the main diagonal and negative offsets are below.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of bands and offsets differ, if an offset
+    /// lies outside the array, or if the length of a band does not match its offset
+    pub fn from_diagonals(
+        shape: [usize; 2],
+        offsets: &[isize],
+        data: Vec<Array1<T>>,
+    ) -> Result<Self, ShapeError>
+    where
+        T: num_traits::Zero + std::ops::AddAssign,
+    {
+        if offsets.len() != data.len() {
+            return Err(ShapeError::from_kind(ErrorKind::IncompatibleShape));
+        }
+        let [n_rows, n_columns] = shape;
+
+        // Bands which wrap onto the same (cyclic) diagonal are combined
+        let mut diagonals = vec![None; n_rows];
+        for (offset, band) in offsets.iter().zip(data) {
+            let (first_row, first_column) = if *offset >= 0 {
+                (0, offset.unsigned_abs())
+            } else {
+                (offset.unsigned_abs(), 0)
+            };
+            if first_row >= n_rows || first_column >= n_columns {
+                return Err(ShapeError::from_kind(ErrorKind::OutOfBounds));
+            }
+            if band.len() != (n_rows - first_row).min(n_columns - first_column) {
+                return Err(ShapeError::from_kind(ErrorKind::IncompatibleShape));
+            }
+
+            for (p, value) in band.into_iter().enumerate() {
+                let (i, j) = (first_row + p, first_column + p);
+                // Each diagonal stores M_{j + o % N_0, j}
+                let o = (i + n_rows - j % n_rows) % n_rows;
+                diagonals[o].get_or_insert_with(|| vec![T::zero(); n_columns])[j] += value;
+            }
+        }
+
+        let (offsets, diagonals) = diagonals
+            .into_iter()
+            .enumerate()
+            .filter_map(|(o, d)| d.map(|d| (o, d)))
+            .unzip();
+        Ok(BandedArray {
+            diagonals,
+            offsets,
+            shape,
+        })
+    }
+
+    /// Embed the array into the top left block of a larger array of the given `shape`,
+    /// padding with zeros.
+    ///
+    /// A rectangular operator `C^m -> C^n` acting between subspaces of a larger space
+    /// becomes a square operator on the full space, with the subspaces spanned by
+    /// the first `m` and `n` basis states.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `shape` is smaller than the shape of the array
+    #[must_use]
+    pub fn embed(&self, shape: [usize; 2]) -> Self
+    where
+        T: num_traits::Zero + std::ops::AddAssign,
+    {
+        let [n_rows, n_columns] = shape;
+        assert!(n_rows >= self.shape[0] && n_columns >= self.shape[1]);
+
+        let mut diagonals = vec![None; n_rows];
+        for (offset, diagonal) in self.offsets.iter().zip(&self.diagonals) {
+            for (j, value) in diagonal.iter().enumerate() {
+                // M_{ij} is stored at j in the diagonal with offset (i - j) % N_0
+                let i = (j + offset) % self.shape[0];
+                let o = (i + n_rows - j % n_rows) % n_rows;
+                diagonals[o].get_or_insert_with(|| vec![T::zero(); n_columns])[j] += *value;
+            }
+        }
+
+        let (offsets, diagonals) = diagonals
+            .into_iter()
+            .enumerate()
+            .filter_map(|(o, d)| d.map(|d| (o, d)))
+            .unzip();
+        BandedArray {
+            diagonals,
+            offsets,
+            shape,
+        }
+    }
+
     #[must_use]
     pub fn transpose(&self) -> TransposedBandedArray<T> {
         TransposedBandedArray {
@@ -63,6 +166,72 @@ impl<T: Copy> BandedArray<T> {
         }
     }
 }
+impl<T: num_complex::ComplexFloat> BandedArray<T> {
+    #[must_use]
+    pub fn conj(&self) -> BandedArray<T> {
+        BandedArray {
+            diagonals: self
+                .diagonals
+                .iter()
+                .map(|d| d.iter().map(|i| i.conj()).collect())
+                .collect(),
+            offsets: self.offsets.clone(),
+            shape: self.shape,
+        }
+    }
+
+    /// Build the array from a dense array, keeping only the diagonals which have
+    /// an element larger than `tolerance` in magnitude.
+    ///
+    /// Returns the array and its detected bandwidth, the largest `|i - j|` of an element
+    /// `M_{ij}` larger than `tolerance`. The kept diagonals are stored exactly,
+    /// so only the elements of the dropped diagonals are lost.
+    #[must_use]
+    pub fn from_dense_with_tolerance(dense: &Array2<T>, tolerance: T::Real) -> (Self, usize) {
+        let [n_rows, n_columns] = [dense.nrows(), dense.ncols()];
+        let mut bandwidth = 0;
+        let mut offsets = Vec::new();
+        let mut diagonals = Vec::new();
+        for o in 0..n_rows {
+            let diagonal = (0..n_columns)
+                .map(|j| dense[[(j + o) % n_rows, j]])
+                .collect::<Vec<_>>();
+            let mut is_significant = false;
+            for (j, value) in diagonal.iter().enumerate() {
+                if value.abs() > tolerance {
+                    is_significant = true;
+                    bandwidth = bandwidth.max(((j + o) % n_rows).abs_diff(j));
+                }
+            }
+            if is_significant {
+                offsets.push(o);
+                diagonals.push(diagonal);
+            }
+        }
+
+        (
+            BandedArray {
+                diagonals,
+                offsets,
+                shape: [n_rows, n_columns],
+            },
+            bandwidth,
+        )
+    }
+}
+
+impl<T: Clone> TransposedBandedArray<T> {
+    /// The inverse of [`BandedArray::transpose`], which shares the same diagonals
+    #[must_use]
+    pub fn transpose(&self) -> BandedArray<T> {
+        BandedArray {
+            diagonals: self.diagonals.clone(),
+            offsets: self.offsets.clone(),
+            shape: [self.shape[1], self.shape[0]],
+        }
+    }
+}
+
 impl<T: num_complex::ComplexFloat> TransposedBandedArray<T> {
     #[must_use]
     pub fn conj(&self) -> TransposedBandedArray<T> {
@@ -78,6 +247,61 @@ impl<T: num_complex::ComplexFloat> TransposedBandedArray<T> {
     }
 }
 
+impl<T> OperatorShape for BandedArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.shape
+    }
+}
+
+impl<T> OperatorShape for TransposedBandedArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.shape
+    }
+}
+
+impl HermitianCheck for BandedArray<Complex<f64>> {
+    fn hermitian_error(&self) -> Option<f64> {
+        let [n_rows, n_columns] = self.shape;
+        if n_rows != n_columns {
+            return Some(f64::INFINITY);
+        }
+        // M_{ij} is stored in the diagonal with offset o at j, and its partner
+        // M_{ji} in the diagonal with offset (N - o) % N at i
+        let find = |offset: usize| {
+            self.offsets
+                .iter()
+                .position(|o| *o == offset)
+                .map(|idx| &self.diagonals[idx])
+        };
+        let mut error = 0f64;
+        for (offset, diagonal) in self.offsets.iter().zip(&self.diagonals) {
+            let partner = find((n_rows - offset % n_rows) % n_rows);
+            for (j, value) in diagonal.iter().enumerate() {
+                let i = (j + offset) % n_rows;
+                let expected = partner.map_or(Complex::default(), |p| p[i].conj());
+                error = error.max((value - expected).norm());
+            }
+        }
+        Some(error)
+    }
+}
+
+impl Adjoint for BandedArray<Complex<f64>> {
+    type Output = TransposedBandedArray<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        self.transpose().conj()
+    }
+}
+
+impl Adjoint for TransposedBandedArray<Complex<f64>> {
+    type Output = BandedArray<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        self.transpose().conj()
+    }
+}
+
 // impl<
 //         T: num_traits::Zero
 //             + Clone
@@ -146,7 +370,7 @@ impl<
 /// Represents an array, stored as a series of (offset) diagonals
 /// Each diagonal stores elements M_{i, i+offset % `N_0`}
 /// length of diagonals is shape[0], with a total of shape[1] offsets
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TransposedBandedArray<T> {
     diagonals: Vec<Vec<T>>,
@@ -218,7 +442,416 @@ impl<
     }
 }
 
-#[derive(Clone)]
+/// Represents an array in compressed sparse row form.
+/// The non-zero elements of row i are `values[row_offsets[i]..row_offsets[i + 1]]`,
+/// with columns `columns[row_offsets[i]..row_offsets[i + 1]]`
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CsrArray<T> {
+    values: Vec<T>,
+    columns: Vec<usize>,
+    row_offsets: Vec<usize>,
+    shape: [usize; 2],
+}
+
+impl<T: Copy + num_traits::Zero> CsrArray<T> {
+    /// Build the array from the non-zero elements of `dense`
+    #[must_use]
+    pub fn from_dense(dense: &Array2<T>) -> Self {
+        let mut values = Vec::new();
+        let mut columns = Vec::new();
+        let mut row_offsets = vec![0];
+        for row in dense.rows() {
+            for (j, value) in row.iter().enumerate() {
+                if !value.is_zero() {
+                    values.push(*value);
+                    columns.push(j);
+                }
+            }
+            row_offsets.push(values.len());
+        }
+
+        CsrArray {
+            values,
+            columns,
+            row_offsets,
+            shape: [dense.shape()[0], dense.shape()[1]],
+        }
+    }
+
+    /// Build the array from coordinate (COO) triplets, where `values[k]` is the element
+    /// `M_{rows[k], columns[k]}`. Repeated triplets are summed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of rows, columns and values differ,
+    /// or if an index lies outside the array
+    pub fn from_triplets(
+        shape: [usize; 2],
+        rows: &[usize],
+        columns: &[usize],
+        values: &[T],
+    ) -> Result<Self, ShapeError>
+    where
+        T: std::ops::AddAssign,
+    {
+        if rows.len() != values.len() || columns.len() != values.len() {
+            return Err(ShapeError::from_kind(ErrorKind::IncompatibleShape));
+        }
+        if rows.iter().any(|i| *i >= shape[0]) || columns.iter().any(|j| *j >= shape[1]) {
+            return Err(ShapeError::from_kind(ErrorKind::OutOfBounds));
+        }
+
+        let mut order = (0..values.len()).collect::<Vec<_>>();
+        order.sort_unstable_by_key(|k| (rows[*k], columns[*k]));
+
+        let mut out_values = Vec::<T>::with_capacity(values.len());
+        let mut out_columns = Vec::with_capacity(values.len());
+        let mut row_offsets = vec![0; shape[0] + 1];
+        let mut previous = None;
+        for k in order {
+            if previous == Some((rows[k], columns[k])) {
+                if let Some(last) = out_values.last_mut() {
+                    *last += values[k];
+                }
+                continue;
+            }
+            previous = Some((rows[k], columns[k]));
+            out_values.push(values[k]);
+            out_columns.push(columns[k]);
+            row_offsets[rows[k] + 1] += 1;
+        }
+        for i in 0..shape[0] {
+            row_offsets[i + 1] += row_offsets[i];
+        }
+
+        Ok(CsrArray {
+            values: out_values,
+            columns: out_columns,
+            row_offsets,
+            shape,
+        })
+    }
+
+    #[must_use]
+    pub fn transpose(&self) -> CsrArray<T> {
+        // Count the elements in each column, which become the rows of the transpose
+        let mut row_offsets = vec![0; self.shape[1] + 1];
+        for j in &self.columns {
+            row_offsets[j + 1] += 1;
+        }
+        for j in 0..self.shape[1] {
+            row_offsets[j + 1] += row_offsets[j];
+        }
+
+        let mut next = row_offsets.clone();
+        let mut values = vec![T::zero(); self.values.len()];
+        let mut columns = vec![0; self.columns.len()];
+        for (i, range) in self.row_offsets.windows(2).enumerate() {
+            for k in range[0]..range[1] {
+                let j = self.columns[k];
+                values[next[j]] = self.values[k];
+                columns[next[j]] = i;
+                next[j] += 1;
+            }
+        }
+
+        CsrArray {
+            values,
+            columns,
+            row_offsets,
+            shape: [self.shape[1], self.shape[0]],
+        }
+    }
+}
+
+impl<T: num_complex::ComplexFloat> CsrArray<T> {
+    #[must_use]
+    pub fn conj(&self) -> CsrArray<T> {
+        CsrArray {
+            values: self.values.iter().map(|v| v.conj()).collect(),
+            columns: self.columns.clone(),
+            row_offsets: self.row_offsets.clone(),
+            shape: self.shape,
+        }
+    }
+}
+
+impl<T> OperatorShape for CsrArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.shape
+    }
+}
+
+impl Adjoint for CsrArray<Complex<f64>> {
+    type Output = CsrArray<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        self.transpose().conj()
+    }
+}
+
+impl<
+        T: num_traits::Zero
+            + Clone
+            + Copy
+            + std::ops::AddAssign<<T as std::ops::Mul>::Output>
+            + std::ops::Mul,
+    > Dot<Array1<T>> for CsrArray<T>
+{
+    type Output = Array1<T>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<T>) -> Self::Output {
+        assert_eq!(self.shape[1], rhs.len());
+
+        let mut out = Array1::zeros(self.shape[0]);
+        for (o, range) in out.iter_mut().zip(self.row_offsets.windows(2)) {
+            for k in range[0]..range[1] {
+                *o += self.values[k] * rhs[self.columns[k]];
+            }
+        }
+
+        out
+    }
+}
+
+/// Represents a diagonal array, storing only the elements on the diagonal
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DiagonalArray<T> {
+    diagonal: Array1<T>,
+}
+
+impl<T> DiagonalArray<T> {
+    #[must_use]
+    pub fn from_diagonal(diagonal: Array1<T>) -> Self {
+        Self { diagonal }
+    }
+}
+
+impl<T: Clone> DiagonalArray<T> {
+    #[must_use]
+    pub fn transpose(&self) -> DiagonalArray<T> {
+        self.clone()
+    }
+}
+
+impl<T: num_complex::ComplexFloat> DiagonalArray<T> {
+    #[must_use]
+    pub fn conj(&self) -> DiagonalArray<T> {
+        DiagonalArray {
+            diagonal: self.diagonal.map(|d| d.conj()),
+        }
+    }
+}
+
+impl<T> OperatorShape for DiagonalArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        [self.diagonal.len(), self.diagonal.len()]
+    }
+}
+
+impl Adjoint for DiagonalArray<Complex<f64>> {
+    type Output = DiagonalArray<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        self.conj()
+    }
+}
+
+impl<T: Copy + std::ops::Mul<Output = T>> Dot<Array1<T>> for DiagonalArray<T> {
+    type Output = Array1<T>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<T>) -> Self::Output {
+        assert_eq!(self.diagonal.len(), rhs.len());
+        Zip::from(&self.diagonal)
+            .and(rhs)
+            .map_collect(|d, r| *d * *r)
+    }
+}
+
+/// A dense array, whose storage is shared with its adjoint [`SharedAdjointArray`]
+#[derive(Clone, PartialEq)]
+pub struct SharedArray<T> {
+    inner: Arc<Array2<T>>,
+}
+
+impl<T> SharedArray<T> {
+    #[must_use]
+    pub fn new(array: Array2<T>) -> Self {
+        Self {
+            inner: Arc::new(array),
+        }
+    }
+}
+
+impl<T> OperatorShape for SharedArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.inner.operator_shape()
+    }
+}
+
+impl<S: ComplexScalar> Dot<Array1<S>> for SharedArray<S> {
+    type Output = Array1<S>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<S>) -> Self::Output {
+        self.inner.dot(rhs)
+    }
+}
+
+impl<T> SharedArray<T> {
+    /// The adjoint `A^\dagger`, which shares the storage of `A`
+    #[must_use]
+    pub fn shared_adjoint(&self) -> SharedAdjointArray<T> {
+        SharedAdjointArray {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl Adjoint for SharedArray<Complex<f64>> {
+    type Output = SharedAdjointArray<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        self.shared_adjoint()
+    }
+}
+
+/// The adjoint `A^\dagger` of a [`SharedArray`], stored as a reference to `A`.
+///
+/// The product is calculated as `A^\dagger v = conj(A^T conj(v))`,
+/// which costs two additional passes over the vector.
+#[derive(Clone, PartialEq)]
+pub struct SharedAdjointArray<T> {
+    inner: Arc<Array2<T>>,
+}
+
+impl<T> OperatorShape for SharedAdjointArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        let [n_rows, n_cols] = self.inner.operator_shape();
+        [n_cols, n_rows]
+    }
+}
+
+impl<S: ComplexScalar> Dot<Array1<S>> for SharedAdjointArray<S> {
+    type Output = Array1<S>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<S>) -> Self::Output {
+        let mut out = self.inner.t().dot(&rhs.map(S::conj));
+        out.mapv_inplace(|d| d.conj());
+        out
+    }
+}
+
+impl Adjoint for SharedAdjointArray<Complex<f64>> {
+    type Output = SharedArray<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        SharedArray {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// An operator multiplied by a real `factor`, as built by [`crate::sse_system::FullNoise::scaled`]
+#[derive(Clone, PartialEq)]
+pub struct ScaledArray<T> {
+    inner: T,
+    factor: f64,
+}
+
+impl<T> ScaledArray<T> {
+    #[must_use]
+    pub fn new(inner: T, factor: f64) -> Self {
+        Self { inner, factor }
+    }
+}
+
+impl<T: OperatorShape> OperatorShape for ScaledArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.inner.operator_shape()
+    }
+}
+
+impl<S: ComplexScalar, T: Tensor<S>> Dot<Array1<S>> for ScaledArray<T> {
+    type Output = Array1<S>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<S>) -> Self::Output {
+        let factor = S::from_c64(Complex::from(self.factor));
+        let mut out = self.inner.dot(rhs);
+        out.mapv_inplace(|d| d * factor);
+        out
+    }
+}
+
+impl<T: Adjoint> Adjoint for ScaledArray<T> {
+    type Output = ScaledArray<T::Output>;
+
+    fn adjoint(&self) -> Self::Output {
+        ScaledArray::new(self.inner.adjoint(), self.factor)
+    }
+}
+
+/// The trait object stored by a [`BoxedTensor`]
+trait BoxedOperator: Tensor + Send + Sync {}
+
+impl<T: Tensor + Send + Sync> BoxedOperator for T {}
+
+/// An operator of any [`Tensor`] type, stored behind a trait object at the cost of a
+/// dynamic dispatch for each product.
+///
+/// Prefer [`crate::builder::CollapseOperator`] for the built in dense, banded and factorized
+/// representations, which can be cloned and serialized. A [`BoxedTensor`] is only required
+/// for other operators, such as a [`CsrArray`] or a user defined [`Tensor`].
+pub struct BoxedTensor {
+    inner: Box<dyn BoxedOperator>,
+    shape: [usize; 2],
+}
+
+impl BoxedTensor {
+    #[must_use]
+    pub fn new<T: Tensor + OperatorShape + Send + Sync + 'static>(operator: T) -> Self {
+        Self {
+            shape: operator.operator_shape(),
+            inner: Box::new(operator),
+        }
+    }
+
+    /// Box both `operator` and its adjoint, as required for a source of a
+    /// [`crate::sse_system::FullNoise`]
+    #[must_use]
+    pub fn with_adjoint<T: Tensor + Adjoint + OperatorShape + Send + Sync + 'static>(
+        operator: T,
+    ) -> (Self, Self)
+    where
+        <T as Adjoint>::Output: OperatorShape + Send + Sync + 'static,
+    {
+        let adjoint = Self::new(operator.adjoint());
+        (Self::new(operator), adjoint)
+    }
+}
+
+impl OperatorShape for BoxedTensor {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.shape
+    }
+}
+
+impl Dot<Array1<Complex<f64>>> for BoxedTensor {
+    type Output = Array1<Complex<f64>>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<Complex<f64>>) -> Self::Output {
+        self.inner.dot(rhs)
+    }
+}
+
+#[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FactorizedArray<T> {
     amplitude: T,
@@ -230,11 +863,11 @@ pub struct FactorizedArray<T> {
     pub(crate) ket: Array1<T>,
 }
 
-impl Dot<Array1<Complex<f64>>> for FactorizedArray<Complex<f64>> {
-    type Output = Array1<Complex<f64>>;
+impl<S: ComplexScalar> Dot<Array1<S>> for FactorizedArray<S> {
+    type Output = Array1<S>;
 
     #[inline]
-    fn dot(&self, rhs: &Array1<Complex<f64>>) -> Self::Output {
+    fn dot(&self, rhs: &Array1<S>) -> Self::Output {
         let applied_bra = self.bra.dot(rhs);
 
         &self.ket * (self.amplitude * applied_bra)
@@ -242,12 +875,48 @@ impl Dot<Array1<Complex<f64>>> for FactorizedArray<Complex<f64>> {
 }
 
 impl<T: num_complex::ComplexFloat> FactorizedArray<T> {
+    /// The elementwise conjugate `A^* |k^*><b^*|`
     #[must_use]
     pub fn conj(&self) -> FactorizedArray<T> {
         FactorizedArray {
             amplitude: self.amplitude.conj(),
-            bra: self.bra.clone(),
-            ket: self.ket.clone(),
+            bra: self.bra.map(|b| b.conj()),
+            ket: self.ket.map(|k| k.conj()),
+        }
+    }
+}
+
+impl FactorizedArray<Complex<f64>> {
+    /// Calculate `<bra|state>`, without applying the full operator
+    #[must_use]
+    pub fn bra_overlap(&self, state: &Array1<Complex<f64>>) -> Complex<f64> {
+        self.bra.dot(state)
+    }
+
+    /// Calculate the jump rate `||A |ket><bra|state>||^2 = |A|^2 |<bra|state>|^2 ||ket||^2`,
+    /// without allocating the jumped state
+    #[must_use]
+    pub fn jump_rate(&self, state: &Array1<Complex<f64>>) -> f64 {
+        let ket_norm_sqr = self.ket.iter().map(Complex::norm_sqr).sum::<f64>();
+        self.amplitude.norm_sqr() * self.bra_overlap(state).norm_sqr() * ket_norm_sqr
+    }
+}
+
+impl<T> OperatorShape for FactorizedArray<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        [self.ket.len(), self.bra.len()]
+    }
+}
+
+impl Adjoint for FactorizedArray<Complex<f64>> {
+    type Output = FactorizedArray<Complex<f64>>;
+
+    /// `(A |k><b|)^\dagger = A^* |b^*><k^*|`
+    fn adjoint(&self) -> Self::Output {
+        FactorizedArray {
+            amplitude: self.amplitude.conj(),
+            bra: self.ket.map(Complex::conj),
+            ket: self.bra.map(Complex::conj),
         }
     }
 }
@@ -271,3 +940,169 @@ impl<T> FactorizedArray<T> {
         }
     }
 }
+
+/// Represents a sum of factorized arrays `\sum_n A_n |Ket_n> <Bra_n|`,
+/// ie a single operator with a (low) rank greater than one
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FactorizedSum<T> {
+    terms: Vec<FactorizedArray<T>>,
+}
+
+impl<T> FactorizedSum<T> {
+    /// # Panics
+    ///
+    /// Will panic if there are no terms
+    #[must_use]
+    pub fn from_terms(terms: Vec<FactorizedArray<T>>) -> Self {
+        assert!(!terms.is_empty(), "A sum requires at least one term");
+        Self { terms }
+    }
+}
+
+impl<T: num_complex::ComplexFloat> FactorizedSum<T> {
+    #[must_use]
+    pub fn conj(&self) -> FactorizedSum<T> {
+        FactorizedSum {
+            terms: self.terms.iter().map(FactorizedArray::conj).collect(),
+        }
+    }
+}
+
+impl<T: Clone> FactorizedSum<T> {
+    #[must_use]
+    pub fn transpose(&self) -> FactorizedSum<T> {
+        FactorizedSum {
+            terms: self.terms.iter().map(FactorizedArray::transpose).collect(),
+        }
+    }
+}
+
+impl<T> OperatorShape for FactorizedSum<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        self.terms[0].operator_shape()
+    }
+}
+
+impl Adjoint for FactorizedSum<Complex<f64>> {
+    type Output = FactorizedSum<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        FactorizedSum {
+            terms: self.terms.iter().map(Adjoint::adjoint).collect(),
+        }
+    }
+}
+
+impl<S: ComplexScalar> Dot<Array1<S>> for FactorizedSum<S> {
+    type Output = Array1<S>;
+
+    #[inline]
+    fn dot(&self, rhs: &Array1<S>) -> Self::Output {
+        let mut out = self.terms[0].dot(rhs);
+        for term in &self.terms[1..] {
+            out += &term.dot(rhs);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{linalg::Dot, Array1, Array2};
+    use num_complex::Complex;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{distribution::StandardComplexNormal, sse_system::Adjoint};
+
+    use super::{BandedArray, CsrArray};
+
+    fn random_banded(rng: &mut StdRng, n_states: usize) -> BandedArray<Complex<f64>> {
+        let offsets = (0..n_states)
+            .filter(|_| rng.gen_bool(0.5))
+            .collect::<Vec<_>>();
+        let diagonals = offsets
+            .iter()
+            .map(|_| {
+                (0..n_states)
+                    .map(|_| rng.sample(StandardComplexNormal))
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        BandedArray::from_sparse(&diagonals, &offsets, &[n_states, n_states])
+    }
+
+    fn to_dense<T: Dot<Array1<Complex<f64>>, Output = Array1<Complex<f64>>>>(
+        array: &T,
+        n_states: usize,
+    ) -> Array2<Complex<f64>> {
+        let mut out = Array2::zeros([n_states, n_states]);
+        for j in 0..n_states {
+            let mut basis = Array1::zeros([n_states]);
+            basis[j] = Complex { re: 1f64, im: 0f64 };
+            out.column_mut(j).assign(&array.dot(&basis));
+        }
+        out
+    }
+
+    #[test]
+    fn test_banded_transpose_adjoint_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for n_states in 1..12 {
+            let banded = random_banded(&mut rng, n_states);
+            let dense = to_dense(&banded, n_states);
+
+            let roundtrip = banded.transpose().transpose();
+            assert_eq!(roundtrip.offsets, banded.offsets);
+            assert_eq!(roundtrip.diagonals, banded.diagonals);
+            assert_eq!(roundtrip.shape, banded.shape);
+            let conj = banded.transpose().conj().conj().transpose();
+            assert_eq!(conj.diagonals, banded.diagonals);
+
+            let adjoint = to_dense(&banded.adjoint(), n_states);
+            let expected = dense.t().map(Complex::conj);
+            assert!((&adjoint - &expected).iter().all(|d| d.norm() < 1e-12));
+
+            let double_adjoint = to_dense(&banded.adjoint().adjoint(), n_states);
+            assert!((&double_adjoint - &dense).iter().all(|d| d.norm() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn test_csr_from_triplets_tridiagonal() {
+        let n_states = 6;
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut dense = Array2::zeros([n_states, n_states]);
+        let (mut rows, mut columns, mut values) = (Vec::new(), Vec::new(), Vec::new());
+        for i in 0..n_states {
+            for j in i.saturating_sub(1)..(i + 2).min(n_states) {
+                let value: Complex<f64> = rng.sample(StandardComplexNormal);
+                dense[[i, j]] = value;
+                // Split each element into two repeated triplets, in reverse order
+                rows.extend([i, i]);
+                columns.extend([j, j]);
+                values.extend([0.25 * value, 0.75 * value]);
+            }
+        }
+        rows.reverse();
+        columns.reverse();
+        values.reverse();
+
+        let csr = CsrArray::from_triplets([n_states, n_states], &rows, &columns, &values).unwrap();
+        assert_eq!(csr.values.len(), 3 * n_states - 2);
+        let state = (0..n_states)
+            .map(|_| rng.sample(StandardComplexNormal))
+            .collect::<Array1<_>>();
+        let expected = dense.dot(&state);
+        assert!((&csr.dot(&state) - &expected)
+            .iter()
+            .all(|d| d.norm() < 1e-12));
+
+        assert!(
+            CsrArray::from_triplets([n_states, n_states], &[n_states], &[0], &values[..1]).is_err()
+        );
+        assert!(
+            CsrArray::from_triplets([n_states, n_states], &[0], &[0, 1], &values[..1]).is_err()
+        );
+    }
+}
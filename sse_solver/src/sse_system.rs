@@ -1,15 +1,22 @@
-use ndarray::{linalg::Dot, Array1, Array2, Array3, Axis};
+use ndarray::{linalg::Dot, Array1, Array2, Array3, Axis, ShapeError, Slice};
+use ndarray_linalg::{Cholesky, EigVals, Norm, UPLO};
 use num_complex::Complex;
+use rand::Rng;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    sparse::{BandedArray, FactorizedArray, TransposedBandedArray},
-    system::{SDEOperators, SDEStep, SDESystem},
+    builder::CollapseOperator,
+    sparse::{
+        BandedArray, BoxedTensor, CsrArray, DiagonalArray, FactorizedArray, FactorizedSum,
+        ScaledArray, SharedAdjointArray, SharedArray, TransposedBandedArray,
+    },
+    system::{ComplexScalar, SDEOperators, SDEStep, SDESystem},
+    SseError,
 };
 
-pub trait Noise {
+pub trait Noise<S: ComplexScalar = Complex<f64>> {
     #[inline]
     fn is_empty(&self) -> bool {
         self.len() == 0
@@ -17,25 +24,60 @@ pub trait Noise {
 
     fn len(&self) -> usize;
 
-    fn get_parts(&self, state: &Array1<Complex<f64>>, t: f64) -> Vec<SSEStochasticPart>;
+    fn get_parts(&self, state: &Array1<S>, t: f64) -> Vec<SSEStochasticPart<S>>;
 
     fn get_incoherent_part(
         &self,
         index: usize,
-        state: &Array1<Complex<f64>>,
+        state: &Array1<S>,
         t: f64,
-    ) -> SSEStochasticIncoherentPart;
+    ) -> SSEStochasticIncoherentPart<S>;
 
     fn get_incoherent_parts(
         &self,
-        state: &Array1<Complex<f64>>,
+        state: &Array1<S>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart<S>>;
+
+    /// The contribution of each source to an euler step of length `dt`, using the
+    /// given `increments` `dW_k`. This is intended for diagnosing a misbehaving source.
+    ///
+    /// Each contribution includes both the stochastic and dissipative terms of the source,
+    /// `(L - <L>) dW |\psi> + (<L^\dagger> L - 1/2 <L^\dagger><L> - 1/2 L^\dagger L) dt |\psi>`,
+    /// so their sum is the step of [`SSESystem`] without the hamiltonian.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the number of increments does not match the number of sources
+    fn euler_step_contributions(
+        &self,
+        state: &Array1<S>,
         t: f64,
-    ) -> Vec<SSEStochasticIncoherentPart>;
+        dt: f64,
+        increments: &[S],
+    ) -> Vec<Array1<S>> {
+        assert_eq!(increments.len(), self.len());
+        let dt = S::from_real(S::real(dt));
+        let half = S::real(0.5);
+        self.get_parts(state, t)
+            .iter()
+            .zip(increments)
+            .map(|(part, dw)| {
+                let mut out = &part.l_state * (*dw + (part.expectation.conj() * dt));
+                out.scaled_add(-dt.mul_real(half), &part.l_dagger_l_state);
+                out.scaled_add(
+                    -((*dw * part.expectation) + dt.mul_real(half * part.expectation.square())),
+                    state,
+                );
+                out
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-struct FullNoiseSource<T: Tensor, U: Tensor> {
+struct FullNoiseSource<T, U> {
     // Uses the convention taken from https://doi.org/10.1103/PhysRevA.66.012108
     // However we multiply L by a factor of i
     // L -> iL
@@ -44,47 +86,53 @@ struct FullNoiseSource<T: Tensor, U: Tensor> {
     // [Z(t), Z(t)^\dagger] = \delta(t-s)
     // Note: we scale the operators such that gamma = 1
     operator: T,
+    // Either L^\dagger, or the composite L^\dagger L if `is_composite` is set
     conjugate_operator: U,
+    is_composite: bool,
+    // The local oscillator phase factor e^(i phi) of the measurement,
+    // which rotates the stochastic term L -> e^(i phi) L.
+    // Note the dissipative terms are invariant under this rotation.
+    phase: Complex<f64>,
 }
 #[derive(Clone)]
-pub struct SSEParts<'a> {
-    state: &'a Array1<Complex<f64>>,
+pub struct SSEParts<'a, S = Complex<f64>> {
+    state: &'a Array1<S>,
     /// H |\psi>
-    hamiltonian: Array1<Complex<f64>>,
+    hamiltonian: Array1<S>,
     /// Parts from a the stochastic terms
-    stochastic: Vec<SSEStochasticPart>,
+    stochastic: Vec<SSEStochasticPart<S>>,
 }
 #[derive(Clone)]
-pub struct SSEStochasticPart {
+pub struct SSEStochasticPart<S = Complex<f64>> {
     /// <L>
-    expectation: Complex<f64>,
+    expectation: S,
     /// L |\psi>
-    l_state: Array1<Complex<f64>>,
+    l_state: Array1<S>,
     /// L^\dagger L |\psi>
-    l_dagger_l_state: Array1<Complex<f64>>,
+    l_dagger_l_state: Array1<S>,
 }
 #[derive(Clone)]
-pub struct SSEIncoherentParts<'a> {
-    state: &'a Array1<Complex<f64>>,
+pub struct SSEIncoherentParts<'a, S = Complex<f64>> {
+    state: &'a Array1<S>,
     /// Parts from a the stochastic terms
-    stochastic: Vec<SSEStochasticIncoherentPart>,
+    stochastic: Vec<SSEStochasticIncoherentPart<S>>,
 }
 #[derive(Clone)]
-pub struct SSEStochasticIncoherentPart {
+pub struct SSEStochasticIncoherentPart<S = Complex<f64>> {
     /// <L>
-    expectation: Complex<f64>,
+    expectation: S,
     /// L |\psi>
-    l_state: Array1<Complex<f64>>,
+    l_state: Array1<S>,
 }
 #[derive(Clone)]
-pub struct SSEIncoherentPart<'a> {
-    state: &'a Array1<Complex<f64>>,
+pub struct SSEIncoherentPart<'a, S = Complex<f64>> {
+    state: &'a Array1<S>,
     /// Parts from a the stochastic terms
-    stochastic: SSEStochasticIncoherentPart,
+    stochastic: SSEStochasticIncoherentPart<S>,
 }
 
-impl<'a> From<SSEParts<'a>> for SSEIncoherentParts<'a> {
-    fn from(val: SSEParts<'a>) -> Self {
+impl<'a, S> From<SSEParts<'a, S>> for SSEIncoherentParts<'a, S> {
+    fn from(val: SSEParts<'a, S>) -> Self {
         SSEIncoherentParts {
             state: val.state,
             stochastic: val
@@ -99,15 +147,103 @@ impl<'a> From<SSEParts<'a>> for SSEIncoherentParts<'a> {
     }
 }
 
-impl<T: Tensor, U: Tensor> FullNoiseSource<T, U> {
+impl<T, U> FullNoiseSource<T, U> {
+    #[inline]
+    fn new(operator: T, conjugate_operator: U) -> Self {
+        Self {
+            operator,
+            conjugate_operator,
+            is_composite: false,
+            phase: Complex { re: 1f64, im: 0f64 },
+        }
+    }
+
+    #[inline]
+    fn as_ref(&self) -> SourceRef<'_, T, U> {
+        SourceRef {
+            operator: &self.operator,
+            conjugate_operator: &self.conjugate_operator,
+            is_composite: self.is_composite,
+            phase: self.phase,
+        }
+    }
+
+    /// Calculate `L^\dagger L |\psi>`, given `L |\psi>`
+    #[inline]
+    fn apply_l_dagger_l<S: ComplexScalar>(
+        &self,
+        state: &Array1<S>,
+        l_state: &Array1<S>,
+    ) -> Array1<S>
+    where
+        U: Tensor<S>,
+    {
+        self.as_ref().apply_l_dagger_l(state, l_state)
+    }
+
+    #[inline]
+    fn get_part<S: ComplexScalar>(&self, state: &Array1<S>, t: f64) -> SSEStochasticPart<S>
+    where
+        T: Tensor<S>,
+        U: Tensor<S>,
+    {
+        self.as_ref().get_part(state, t)
+    }
+
+    #[inline]
+    fn get_incoherent_part<S: ComplexScalar>(
+        &self,
+        state: &Array1<S>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart<S>
+    where
+        T: Tensor<S>,
+    {
+        self.as_ref().get_incoherent_part(state, t)
+    }
+}
+
+/// A borrowed [`FullNoiseSource`], which allows the operators of a source
+/// to be stored separately from its settings (see [`SoaNoise`])
+struct SourceRef<'a, T, U> {
+    operator: &'a T,
+    conjugate_operator: &'a U,
+    is_composite: bool,
+    phase: Complex<f64>,
+}
+
+impl<T, U> SourceRef<'_, T, U> {
+    /// Calculate `L^\dagger L |\psi>`, given `L |\psi>`
+    #[inline]
+    fn apply_l_dagger_l<S: ComplexScalar>(
+        &self,
+        state: &Array1<S>,
+        l_state: &Array1<S>,
+    ) -> Array1<S>
+    where
+        U: Tensor<S>,
+    {
+        if self.is_composite {
+            self.conjugate_operator.dot(state)
+        } else {
+            self.conjugate_operator.dot(l_state)
+        }
+    }
+
     #[inline]
-    fn get_part(&self, state: &Array1<Complex<f64>>, t: f64) -> SSEStochasticPart {
+    fn get_part<S: ComplexScalar>(&self, state: &Array1<S>, _t: f64) -> SSEStochasticPart<S>
+    where
+        T: Tensor<S>,
+        U: Tensor<S>,
+    {
+        let l_state = self.operator.dot(state);
+        // L^\dagger L is unaffected by the measurement phase, so we use the unrotated L |\psi>
+        let l_dagger_l_state = self.apply_l_dagger_l(state, &l_state);
+
         let SSEStochasticIncoherentPart {
             expectation,
             l_state,
-        } = self.get_incoherent_part(state, t);
-
-        let l_dagger_l_state = self.conjugate_operator.dot(&l_state);
+        } = self.get_incoherent_part_from_l_state(state, l_state);
 
         SSEStochasticPart {
             expectation,
@@ -117,16 +253,29 @@ impl<T: Tensor, U: Tensor> FullNoiseSource<T, U> {
     }
 
     #[inline]
-    fn get_incoherent_part(
+    fn get_incoherent_part<S: ComplexScalar>(
         &self,
-        state: &Array1<Complex<f64>>,
+        state: &Array1<S>,
         _t: f64,
-    ) -> SSEStochasticIncoherentPart {
-        let l_state = self.operator.dot(state);
-        let mut expectation = Complex::default();
-        // Todo assert etc to improve perf
-        for i in 0..state.len() {
-            expectation += state[i].conj() * l_state[i];
+    ) -> SSEStochasticIncoherentPart<S>
+    where
+        T: Tensor<S>,
+    {
+        self.get_incoherent_part_from_l_state(state, self.operator.dot(state))
+    }
+
+    #[inline]
+    fn get_incoherent_part_from_l_state<S: ComplexScalar>(
+        &self,
+        state: &Array1<S>,
+        mut l_state: Array1<S>,
+    ) -> SSEStochasticIncoherentPart<S> {
+        let mut expectation = inner_product(state, &l_state);
+
+        if self.phase != (Complex { re: 1f64, im: 0f64 }) {
+            let phase = S::from_c64(self.phase);
+            l_state *= phase;
+            expectation *= phase;
         }
 
         SSEStochasticIncoherentPart {
@@ -136,15 +285,167 @@ impl<T: Tensor, U: Tensor> FullNoiseSource<T, U> {
     }
 }
 
-impl FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>> {
+impl<S: ComplexScalar> FullNoise<Array2<S>, Array2<S>> {
+    #[must_use]
+    pub fn from_operators(operators: &Array3<S>) -> Self {
+        Self(
+            operators
+                .axis_iter(Axis(0))
+                .map(|o| {
+                    // Store L^\dagger in standard layout, rather than as a transposed view of L
+                    let conjugate = o
+                        .map(S::conj)
+                        .reversed_axes()
+                        .as_standard_layout()
+                        .into_owned();
+                    FullNoiseSource::new(o.to_owned(), conjugate)
+                })
+                .collect(),
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`SseError::NonSquareOperator`] if the operators are not square
+    pub fn try_from_operators(operators: &Array3<S>) -> Result<Self, SseError> {
+        let shape = operators.shape();
+        if operators.len_of(Axis(0)) > 0 && shape[1] != shape[2] {
+            return Err(SseError::NonSquareOperator {
+                index: 0,
+                shape: [shape[1], shape[2]],
+            });
+        }
+        Ok(Self::from_operators(operators))
+    }
+}
+
+impl<S: ComplexScalar> FullNoise<SharedArray<S>, SharedAdjointArray<S>> {
+    /// Build the noise as in [`FullNoise::from_operators`], where each `L^\dagger`
+    /// shares the storage of `L` rather than storing a transposed copy.
+    ///
+    /// This halves the memory of the noise, at the cost of conjugating the state
+    /// before and after applying `L^\dagger`.
     #[must_use]
-    pub fn from_operators(operators: &Array3<Complex<f64>>) -> Self {
+    pub fn from_shared_operators(operators: &Array3<S>) -> Self {
         Self(
             operators
                 .axis_iter(Axis(0))
-                .map(|o| FullNoiseSource {
-                    operator: o.to_owned(),
-                    conjugate_operator: o.map(num_complex::Complex::conj).reversed_axes(),
+                .map(|o| {
+                    let operator = SharedArray::new(o.to_owned());
+                    let conjugate_operator = operator.shared_adjoint();
+                    FullNoiseSource::new(operator, conjugate_operator)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<T: OperatorShape, U> FullNoise<T, U> {
+    /// The number of states the noise acts on, or `None` if there are no sources
+    #[must_use]
+    pub fn n_states(&self) -> Option<usize> {
+        self.0.first().map(|s| s.operator.operator_shape()[0])
+    }
+}
+
+impl FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>> {
+    /// Build the noise of a coupling `L` to a bath at a finite temperature,
+    /// with mean occupation `n_bar`.
+    ///
+    /// This gives an emission source `sqrt(n_bar + 1) L` and an absorption source
+    /// `sqrt(n_bar) L^\dagger`, which is omitted if `n_bar` is zero.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `n_bar` is negative, or if `operator` is not square
+    #[must_use]
+    pub fn thermal(operator: &Array2<Complex<f64>>, n_bar: f64) -> Self {
+        assert!(n_bar >= 0f64, "n_bar must not be negative");
+        assert_eq!(operator.nrows(), operator.ncols());
+
+        let mut operators = vec![operator * (n_bar + 1f64).sqrt()];
+        if n_bar > 0f64 {
+            operators.push(operator.adjoint() * n_bar.sqrt());
+        }
+        let views = operators.iter().map(Array2::view).collect::<Vec<_>>();
+        Self::from_operators(&ndarray::stack(Axis(0), &views).unwrap())
+    }
+
+    /// Build the noise from the kraus operators `K_i` of a channel applied every `dt`.
+    ///
+    /// The first operator `K_0 = I - (iH + 1/2 \sum L^\dagger L) dt` is the no-jump operator,
+    /// and the remaining operators `K_i = sqrt(dt) L_i` give the collapse operators
+    /// `L_i = K_i / sqrt(dt)` to leading order in `dt`.
+    /// Note the hamiltonian part of `K_0` is not included in the noise.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are no kraus operators, if `dt` is not positive,
+    /// or if the operators do not satisfy `\sum K^\dagger K = I`
+    #[must_use]
+    pub fn from_kraus(kraus: &Array3<Complex<f64>>, dt: f64) -> Self {
+        assert!(kraus.len_of(Axis(0)) > 0, "K_0 is required");
+        assert!(dt > 0f64);
+        let shape = kraus.shape();
+        assert_eq!(shape[1], shape[2]);
+
+        let mut completeness = Array2::<Complex<f64>>::zeros([shape[1], shape[2]]);
+        for k in kraus.axis_iter(Axis(0)) {
+            completeness = completeness + k.map(Complex::conj).t().dot(&k);
+        }
+        for ((i, j), value) in completeness.indexed_iter() {
+            let expected = if i == j { 1f64 } else { 0f64 };
+            assert!(
+                (value - expected).norm() < 1e-8,
+                "Kraus operators must satisfy sum K^dagger K = I"
+            );
+        }
+
+        let operators = kraus
+            .slice_axis(Axis(0), Slice::from(1..))
+            .map(|k| k / dt.sqrt());
+        Self::from_operators(&operators)
+    }
+}
+
+impl<S: ComplexScalar> FullNoise<Array2<S>, Array2<S>> {
+    /// Precompute the composite operator `L^\dagger L` of each source,
+    /// which is then applied directly to the state in place of `L^\dagger`.
+    ///
+    /// Note `L |\psi>` is still required for `<L>` and the stochastic term,
+    /// so each step applies two operators with or without the composite.
+    #[must_use]
+    pub fn with_cached_l_dagger_l(self) -> Self {
+        Self(
+            self.0
+                .into_iter()
+                .map(|s| {
+                    if s.is_composite {
+                        return s;
+                    }
+                    FullNoiseSource {
+                        conjugate_operator: s.conjugate_operator.dot(&s.operator),
+                        is_composite: true,
+                        ..s
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>> {
+    /// Transform each noise operator into a new basis, `L -> U L U^\dagger`
+    #[must_use]
+    pub fn transform(&self, u: &Array2<Complex<f64>>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|s| FullNoiseSource {
+                    operator: transform_operator(&s.operator, u),
+                    conjugate_operator: transform_operator(&s.conjugate_operator, u),
+                    is_composite: s.is_composite,
+                    phase: s.phase,
                 })
                 .collect(),
         )
@@ -157,13 +458,197 @@ impl FullNoise<BandedArray<Complex<f64>>, TransposedBandedArray<Complex<f64>>> {
         Self(
             operators
                 .iter()
-                .map(|o| FullNoiseSource {
-                    operator: o.clone(),
-                    conjugate_operator: o.transpose().conj(),
+                .map(|o| FullNoiseSource::new(o.clone(), o.transpose().conj()))
+                .collect(),
+        )
+    }
+
+    /// Build the noise from rectangular operators `L_i: C^m -> C^n` between subspaces of
+    /// a space of dimension `n_states`, by embedding each operator with [`BandedArray::embed`].
+    ///
+    /// The SSE adds `L |\psi>` to the state, so each operator must act within the space of the state.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if an operator is larger than `n_states`
+    #[must_use]
+    pub fn from_rectangular_banded(
+        operators: &[BandedArray<Complex<f64>>],
+        n_states: usize,
+    ) -> Self {
+        let embedded = operators
+            .iter()
+            .map(|o| o.embed([n_states, n_states]))
+            .collect::<Vec<_>>();
+        Self::from_banded(&embedded)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if an operator is not square, or the operators differ in shape
+    pub fn try_from_banded(operators: &[BandedArray<Complex<f64>>]) -> Result<Self, SseError> {
+        check_operator_shapes(operators)?;
+        Ok(Self::from_banded(operators))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl FullNoise<crate::mmap::MmapBandedArray, crate::mmap::MmapBandedArray> {
+    /// Build the noise from memory mapped operators, where each `L^\dagger`
+    /// shares the map of `L` so the operators are never loaded into memory.
+    #[must_use]
+    pub fn from_mmap_banded(operators: &[crate::mmap::MmapBandedArray]) -> Self {
+        Self(
+            operators
+                .iter()
+                .map(|o| FullNoiseSource::new(o.clone(), o.adjoint()))
+                .collect(),
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if an operator is not square, or the operators differ in shape
+    pub fn try_from_mmap_banded(
+        operators: &[crate::mmap::MmapBandedArray],
+    ) -> Result<Self, SseError> {
+        check_operator_shapes(operators)?;
+        Ok(Self::from_mmap_banded(operators))
+    }
+}
+
+impl FullNoise<CsrArray<Complex<f64>>, CsrArray<Complex<f64>>> {
+    #[must_use]
+    pub fn from_csr(operators: &[CsrArray<Complex<f64>>]) -> Self {
+        Self(
+            operators
+                .iter()
+                .map(|o| FullNoiseSource::new(o.clone(), o.adjoint()))
+                .collect(),
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if an operator is not square, or the operators differ in shape
+    pub fn try_from_csr(operators: &[CsrArray<Complex<f64>>]) -> Result<Self, SseError> {
+        check_operator_shapes(operators)?;
+        Ok(Self::from_csr(operators))
+    }
+
+    /// Build the noise from operators given as `(rows, columns, values)` triplets,
+    /// as in [`CsrArray::from_triplets`], acting on `n_states` states.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the triplets of an operator differ in length,
+    /// or if an index lies outside the operator
+    #[allow(clippy::type_complexity)]
+    pub fn from_triplet_operators(
+        n_states: usize,
+        operators: &[(&[usize], &[usize], &[Complex<f64>])],
+    ) -> Result<Self, ShapeError> {
+        let operators = operators
+            .iter()
+            .map(|(rows, columns, values)| {
+                CsrArray::from_triplets([n_states, n_states], rows, columns, values)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_csr(&operators))
+    }
+}
+
+impl FullNoise<BoxedTensor, BoxedTensor> {
+    /// Build the noise from pairs `(L, L^\dagger)`, where each source may use a
+    /// different representation (see [`BoxedTensor::with_adjoint`])
+    #[must_use]
+    pub fn from_boxed(operators: Vec<(BoxedTensor, BoxedTensor)>) -> Self {
+        Self(
+            operators
+                .into_iter()
+                .map(|(operator, conjugate_operator)| {
+                    FullNoiseSource::new(operator, conjugate_operator)
                 })
                 .collect(),
         )
     }
+
+    /// # Errors
+    ///
+    /// Returns an error if an operator is not square, or the operators differ in shape
+    pub fn try_from_boxed(operators: Vec<(BoxedTensor, BoxedTensor)>) -> Result<Self, SseError> {
+        check_operator_shapes(operators.iter().map(|(operator, _)| operator))?;
+        Ok(Self::from_boxed(operators))
+    }
+}
+
+impl FullNoise<DiagonalArray<Complex<f64>>, DiagonalArray<Complex<f64>>> {
+    #[must_use]
+    pub fn from_diagonal(operators: &[DiagonalArray<Complex<f64>>]) -> Self {
+        Self(
+            operators
+                .iter()
+                .map(|o| FullNoiseSource::new(o.clone(), o.adjoint()))
+                .collect(),
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`SseError::MismatchedOperator`] if the diagonals differ in length
+    pub fn try_from_diagonal(operators: &[DiagonalArray<Complex<f64>>]) -> Result<Self, SseError> {
+        check_operator_shapes(operators)?;
+        Ok(Self::from_diagonal(operators))
+    }
+}
+
+impl FullNoise<FactorizedSum<Complex<f64>>, FactorizedSum<Complex<f64>>> {
+    /// Build the noise where each source is a single operator `L_i = \sum_n A_n |Ket_n> <Bra_n|`.
+    ///
+    /// Note this is not equivalent to [`FullNoise::from_bra_ket`] using all of the terms,
+    /// which treats each term as an independent source.
+    #[must_use]
+    pub fn from_factorized_sum(operators: &[FactorizedSum<Complex<f64>>]) -> Self {
+        Self(
+            operators
+                .iter()
+                .map(|o| FullNoiseSource::new(o.clone(), o.adjoint()))
+                .collect(),
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if an operator is not square, or the operators differ in shape
+    pub fn try_from_factorized_sum(
+        operators: &[FactorizedSum<Complex<f64>>],
+    ) -> Result<Self, SseError> {
+        check_operator_shapes(operators)?;
+        Ok(Self::from_factorized_sum(operators))
+    }
+}
+
+impl FullNoise<CollapseOperator, CollapseOperator> {
+    /// Build the noise from operators of mixed representation, see [`SseSystemBuilder`].
+    ///
+    /// [`SseSystemBuilder`]: crate::builder::SseSystemBuilder
+    #[must_use]
+    pub fn from_collapse_operators(operators: &[CollapseOperator]) -> Self {
+        Self(
+            operators
+                .iter()
+                .map(|o| FullNoiseSource::new(o.clone(), o.adjoint()))
+                .collect(),
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if an operator is not square, or the operators differ in shape
+    pub fn try_from_collapse_operators(operators: &[CollapseOperator]) -> Result<Self, SseError> {
+        check_operator_shapes(operators)?;
+        Ok(Self::from_collapse_operators(operators))
+    }
 }
 
 impl FullNoise<FactorizedArray<Complex<f64>>, FactorizedArray<Complex<f64>>> {
@@ -177,79 +662,1105 @@ impl FullNoise<FactorizedArray<Complex<f64>>, FactorizedArray<Complex<f64>>> {
             .into_iter()
             .zip(bra.axis_iter(Axis(0)).zip(ket.axis_iter(Axis(0))))
             .map(|(a, (b, k))| FactorizedArray::from_bra_ket(a, b.to_owned(), k.to_owned()))
-            .map(|operator| FullNoiseSource {
-                conjugate_operator: operator.conj().transpose(),
-                operator: operator.clone(),
-            })
+            .map(|operator| FullNoiseSource::new(operator.clone(), operator.adjoint()))
             .collect::<Vec<_>>();
         Self(sources)
     }
+
+    /// Build the noise from bra and ket, checking that each `|ket><bra|` is square.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SseError::MismatchedLength`] if the number of amplitudes, bras and kets differ,
+    /// and [`SseError::NonSquareOperator`] if the bra and ket have a different number of states
+    /// (for example if one has been transposed)
+    pub fn try_from_bra_ket(
+        amplitudes: Array1<Complex<f64>>,
+        bra: &Array2<Complex<f64>>,
+        ket: &Array2<Complex<f64>>,
+    ) -> Result<Self, SseError> {
+        if amplitudes.len() != bra.nrows() || amplitudes.len() != ket.nrows() {
+            return Err(SseError::MismatchedLength {
+                amplitudes: amplitudes.len(),
+                bras: bra.nrows(),
+                kets: ket.nrows(),
+            });
+        }
+        if amplitudes.is_empty() || bra.ncols() == ket.ncols() {
+            return Ok(Self::from_bra_ket(amplitudes, bra, ket));
+        }
+        Err(SseError::NonSquareOperator {
+            index: 0,
+            shape: [ket.ncols(), bra.ncols()],
+        })
+    }
+}
+
+pub trait Tensor<S: ComplexScalar = Complex<f64>>: Dot<Array1<S>, Output = Array1<S>> {
+    /// Calculate `<\psi|A|\psi>`, where the state is not normalized
+    #[inline]
+    fn expectation(&self, state: &Array1<S>) -> S {
+        inner_product(state, &self.dot(state))
+    }
+}
+
+/// Calculate `<bra|ket>`, conjugating the elements of `bra`
+#[inline]
+fn inner_product<S: ComplexScalar>(bra: &Array1<S>, ket: &Array1<S>) -> S {
+    let mut out = S::zero();
+    for (b, k) in bra.iter().zip(ket.iter()) {
+        out += b.conj() * *k;
+    }
+    out
+}
+
+impl<S: ComplexScalar, T: Dot<Array1<S>, Output = Array1<S>>> Tensor<S> for T {}
+
+/// A compensated (kahan) sum, which keeps the error of summing many terms
+/// of differing magnitude independent of the number of terms.
+#[derive(Clone, Copy)]
+struct KahanSum<S> {
+    sum: S,
+    // The low order bits lost from `sum` by the previous addition
+    compensation: S,
+}
+
+impl<S: ComplexScalar> KahanSum<S> {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            sum: S::zero(),
+            compensation: S::zero(),
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, value: S) {
+        let compensated = value - self.compensation;
+        let sum = self.sum + compensated;
+        self.compensation = (sum - self.sum) - compensated;
+        self.sum = sum;
+    }
+
+    #[inline]
+    fn sub(&mut self, value: S) {
+        self.add(-value);
+    }
+
+    #[inline]
+    fn value(&self) -> S {
+        self.sum
+    }
+}
+
+/// An operator with a hermitian adjoint `A^\dagger`
+pub trait Adjoint {
+    type Output: Tensor;
+
+    #[must_use]
+    fn adjoint(&self) -> Self::Output;
+}
+
+/// An operator with a known `[n_rows, n_columns]` shape
+pub trait OperatorShape {
+    fn operator_shape(&self) -> [usize; 2];
+}
+
+impl<T> OperatorShape for Array2<T> {
+    fn operator_shape(&self) -> [usize; 2] {
+        [self.nrows(), self.ncols()]
+    }
+}
+
+/// An operator which may be checked for hermiticity
+pub trait HermitianCheck {
+    /// The largest element of `A - A^\dagger`, or `None` if this cannot be cheaply calculated.
+    /// This is infinite for a non-square operator.
+    fn hermitian_error(&self) -> Option<f64>;
+}
+
+impl HermitianCheck for Array2<Complex<f64>> {
+    fn hermitian_error(&self) -> Option<f64> {
+        if !self.is_square() {
+            return Some(f64::INFINITY);
+        }
+        Some(
+            self.indexed_iter()
+                .map(|((i, j), value)| (value - self[[j, i]].conj()).norm())
+                .fold(0f64, f64::max),
+        )
+    }
+}
+
+/// Check each operator is square and has the same shape as the others,
+/// returning the number of states they act on (if there are any operators).
+fn check_operator_shapes<'a, T: OperatorShape + 'a>(
+    operators: impl IntoIterator<Item = &'a T>,
+) -> Result<Option<usize>, SseError> {
+    let mut expected = None;
+    for (index, operator) in operators.into_iter().enumerate() {
+        let shape = operator.operator_shape();
+        if shape[0] != shape[1] {
+            return Err(SseError::NonSquareOperator { index, shape });
+        }
+        match expected {
+            Some(expected) if expected != shape => {
+                return Err(SseError::MismatchedOperator {
+                    index,
+                    shape,
+                    expected,
+                });
+            }
+            _ => expected = Some(shape),
+        }
+    }
+    Ok(expected.map(|[n_states, _]| n_states))
+}
+
+impl Adjoint for Array2<Complex<f64>> {
+    type Output = Array2<Complex<f64>>;
+
+    fn adjoint(&self) -> Self::Output {
+        self.t().map(Complex::conj)
+    }
+}
+
+/// A hamiltonian `H(t)` which may depend explicitly on time
+pub trait TimeDependentHamiltonian<S: ComplexScalar = Complex<f64>> {
+    /// Calculate `H(t) |\psi>`
+    fn apply(&self, state: &Array1<S>, t: f64) -> Array1<S>;
+}
+
+/// A constant operator is a (trivially) time dependent hamiltonian
+impl<S: ComplexScalar, T: Tensor<S>> TimeDependentHamiltonian<S> for T {
+    #[inline]
+    fn apply(&self, state: &Array1<S>, _t: f64) -> Array1<S> {
+        self.dot(state)
+    }
+}
+
+/// A hamiltonian `H(t)` given by a closure which produces the operator at each time
+pub struct OperatorFnHamiltonian<F: Fn(f64) -> Array2<Complex<f64>>>(pub F);
+
+impl<F: Fn(f64) -> Array2<Complex<f64>>> TimeDependentHamiltonian for OperatorFnHamiltonian<F> {
+    #[inline]
+    fn apply(&self, state: &Array1<Complex<f64>>, t: f64) -> Array1<Complex<f64>> {
+        (self.0)(t).dot(state)
+    }
+}
+
+/// The closure is opaque, so hermiticity is not checked
+impl<F: Fn(f64) -> Array2<Complex<f64>>> HermitianCheck for OperatorFnHamiltonian<F> {
+    fn hermitian_error(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// A hamiltonian `H(t) = H_0 + f(t) V`, with a constant base `H_0`
+/// and a drive `V` modulated by the scalar envelope `f(t)`
+pub struct ModulatedHamiltonian<H: Tensor, V: Tensor, F: Fn(f64) -> Complex<f64>> {
+    pub base: H,
+    pub drive: V,
+    pub envelope: F,
+}
+
+impl<H: Tensor, V: Tensor, F: Fn(f64) -> Complex<f64>> TimeDependentHamiltonian
+    for ModulatedHamiltonian<H, V, F>
+{
+    #[inline]
+    fn apply(&self, state: &Array1<Complex<f64>>, t: f64) -> Array1<Complex<f64>> {
+        let mut out = self.base.dot(state);
+        let amplitude = (self.envelope)(t);
+        if amplitude != Complex::default() {
+            out += &(amplitude * self.drive.dot(state));
+        }
+        out
+    }
+}
+/// Represents a noise operator in factorized form
+/// `S_n = A_n |Ket_n> <Bra_n|`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FullNoise<T, U>(Vec<FullNoiseSource<T, U>>);
+
+impl<T, U> FullNoise<T, U> {
+    /// Set the local oscillator phase `phi` used to measure each source,
+    /// such that the measured operator is `L e^(i phi)`.
+    ///
+    /// This rotates the measurement backaction (the stochastic term) of each source,
+    /// while the ensemble averaged dynamics are unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the number of phases does not match the number of sources
+    #[must_use]
+    pub fn with_measurement_phases(mut self, phases: &[f64]) -> Self {
+        assert_eq!(phases.len(), self.0.len());
+        for (source, phase) in self.0.iter_mut().zip(phases) {
+            source.phase = Complex::from_polar(1f64, *phase);
+        }
+        self
+    }
+
+    /// Scale the rate of every source by `scale`, such that `L -> \sqrt{scale} L`.
+    ///
+    /// As `scale` goes to zero the noise vanishes, and the SSE reduces
+    /// to the schrodinger equation of the hamiltonian.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `scale` is negative
+    #[must_use]
+    pub fn scaled(self, scale: f64) -> FullNoise<ScaledArray<T>, ScaledArray<U>> {
+        assert!(scale >= 0f64);
+        let amplitude = scale.sqrt();
+        FullNoise(
+            self.0
+                .into_iter()
+                .map(|s| FullNoiseSource {
+                    operator: ScaledArray::new(s.operator, amplitude),
+                    // The composite L^\dagger L is quadratic in L
+                    conjugate_operator: ScaledArray::new(
+                        s.conjugate_operator,
+                        if s.is_composite { scale } else { amplitude },
+                    ),
+                    is_composite: s.is_composite,
+                    phase: s.phase,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<T: Tensor, U: Tensor> FullNoise<T, U> {
+    /// Build the dense matrix `\sum_i L_i^\dagger L_i` by applying each operator to the basis states
+    pub(crate) fn l_dagger_l(&self, n_states: usize) -> Array2<Complex<f64>> {
+        let mut out = Array2::zeros([n_states, n_states]);
+        for j in 0..n_states {
+            let mut basis = Array1::zeros([n_states]);
+            basis[j] = Complex { re: 1f64, im: 0f64 };
+            let mut column = out.column_mut(j);
+            for source in &self.0 {
+                column += &source.apply_l_dagger_l(&basis, &source.operator.dot(&basis));
+            }
+        }
+        out
+    }
+
+    /// Calculate the expectation `<L_k e^(i phi_k)>` of each measured operator in `state`
+    #[must_use]
+    pub fn expectations(&self, state: &Array1<Complex<f64>>) -> Vec<Complex<f64>> {
+        let norm_sqr = state.iter().map(Complex::norm_sqr).sum::<f64>();
+        self.0
+            .iter()
+            .map(|source| source.get_incoherent_part(state, 0f64).expectation / norm_sqr)
+            .collect()
+    }
+}
+
+impl<S: ComplexScalar, T: Tensor<S>, U: Tensor<S>> Noise<S> for FullNoise<T, U> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    #[inline]
+    fn get_parts(&self, state: &Array1<S>, t: f64) -> Vec<SSEStochasticPart<S>> {
+        self.0.iter().map(|s| s.get_part(state, t)).collect()
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<S>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart<S>> {
+        self.0
+            .iter()
+            .map(|s| s.get_incoherent_part(state, t))
+            .collect()
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<S>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart<S> {
+        self.0[index].get_incoherent_part(state, t)
+    }
+}
+
+/// A [`FullNoise`] with a structure of arrays layout, where the operators, conjugate operators
+/// and settings of the sources are each stored contiguously.
+///
+/// The result is identical to the [`FullNoise`] it is built from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SoaNoise<T, U> {
+    operators: Vec<T>,
+    conjugate_operators: Vec<U>,
+    is_composite: Vec<bool>,
+    phases: Vec<Complex<f64>>,
+}
+
+impl<T, U> SoaNoise<T, U> {
+    #[inline]
+    fn source(&self, index: usize) -> SourceRef<'_, T, U> {
+        SourceRef {
+            operator: &self.operators[index],
+            conjugate_operator: &self.conjugate_operators[index],
+            is_composite: self.is_composite[index],
+            phase: self.phases[index],
+        }
+    }
+}
+
+impl<T, U> From<FullNoise<T, U>> for SoaNoise<T, U> {
+    fn from(noise: FullNoise<T, U>) -> Self {
+        let mut out = Self {
+            operators: Vec::with_capacity(noise.0.len()),
+            conjugate_operators: Vec::with_capacity(noise.0.len()),
+            is_composite: Vec::with_capacity(noise.0.len()),
+            phases: Vec::with_capacity(noise.0.len()),
+        };
+        for source in noise.0 {
+            out.operators.push(source.operator);
+            out.conjugate_operators.push(source.conjugate_operator);
+            out.is_composite.push(source.is_composite);
+            out.phases.push(source.phase);
+        }
+        out
+    }
+}
+
+impl<T, U> FullNoise<T, U> {
+    /// Convert the noise into the structure of arrays layout of [`SoaNoise`]
+    #[must_use]
+    pub fn into_soa(self) -> SoaNoise<T, U> {
+        self.into()
+    }
+}
+
+impl<S: ComplexScalar, T: Tensor<S>, U: Tensor<S>> Noise<S> for SoaNoise<T, U> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.operators.len()
+    }
+    #[inline]
+    fn get_parts(&self, state: &Array1<S>, t: f64) -> Vec<SSEStochasticPart<S>> {
+        (0..self.len())
+            .map(|i| self.source(i).get_part(state, t))
+            .collect()
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<S>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart<S>> {
+        (0..self.len())
+            .map(|i| self.source(i).get_incoherent_part(state, t))
+            .collect()
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<S>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart<S> {
+        self.source(index).get_incoherent_part(state, t)
+    }
+}
+
+/// A [`FullNoise`] which computes the parts of each source in parallel,
+/// across the rayon thread pool.
+///
+/// Only the linear algebra of each source is parallel. The increments are still drawn
+/// by the solver in a fixed order, and the parts are collected in order of the sources,
+/// so the result is identical to the serial noise. This is only worthwhile for
+/// many sources with expensive (ie dense) operators.
+#[cfg(feature = "rayon")]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParallelNoise<T, U>(pub FullNoise<T, U>);
+
+#[cfg(feature = "rayon")]
+impl<S: ComplexScalar + Send + Sync, T: Tensor<S> + Sync, U: Tensor<S> + Sync> Noise<S>
+    for ParallelNoise<T, U>
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get_parts(&self, state: &Array1<S>, t: f64) -> Vec<SSEStochasticPart<S>> {
+        use rayon::prelude::*;
+        self.0 .0.par_iter().map(|s| s.get_part(state, t)).collect()
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<S>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart<S>> {
+        use rayon::prelude::*;
+        self.0
+             .0
+            .par_iter()
+            .map(|s| s.get_incoherent_part(state, t))
+            .collect()
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<S>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart<S> {
+        self.0.get_incoherent_part(index, state, t)
+    }
+}
+
+/// Noise with collapse operators `L_i(t)` which depend explicitly on time,
+/// given by a closure which produces the operators at each time.
+///
+/// The operators are rebuilt each time the noise is evaluated, so a constant
+/// [`FullNoise`] should be preferred for time-independent operators.
+pub struct TimeDependentNoise<F: Fn(f64) -> Array3<Complex<f64>>> {
+    operators: F,
+    n_sources: usize,
+}
+
+impl<F: Fn(f64) -> Array3<Complex<f64>>> TimeDependentNoise<F> {
+    /// Build the noise from a closure producing the operators `L_i(t)`,
+    /// with the same layout as [`FullNoise::from_operators`].
+    /// The number of sources is taken from the operators at `t = 0`.
+    #[must_use]
+    pub fn from_operators_fn(operators: F) -> Self {
+        let n_sources = operators(0f64).len_of(Axis(0));
+        Self {
+            operators,
+            n_sources,
+        }
+    }
+
+    #[inline]
+    fn noise_at(&self, t: f64) -> FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>> {
+        let operators = (self.operators)(t);
+        assert_eq!(operators.len_of(Axis(0)), self.n_sources);
+        FullNoise::from_operators(&operators)
+    }
+}
+
+impl<F: Fn(f64) -> Array3<Complex<f64>>> Noise for TimeDependentNoise<F> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.n_sources
+    }
+
+    fn get_parts(&self, state: &Array1<Complex<f64>>, t: f64) -> Vec<SSEStochasticPart> {
+        self.noise_at(t).get_parts(state, t)
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<Complex<f64>>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart> {
+        self.noise_at(t).get_incoherent_parts(state, t)
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<Complex<f64>>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart {
+        self.noise_at(t).get_incoherent_part(index, state, t)
+    }
+}
+
+/// Transform a state into a new basis, `|\psi> -> U |\psi>`
+#[must_use]
+pub fn transform_state(
+    state: &Array1<Complex<f64>>,
+    u: &Array2<Complex<f64>>,
+) -> Array1<Complex<f64>> {
+    u.dot(state)
+}
+
+/// Transform an operator into a new basis, `O -> U O U^\dagger`
+#[must_use]
+pub fn transform_operator(
+    operator: &Array2<Complex<f64>>,
+    u: &Array2<Complex<f64>>,
+) -> Array2<Complex<f64>> {
+    u.dot(&operator.dot(&u.adjoint()))
+}
+
+/// Check that `U^\dagger U = I`, within `tolerance`.
+///
+/// Note the transform functions do not check that `U` is unitary.
+#[must_use]
+pub fn is_unitary(u: &Array2<Complex<f64>>, tolerance: f64) -> bool {
+    u.is_square()
+        && u.adjoint().dot(u).indexed_iter().all(|((i, j), value)| {
+            let expected = if i == j { 1f64 } else { 0f64 };
+            (value - expected).norm() < tolerance
+        })
+}
+
+/// How the output of a noise source is monitored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Monitoring {
+    /// The source is monitored by photon counting, giving a jump unraveling
+    Jump,
+    /// The source is monitored by homodyne detection, giving a diffusive unraveling
+    Diffusive,
+    /// The source is not monitored. Any unraveling reproduces the averaged (lindblad)
+    /// dynamics, so the diffusive unraveling is used and the record is discarded.
+    Unmonitored,
+}
+
+/// A noise where each source is unraveled according to how it is monitored.
+///
+/// As a [`Noise`] this includes only the diffusive (and unmonitored) sources,
+/// and the jump sources are applied separately by [`HybridNoise::apply_jumps`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HybridNoise<T: Tensor, U: Tensor> {
+    noise: FullNoise<T, U>,
+    monitoring: Vec<Monitoring>,
+    // The index of each diffusive source in noise
+    diffusive: Vec<usize>,
+}
+
+impl<T: Tensor, U: Tensor> HybridNoise<T, U> {
+    /// # Panics
+    ///
+    /// Will panic if the number of sources does not match the length of `monitoring`
+    #[must_use]
+    pub fn new(noise: FullNoise<T, U>, monitoring: Vec<Monitoring>) -> Self {
+        assert_eq!(noise.0.len(), monitoring.len());
+        let diffusive = monitoring
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| **m != Monitoring::Jump)
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            noise,
+            monitoring,
+            diffusive,
+        }
+    }
+
+    #[must_use]
+    pub fn monitoring(&self) -> &[Monitoring] {
+        &self.monitoring
+    }
+
+    /// Apply the jump sources to a normalized `state` over a time `dt`, returning the normalized state.
+    ///
+    /// A jump occurs with probability `dt \sum_i ||L_i |\psi>||^2`, in which case
+    /// `|\psi> -> L_i |\psi>` for a source chosen in proportion to its rate.
+    /// Otherwise the state evolves under `-1/2 \sum_i (L_i^\dagger L_i - <L_i^\dagger L_i>) dt`.
+    pub fn apply_jumps<R: Rng + ?Sized>(
+        &self,
+        state: &Array1<Complex<f64>>,
+        dt: f64,
+        rng: &mut R,
+    ) -> Array1<Complex<f64>> {
+        let jump_sources = self
+            .noise
+            .0
+            .iter()
+            .zip(&self.monitoring)
+            .filter(|(_, m)| **m == Monitoring::Jump)
+            .map(|(s, _)| s);
+
+        let mut l_states = Vec::new();
+        let mut no_jump = Array1::zeros(state.len());
+        for source in jump_sources {
+            let l_state = source.operator.dot(state);
+            no_jump += &source.apply_l_dagger_l(state, &l_state);
+            let rate = l_state.iter().map(Complex::norm_sqr).sum::<f64>();
+            l_states.push((rate, l_state));
+        }
+        if l_states.is_empty() {
+            return state.to_owned();
+        }
+
+        let total_rate = l_states.iter().map(|(rate, _)| rate).sum::<f64>();
+        let mut sample = rng.gen::<f64>();
+        let mut out = if sample < total_rate * dt {
+            sample /= dt;
+            // Fall back to the last source to guard against rounding in the sum of the rates
+            let index = l_states
+                .iter()
+                .position(|(rate, _)| {
+                    sample -= rate;
+                    sample < 0f64
+                })
+                .unwrap_or(l_states.len() - 1);
+            l_states.swap_remove(index).1
+        } else {
+            let mut out = state - &(no_jump * Complex::from(0.5 * dt));
+            out += &(state * Complex::from(0.5 * dt * total_rate));
+            out
+        };
+        let norm = out.iter().map(Complex::norm_sqr).sum::<f64>().sqrt();
+        out /= Complex::from(norm);
+        out
+    }
+}
+
+impl<T: Tensor, U: Tensor> Noise for HybridNoise<T, U> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.diffusive.len()
+    }
+
+    fn get_parts(&self, state: &Array1<Complex<f64>>, t: f64) -> Vec<SSEStochasticPart> {
+        self.diffusive
+            .iter()
+            .map(|i| self.noise.0[*i].get_part(state, t))
+            .collect()
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<Complex<f64>>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart> {
+        self.diffusive
+            .iter()
+            .map(|i| self.noise.0[*i].get_incoherent_part(state, t))
+            .collect()
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<Complex<f64>>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart {
+        self.noise.0[self.diffusive[index]].get_incoherent_part(state, t)
+    }
+}
+
+/// A noise where the increments of each source are correlated,
+/// with `E[dW_i dW_j^*] = C_{ij} dt` for a hermitian, positive definite correlation matrix `C`.
+///
+/// The correlated increments `dW' = K dW` are formed by mixing independent increments by the
+/// cholesky factor `C = K K^\dagger`. This is equivalent to the independent sources
+/// `M_j = \sum_i K_{ij} L_i`, which are stored in place of `L_i`, so that the dissipative
+/// terms `\sum_j M_j^\dagger M_j` include the cross terms between correlated sources.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CorrelatedNoise {
+    noise: FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>>,
+    correlation: Array2<Complex<f64>>,
+}
+
+impl CorrelatedNoise {
+    /// # Panics
+    ///
+    /// Will panic if `correlation` does not have shape `[n, n]` for `n` operators,
+    /// or if it is not positive definite
+    #[must_use]
+    pub fn new(operators: &Array3<Complex<f64>>, correlation: Array2<Complex<f64>>) -> Self {
+        let n_sources = operators.len_of(Axis(0));
+        assert_eq!(correlation.shape(), [n_sources, n_sources]);
+        let cholesky = correlation
+            .cholesky(UPLO::Lower)
+            .expect("The correlation matrix must be positive definite");
+
+        let mut mixed = Array3::zeros(operators.raw_dim());
+        for ((i, j), k) in cholesky.indexed_iter() {
+            mixed
+                .index_axis_mut(Axis(0), j)
+                .scaled_add(*k, &operators.index_axis(Axis(0), i));
+        }
+        Self {
+            noise: FullNoise::from_operators(&mixed),
+            correlation,
+        }
+    }
+
+    #[must_use]
+    pub fn correlation(&self) -> &Array2<Complex<f64>> {
+        &self.correlation
+    }
+}
+
+impl Noise for CorrelatedNoise {
+    #[inline]
+    fn len(&self) -> usize {
+        self.noise.len()
+    }
+
+    fn get_parts(&self, state: &Array1<Complex<f64>>, t: f64) -> Vec<SSEStochasticPart> {
+        self.noise.get_parts(state, t)
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<Complex<f64>>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart> {
+        self.noise.get_incoherent_parts(state, t)
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<Complex<f64>>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart {
+        self.noise.get_incoherent_part(index, state, t)
+    }
+}
+
+/// The stochastic calculus used to interpret the noise of an SDE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NoiseConvention {
+    Ito,
+    Stratonovich,
+}
+
+/// Converts a noise, defined as usual in the ito form, into the equivalent stratonovich form.
+///
+/// For the diffusion `b_k = (L_k - <L_k>) |\psi>` and complex noise with `dW dW^* = dt`, `dW^2 = 0`,
+/// the stratonovich drift is `a_S = a_I - 1/2 \sum_k (\partial b_k / \partial \psi^*) b_k^*`.
+/// Only `<L_k> = <\psi|L_k|\psi>` depends on `\psi^*`, so the correction
+/// `+1/2 \sum_k (<\psi|L_k^\dagger L_k|\psi> - |<L_k>|^2) |\psi>` is along the state,
+/// and affects only its norm and phase.
+///
+/// A system using this noise should be integrated with a [`NoiseConvention::Stratonovich`]
+/// solver, to reproduce the dynamics of the original noise with an ito solver.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StratonovichNoise<N>(pub N);
+
+impl<S: ComplexScalar, N: Noise<S>> Noise<S> for StratonovichNoise<N> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get_parts(&self, state: &Array1<S>, t: f64) -> Vec<SSEStochasticPart<S>> {
+        let mut parts = self.0.get_parts(state, t);
+        for part in &mut parts {
+            // The drift includes -1/2 L^\dagger L |\psi>, so we remove the correction from this term
+            let variance = inner_product(&part.l_state, &part.l_state)
+                - part.expectation.conj() * part.expectation;
+            part.l_dagger_l_state -= &(state * variance);
+        }
+        parts
+    }
+
+    fn get_incoherent_parts(
+        &self,
+        state: &Array1<S>,
+        t: f64,
+    ) -> Vec<SSEStochasticIncoherentPart<S>> {
+        self.0.get_incoherent_parts(state, t)
+    }
+
+    fn get_incoherent_part(
+        &self,
+        index: usize,
+        state: &Array1<S>,
+        t: f64,
+    ) -> SSEStochasticIncoherentPart<S> {
+        self.0.get_incoherent_part(index, state, t)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SSESystem<H, N> {
+    pub hamiltonian: H,
+    pub noise: N,
+}
+impl<H: OperatorShape, T: OperatorShape, U> SSESystem<H, FullNoise<T, U>> {
+    /// Build the system, checking that the hamiltonian is square and acts
+    /// on the same states as the noise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SseError::NonSquareOperator`] if the hamiltonian is not square,
+    /// and [`SseError::HamiltonianMismatch`] if it does not match the noise
+    pub fn try_new(hamiltonian: H, noise: FullNoise<T, U>) -> Result<Self, SseError> {
+        let shape = hamiltonian.operator_shape();
+        if shape[0] != shape[1] {
+            return Err(SseError::NonSquareOperator { index: 0, shape });
+        }
+        match noise.n_states() {
+            Some(n_states) if n_states != shape[0] => {
+                Err(SseError::HamiltonianMismatch { shape, n_states })
+            }
+            _ => Ok(Self { hamiltonian, noise }),
+        }
+    }
+}
+
+impl SSESystem<Array2<Complex<f64>>, FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>>> {
+    /// Assemble the linear part of the drift, `D = -iH - 1/2 \sum L^\dagger L`.
+    ///
+    /// The remaining drift terms depend on the expectations `<L>`, and are therefore non-linear.
+    #[must_use]
+    pub fn drift_operator(&self) -> Array2<Complex<f64>> {
+        let mut out = self.hamiltonian.map(|h| {
+            h * Complex {
+                re: 0f64,
+                im: -1f64,
+            }
+        });
+        for source in &self.noise.0 {
+            if source.is_composite {
+                out -= &(&source.conjugate_operator * 0.5f64);
+            } else {
+                out -= &(source.conjugate_operator.dot(&source.operator) * 0.5f64);
+            }
+        }
+        out
+    }
+
+    /// Calculate the eigenvalues of the linear drift `D = -iH - 1/2 \sum L^\dagger L`
+    /// (see [`SSESystem::drift_operator`]).
+    ///
+    /// The real part of each eigenvalue is the decay rate of the amplitude of its eigenvector,
+    /// so the most negative real part sets the fastest decay, and `dt` should be small
+    /// compared to the inverse of the largest magnitude.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the eigensolver fails to converge
+    #[must_use]
+    pub fn drift_eigenvalues(&self) -> Array1<Complex<f64>> {
+        self.drift_operator()
+            .eigvals()
+            .expect("The eigenvalues of the drift should converge")
+    }
+}
+
+/// Apply `operator` to each column of `matrix`
+fn apply_to_columns<T: Tensor>(
+    operator: &T,
+    matrix: &Array2<Complex<f64>>,
+) -> Array2<Complex<f64>> {
+    let mut out = Array2::zeros(matrix.raw_dim());
+    for (column, mut out) in matrix.columns().into_iter().zip(out.columns_mut()) {
+        out.assign(&operator.dot(&column.to_owned()));
+    }
+    out
+}
+
+impl<H: Tensor, T: Tensor, U: Tensor> SSESystem<H, FullNoise<T, U>> {
+    /// Calculate the lindblad master equation
+    /// `d\rho / dt = -i[H, \rho] + \sum (L \rho L^\dagger - 1/2 {L^\dagger L, \rho})`
+    /// of the density matrix averaged over all trajectories of the system.
+    ///
+    /// The operators are applied to each column of `rho`, so this requires `O(N)` products.
+    /// Products from the right are found using `A B = (B^\dagger A^\dagger)^\dagger`,
+    /// which requires the hamiltonian to be hermitian.
+    #[must_use]
+    pub fn lindblad_derivative(&self, rho: &Array2<Complex<f64>>) -> Array2<Complex<f64>> {
+        let adjoint = |m: &Array2<Complex<f64>>| m.t().map(Complex::conj);
+        let rho_dagger = adjoint(rho);
+
+        let mut out = apply_to_columns(&self.hamiltonian, rho);
+        out -= &adjoint(&apply_to_columns(&self.hamiltonian, &rho_dagger));
+        out *= Complex {
+            re: 0f64,
+            im: -1f64,
+        };
+
+        for source in &self.noise.0 {
+            let apply_l_dagger_l = |m: &Array2<Complex<f64>>| {
+                let mut out = Array2::zeros(m.raw_dim());
+                for (column, mut out) in m.columns().into_iter().zip(out.columns_mut()) {
+                    let column = column.to_owned();
+                    let l_column = source.operator.dot(&column);
+                    out.assign(&source.apply_l_dagger_l(&column, &l_column));
+                }
+                out
+            };
+            // L \rho L^\dagger = L (L \rho^\dagger)^\dagger
+            let l_rho_dagger = apply_to_columns(&source.operator, &rho_dagger);
+            out += &apply_to_columns(&source.operator, &adjoint(&l_rho_dagger));
+            // \rho L^\dagger L = (L^\dagger L \rho^\dagger)^\dagger
+            let anticommutator = apply_l_dagger_l(rho) + adjoint(&apply_l_dagger_l(&rho_dagger));
+            out.scaled_add(Complex::from(-0.5), &anticommutator);
+        }
+        out
+    }
 }
 
-pub trait Tensor: Dot<Array1<Complex<f64>>, Output = Array1<Complex<f64>>> {}
-
-impl<T: Dot<Array1<Complex<f64>>, Output = Array1<Complex<f64>>>> Tensor for T {}
-/// Represents a noise operator in factorized form
-/// `S_n = A_n |Ket_n> <Bra_n|`
-#[derive(Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct FullNoise<T: Tensor, U: Tensor>(Vec<FullNoiseSource<T, U>>);
+impl<H: HermitianCheck, N> SSESystem<H, N> {
+    /// Check that the hamiltonian is hermitian, within `tolerance`.
+    ///
+    /// Hamiltonians which cannot be checked cheaply (see [`HermitianCheck`]) are accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SseError::NonHermitian`] if an element of `H - H^\dagger` exceeds `tolerance`
+    pub fn validate(&self, tolerance: f64) -> Result<(), SseError> {
+        match self.hamiltonian.hermitian_error() {
+            // NaN elements are also rejected
+            Some(error) if error.is_nan() || error > tolerance => {
+                Err(SseError::NonHermitian { error })
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
-impl<T: Tensor, U: Tensor> Noise for FullNoise<T, U> {
-    #[inline]
-    fn len(&self) -> usize {
-        self.0.len()
+impl<H, N> SSESystem<H, N> {
+    fn coherent<S: ComplexScalar>(&self, state: &Array1<S>, t: f64) -> Array1<S>
+    where
+        H: TimeDependentHamiltonian<S>,
+    {
+        self.hamiltonian.apply(state, t)
     }
-    #[inline]
-    fn get_parts(&self, state: &Array1<Complex<f64>>, t: f64) -> Vec<SSEStochasticPart> {
-        self.0.iter().map(|s| s.get_part(state, t)).collect()
+}
+
+impl<H, N> SSESystem<H, N> {
+    /// Calculate `H |\psi_j>` for each column `|\psi_j>` of `states`, as a single matrix product
+    #[must_use]
+    pub fn coherent_batch<S: ComplexScalar>(&self, states: &Array2<S>) -> Array2<S>
+    where
+        H: Dot<Array2<S>, Output = Array2<S>>,
+    {
+        self.hamiltonian.dot(states)
     }
 
-    fn get_incoherent_parts(
+    /// Get the step of each column of `states` as in [`SDESystem::get_step`],
+    /// using the same increments for every column.
+    ///
+    /// The hamiltonian is applied to all columns at once, but the stochastic parts
+    /// are calculated separately for each column.
+    #[must_use]
+    pub fn get_step_batch<S: ComplexScalar>(
         &self,
-        state: &Array1<Complex<f64>>,
+        step: &SDEStep<S>,
+        states: &Array2<S>,
         t: f64,
-    ) -> Vec<SSEStochasticIncoherentPart> {
-        self.0
-            .iter()
-            .map(|s| s.get_incoherent_part(state, t))
-            .collect()
+    ) -> Array2<S>
+    where
+        H: TimeDependentHamiltonian<S> + Dot<Array2<S>, Output = Array2<S>>,
+        N: Noise<S>,
+    {
+        let hamiltonian = self.coherent_batch(states);
+        let mut out = Array2::zeros(states.raw_dim());
+        for ((state, hamiltonian), mut out) in states
+            .columns()
+            .into_iter()
+            .zip(hamiltonian.columns())
+            .zip(out.columns_mut())
+        {
+            let state = state.to_owned();
+            let parts = SSEParts {
+                state: &state,
+                hamiltonian: hamiltonian.to_owned(),
+                stochastic: self.noise.get_parts(&state, t),
+            };
+            out.assign(&Self::get_step_from_parts(&parts, step));
+        }
+        out
     }
+}
 
-    fn get_incoherent_part(
+impl<H: TimeDependentHamiltonian, N: Noise> SSESystem<H, N> {
+    /// Get the no-jump evolution `-i H_eff |\psi>`, where `H_eff = H - i/2 \sum_i L_i^\dagger L_i`,
+    /// and the (unnormalized) jumped state `L_i |\psi>` of each source
+    pub(crate) fn get_jump_parts(
         &self,
-        index: usize,
         state: &Array1<Complex<f64>>,
         t: f64,
-    ) -> SSEStochasticIncoherentPart {
-        self.0[index].get_incoherent_part(state, t)
+    ) -> (Array1<Complex<f64>>, Vec<Array1<Complex<f64>>>) {
+        let mut no_jump = Complex {
+            re: 0f64,
+            im: -1f64,
+        } * self.coherent(state, t);
+        let l_states = self
+            .noise
+            .get_parts(state, t)
+            .into_iter()
+            .map(|part| {
+                no_jump -= &(Complex::from(0.5) * &part.l_dagger_l_state);
+                part.l_state
+            })
+            .collect();
+        (no_jump, l_states)
     }
 }
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct SSESystem<H: Tensor, N: Noise> {
-    pub hamiltonian: H,
-    pub noise: N,
+impl<H: TimeDependentHamiltonian + OperatorShape, N: Noise> SSESystem<H, N> {
+    /// Suggest a time step `dt = safety / r(D)` for the system, where `r(D)` is an estimate
+    /// of the spectral radius (the largest eigenvalue magnitude) of the linear drift
+    /// `D = -iH - 1/2 \sum L^\dagger L`.
+    ///
+    /// The spectral radius is estimated with a few steps of power iteration, so this is cheap
+    /// even for a large system. Note for a non-normal drift the spectral radius can be smaller
+    /// than the norm of `D`. A `safety` of around 0.1 is a sensible starting point.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `safety` is not positive
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn suggest_dt(&self, safety: f64) -> f64 {
+        assert!(safety > 0f64);
+        let n_states = self.hamiltonian.operator_shape()[0];
+        // An uneven initial vector, which overlaps with every eigenvector of a diagonal drift
+        let mut vector = Array1::from_shape_fn([n_states], |i| {
+            Complex::from(1f64 + i as f64 / n_states as f64)
+        });
+        let mut radius_estimate = 0f64;
+        for _ in 0..20 {
+            vector /= Complex::from(vector.norm_l2());
+            vector = self.get_jump_parts(&vector, 0f64).0;
+            radius_estimate = vector.norm_l2();
+            if radius_estimate == 0f64 {
+                return f64::INFINITY;
+            }
+        }
+        safety / radius_estimate
+    }
 }
-impl<H: Tensor, N: Noise> SSESystem<H, N> {
-    fn coherent(&self, state: &Array1<Complex<f64>>, _t: f64) -> Array1<Complex<f64>> {
-        self.hamiltonian.dot(state)
+
+impl SSESystem<Array2<Complex<f64>>, FullNoise<Array2<Complex<f64>>, Array2<Complex<f64>>>> {
+    /// Transform the system into a new basis given by the unitary `U`,
+    /// such that states of the new system are `U |\psi>`
+    #[must_use]
+    pub fn transform(&self, u: &Array2<Complex<f64>>) -> Self {
+        Self {
+            hamiltonian: transform_operator(&self.hamiltonian, u),
+            noise: self.noise.transform(u),
+        }
     }
 }
 
-impl<H: Tensor, N: Noise> SDESystem for SSESystem<H, N> {
+impl<S: ComplexScalar, H: TimeDependentHamiltonian<S>, N: Noise<S>> SDESystem<S>
+    for SSESystem<H, N>
+{
     #[inline]
     fn n_incoherent(&self) -> usize {
         self.noise.len()
     }
 
-    type Parts<'a> = SSEParts<'a>;
-    type IncoherentParts<'a> = SSEIncoherentParts<'a>;
-    type CoherentParts<'a> = SSEParts<'a>;
-    type IncoherentPart<'a> = SSEIncoherentPart<'a>;
+    type Parts<'a> = SSEParts<'a, S>;
+    type IncoherentParts<'a> = SSEIncoherentParts<'a, S>;
+    type CoherentParts<'a> = SSEParts<'a, S>;
+    type IncoherentPart<'a> = SSEIncoherentPart<'a, S>;
 
     #[inline]
-    fn get_parts<'a>(&self, state: &'a Array1<Complex<f64>>, t: f64) -> Self::Parts<'a> {
+    fn get_parts<'a>(&self, state: &'a Array1<S>, t: f64) -> Self::Parts<'a> {
         SSEParts {
             state,
             hamiltonian: self.coherent(state, t),
@@ -260,7 +1771,7 @@ impl<H: Tensor, N: Noise> SDESystem for SSESystem<H, N> {
     fn get_incoherent_part<'a>(
         &self,
         index: usize,
-        state: &'a Array1<Complex<f64>>,
+        state: &'a Array1<S>,
         t: f64,
     ) -> Self::IncoherentPart<'a> {
         SSEIncoherentPart {
@@ -269,32 +1780,23 @@ impl<H: Tensor, N: Noise> SDESystem for SSESystem<H, N> {
         }
     }
     #[inline]
-    fn get_incoherent_parts<'a>(
-        &self,
-        state: &'a Array1<Complex<f64>>,
-        t: f64,
-    ) -> Self::IncoherentParts<'a> {
+    fn get_incoherent_parts<'a>(&self, state: &'a Array1<S>, t: f64) -> Self::IncoherentParts<'a> {
         SSEIncoherentParts {
             state,
             stochastic: self.noise.get_incoherent_parts(state, t),
         }
     }
     #[inline]
-    fn get_coherent_parts<'a>(
-        &self,
-        state: &'a Array1<Complex<f64>>,
-        t: f64,
-    ) -> Self::CoherentParts<'a> {
+    fn get_coherent_parts<'a>(&self, state: &'a Array1<S>, t: f64) -> Self::CoherentParts<'a> {
         self.get_parts(state, t)
     }
 
     #[inline]
-    fn get_step_from_parts(parts: &Self::Parts<'_>, step: &SDEStep) -> Array1<Complex<f64>> {
-        let mut diagonal = Complex::default();
-        let mut out = Complex {
-            re: step.coherent.im,
-            im: -step.coherent.re,
-        } * &parts.hamiltonian;
+    fn get_step_from_parts(parts: &Self::Parts<'_>, step: &SDEStep<S>) -> Array1<S> {
+        let half = S::real(0.5);
+        let mut diagonal = KahanSum::new();
+        // -i dt H |\psi>
+        let mut out = &parts.hamiltonian * (step.coherent * S::complex(0f64, -1f64));
 
         assert!(parts.stochastic.len() == step.incoherent.len());
         for (part, dw) in parts.stochastic.iter().zip(step.incoherent.iter()) {
@@ -302,83 +1804,113 @@ impl<H: Tensor, N: Noise> SDESystem for SSESystem<H, N> {
             // (L <L^\dagger> - 1 / 2 <L^\dagger><L> - 1 / 2 L^\dagger L) * coherent_step + (L - <L>) * incoherent_step_i |\psi>
 
             // - <L> dw - dt / 2 <L^\dagger><L> |\psi>
-            diagonal -=
-                (dw * part.expectation) + (0.5 * step.coherent * part.expectation.norm_sqr());
+            diagonal.sub(
+                (*dw * part.expectation) + step.coherent.mul_real(half * part.expectation.square()),
+            );
 
             // + dt L <L^\dagger> + dw L |\psi>
-            out += &((dw + (part.expectation.conj() * step.coherent)) * &part.l_state);
+            out += &(&part.l_state * (*dw + (part.expectation.conj() * step.coherent)));
 
             // - (dt / 2) L^\dagger L |\psi>
-            out -= &((0.5 * step.coherent) * &part.l_dagger_l_state);
+            out -= &(&part.l_dagger_l_state * step.coherent.mul_real(half));
         }
 
-        out += &(diagonal * parts.state);
+        // A closed system has no stochastic part, so the state is not rescaled
+        if !parts.stochastic.is_empty() {
+            out += &(parts.state * diagonal.value());
+        }
         out
     }
+    fn get_step_from_parts_into(parts: &Self::Parts<'_>, step: &SDEStep<S>, out: &mut Array1<S>) {
+        let half = S::real(0.5);
+        let mut diagonal = KahanSum::new();
+        out.clone_from(&parts.hamiltonian);
+        // -i dt H |\psi>
+        *out *= step.coherent * S::complex(0f64, -1f64);
+
+        assert_eq!(parts.stochastic.len(), step.incoherent.len());
+        for (part, dw) in parts.stochastic.iter().zip(step.incoherent.iter()) {
+            // The same terms as get_step_from_parts, accumulated without temporaries
+            diagonal.sub(
+                (*dw * part.expectation) + step.coherent.mul_real(half * part.expectation.square()),
+            );
+            out.scaled_add(
+                *dw + (part.expectation.conj() * step.coherent),
+                &part.l_state,
+            );
+            out.scaled_add(-step.coherent.mul_real(half), &part.l_dagger_l_state);
+        }
+
+        if !parts.stochastic.is_empty() {
+            out.scaled_add(diagonal.value(), parts.state);
+        }
+    }
     #[inline]
     fn get_incoherent_steps_from_parts(
         parts: &Self::IncoherentParts<'_>,
-        incoherent_step: &[Complex<f64>],
-    ) -> Array1<Complex<f64>> {
+        incoherent_step: &[S],
+    ) -> Array1<S> {
         let mut out = Array1::zeros([parts.state.len()]);
-        let mut diagonal = Complex::default();
+        let mut diagonal = KahanSum::new();
 
         for (part, step) in parts.stochastic.iter().zip(incoherent_step.iter()) {
             // (L - <L>) * incoherent_step |\psi>
-            diagonal -= step * part.expectation;
+            diagonal.sub(*step * part.expectation);
 
-            out += &(*step * &part.l_state);
+            out += &(&part.l_state * *step);
         }
 
-        out += &(diagonal * parts.state);
+        out += &(parts.state * diagonal.value());
         out
     }
     #[inline]
     fn get_incoherent_step_from_part(
         part: &Self::IncoherentPart<'_>,
-        incoherent_step: Complex<f64>,
-    ) -> Array1<Complex<f64>> {
+        incoherent_step: S,
+    ) -> Array1<S> {
         // (L - <L>) * incoherent_step |\psi>
-        let mut out = incoherent_step * &part.stochastic.l_state;
-        out -= &((incoherent_step * part.stochastic.expectation) * part.state);
+        let mut out = &part.stochastic.l_state * incoherent_step;
+        out -= &(part.state * (incoherent_step * part.stochastic.expectation));
         out
     }
     #[inline]
     fn get_coherent_step_from_parts(
         parts: &Self::CoherentParts<'_>,
-        coherent_step: Complex<f64>,
-    ) -> Array1<Complex<f64>> {
-        let mut diagonal = Complex::default();
+        coherent_step: S,
+    ) -> Array1<S> {
+        let half = S::real(0.5);
+        let mut diagonal = KahanSum::new();
 
-        let mut out = Complex {
-            re: coherent_step.im,
-            im: -coherent_step.re,
-        } * &parts.hamiltonian;
+        // -i coherent_step H |\psi>
+        let mut out = &parts.hamiltonian * (coherent_step * S::complex(0f64, -1f64));
 
         for part in &parts.stochastic {
             // Terms involving the collapse operator contribute to the coherent part
             // (L <L^\dagger> - 1 / 2 <L^\dagger><L> - 1 / 2 L^\dagger L) * coherent_step
 
             // - coherent_step * 1 / 2 <L^\dagger><L> |\psi>
-            diagonal -= 0.5 * coherent_step * part.expectation.norm_sqr();
+            diagonal.sub(coherent_step.mul_real(half * part.expectation.square()));
 
             // + coherent_step L <L^\dagger>  |\psi>
-            out += &((part.expectation.conj() * coherent_step) * &part.l_state);
+            out += &(&part.l_state * (part.expectation.conj() * coherent_step));
             // - (coherent_step / 2) L^\dagger L |\psi>
-            out -= &((0.5 * coherent_step) * &part.l_dagger_l_state);
+            out -= &(&part.l_dagger_l_state * coherent_step.mul_real(half));
         }
 
-        out += &(diagonal * parts.state);
+        // A closed system has no stochastic part, so the state is not rescaled
+        if !parts.stochastic.is_empty() {
+            out += &(parts.state * diagonal.value());
+        }
         out
     }
     #[inline]
-    fn operators_from_parts(&self, parts: &Self::Parts<'_>) -> SDEOperators {
+    fn operators_from_parts(&self, parts: &Self::Parts<'_>) -> SDEOperators<S> {
         SDEOperators {
-            coherent: Self::get_coherent_step_from_parts(parts, Complex { re: 1f64, im: 0f64 }),
+            coherent: Self::get_coherent_step_from_parts(parts, S::one()),
             incoherent: parts
                 .stochastic
                 .iter()
-                .map(|p| &p.l_state - p.expectation * parts.state)
+                .map(|p| &p.l_state - &(parts.state * p.expectation))
                 .collect(),
         }
     }
@@ -386,13 +1918,23 @@ impl<H: Tensor, N: Noise> SDESystem for SSESystem<H, N> {
 
 #[cfg(test)]
 mod test {
-    use ndarray::{s, Array1, Array2, Array3};
+    use ndarray::{linalg::Dot, s, Array1, Array2, Array3, Axis};
     use num_complex::Complex;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
-    use crate::solvers::{EulerSolver, Solver};
+    use crate::analysis::eigh_hermitian;
+    use crate::distribution::StandardComplexNormal;
+    use crate::solvers::{EulerSolver, HybridSolver, NormalizedEulerSolver, Solver};
+    use crate::sparse::{BandedArray, DiagonalArray, FactorizedArray};
+    use crate::system::{SDEStep, SDESystem};
     use crate::tests::{get_initial_state, get_random_system};
+    use crate::SseError;
 
-    use super::{FullNoise, SSESystem};
+    use super::{
+        is_unitary, transform_operator, transform_state, Adjoint, CorrelatedNoise, FullNoise,
+        HybridNoise, KahanSum, ModulatedHamiltonian, Monitoring, Noise, OperatorFnHamiltonian,
+        SSESystem, Tensor, TimeDependentHamiltonian, TimeDependentNoise,
+    };
 
     fn compute_outer_product(
         a: &Array1<Complex<f64>>,
@@ -407,6 +1949,189 @@ mod test {
         result
     }
 
+    #[test]
+    fn test_euler_step_contributions_sum_to_step() {
+        let n_states = 5;
+        let system = get_random_system(3, n_states);
+        let state = get_initial_state(n_states);
+        let mut rng = StdRng::seed_from_u64(8);
+        let dt = 1e-2f64;
+        let increments = (0..system.noise.len())
+            .map(|_| rng.sample::<Complex<f64>, _>(StandardComplexNormal) * dt.sqrt())
+            .collect::<Vec<_>>();
+
+        let contributions = system
+            .noise
+            .euler_step_contributions(&state, 0f64, dt, &increments);
+        assert_eq!(contributions.len(), 3);
+        let total = contributions
+            .iter()
+            .fold(Array1::<Complex<f64>>::zeros([n_states]), |acc, c| acc + c);
+
+        let step = SDEStep {
+            coherent: Complex { re: dt, im: 0f64 },
+            incoherent: increments,
+        };
+        let hamiltonian = system.hamiltonian.dot(&state) * Complex { re: 0f64, im: -dt };
+        let expected = system.get_step(&step, &state, 0f64) - hamiltonian;
+        assert!((&expected - &total).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_kahan_sum_of_small_terms() {
+        // Each term is below the precision of 1.0, so a naive sum never changes
+        let n_terms = 1_000_000;
+        let term = Complex {
+            re: 1e-17,
+            im: -1e-17,
+        };
+        let mut naive = Complex { re: 1f64, im: 0f64 };
+        let mut kahan = KahanSum::new();
+        kahan.add(naive);
+        for _ in 0..n_terms {
+            naive += term;
+            kahan.add(term);
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let expected = Complex { re: 1f64, im: 0f64 } + term * n_terms as f64;
+        assert!((naive - expected).norm() > 1e-12);
+        assert!((kahan.value() - expected).norm() < 1e-15);
+
+        let mut difference = KahanSum::new();
+        difference.add(Complex { re: 2f64, im: 1f64 });
+        difference.sub(Complex { re: 0.5, im: 1f64 });
+        assert!((difference.value() - Complex { re: 1.5, im: 0f64 }).norm() < 1e-15);
+    }
+
+    #[test]
+    fn test_soa_noise_matches_full_noise() {
+        let n_states = 6;
+        let mut rng = StdRng::seed_from_u64(5);
+        let operators = Array3::from_shape_fn([4, n_states, n_states], |_| {
+            rng.sample::<Complex<f64>, _>(StandardComplexNormal)
+        });
+        let build = || {
+            FullNoise::from_operators(&operators)
+                .with_cached_l_dagger_l()
+                .with_measurement_phases(&[0.3, 0f64, -1.2, 2.0])
+        };
+        let hamiltonian = get_random_system(0, n_states).hamiltonian;
+        let aos = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: build(),
+        };
+        let soa = SSESystem {
+            hamiltonian,
+            noise: build().into_soa(),
+        };
+
+        let initial_state = get_initial_state(n_states);
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &aos,
+            5,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(2),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &soa,
+            5,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(2),
+        );
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_thermal_noise() {
+        let n_states = 4;
+        let mut lowering = Array2::<Complex<f64>>::zeros([n_states, n_states]);
+        for i in 1..n_states {
+            #[allow(clippy::cast_precision_loss)]
+            let amplitude = (i as f64).sqrt();
+            lowering[[i - 1, i]] = Complex {
+                re: amplitude,
+                im: 0f64,
+            };
+        }
+
+        let zero = FullNoise::thermal(&lowering, 0f64);
+        let expected = FullNoise::from_operators(&lowering.clone().insert_axis(Axis(0)));
+        assert_eq!(zero.0.len(), 1);
+        assert_eq!(zero.0[0].operator, expected.0[0].operator);
+        assert_eq!(
+            zero.0[0].conjugate_operator,
+            expected.0[0].conjugate_operator
+        );
+
+        let n_bar = 0.5;
+        let thermal = FullNoise::thermal(&lowering, n_bar);
+        assert_eq!(thermal.0.len(), 2);
+        let expected = lowering.adjoint() * n_bar.sqrt();
+        assert!((&thermal.0[1].operator - &expected)
+            .iter()
+            .all(|d| d.norm() < 1e-12));
+        // Emission gives (n_bar + 1) L^dagger L, and absorption gives n_bar L L^dagger
+        let l_dagger_l = thermal.l_dagger_l(n_states);
+        let expected = lowering.adjoint().dot(&lowering) * (n_bar + 1f64)
+            + lowering.dot(&lowering.adjoint()) * n_bar;
+        assert!((&l_dagger_l - &expected).iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_shared_conjugate_operator_is_adjoint() {
+        let n_states = 6;
+        let mut rng = StdRng::seed_from_u64(4);
+        let operators = Array3::from_shape_fn([3, n_states, n_states], |_| {
+            rng.sample::<Complex<f64>, _>(StandardComplexNormal)
+        });
+        let shared = FullNoise::from_shared_operators(&operators);
+
+        let state = Array1::from_shape_fn([n_states], |_| {
+            rng.sample::<Complex<f64>, _>(StandardComplexNormal)
+        });
+        for (source, operator) in shared.0.iter().zip(operators.axis_iter(Axis(0))) {
+            let expected = operator.to_owned().adjoint().dot(&state);
+            let actual = source.conjugate_operator.dot(&state);
+            assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-12));
+            assert!((&source.operator.dot(&state) - &operator.dot(&state))
+                .iter()
+                .all(|d| d.norm() < 1e-12));
+        }
+
+        let system = get_random_system(0, n_states);
+        let dense = SSESystem {
+            hamiltonian: system.hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let shared = SSESystem {
+            hamiltonian: system.hamiltonian,
+            noise: shared,
+        };
+        let initial_state = get_initial_state(n_states);
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &dense,
+            5,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(1),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &shared,
+            5,
+            10,
+            1e-3,
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert!((&expected - &actual).iter().all(|d| d.norm() < 1e-10));
+    }
+
     #[test]
     fn test_diagonal_full_equivalent() {
         // TODO: this should pass actually ...
@@ -447,4 +2172,596 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_measurement_phase_rotates_backaction() {
+        let n_states = 10;
+        let n_operators = 3;
+        let phases = [0.3, -1.2, 2.5];
+        let system = get_random_system(n_operators, n_states);
+        let state = get_initial_state(n_states);
+
+        let incoherent = vec![
+            Complex { re: 0.1, im: 0.2 },
+            Complex { re: -0.3, im: 0.1 },
+            Complex { re: 0.05, im: -0.4 },
+        ];
+        let dt = Complex { re: 0.01, im: 0f64 };
+        let step = SDEStep {
+            coherent: dt,
+            incoherent: incoherent.clone(),
+        };
+        let expected = system.get_step(&step, &state, 0f64);
+
+        // A phase of zero recovers the original step exactly
+        let system = SSESystem {
+            noise: system.noise.with_measurement_phases(&[0f64; 3]),
+            hamiltonian: system.hamiltonian,
+        };
+        assert_eq!(system.get_step(&step, &state, 0f64), expected);
+
+        // Rotating the measurement is equivalent to rotating the noise, which leaves the
+        // distribution of (circularly symmetric) dW, and so the ensemble average unchanged
+        let rotated_step = SDEStep {
+            coherent: dt,
+            incoherent: incoherent
+                .iter()
+                .zip(phases)
+                .map(|(dw, phase)| dw * Complex::from_polar(1f64, phase))
+                .collect(),
+        };
+        let expected_rotated = system.get_step(&rotated_step, &state, 0f64);
+        let expected_coherent = system.get_coherent_step(dt, &state, 0f64);
+
+        let rotated = SSESystem {
+            noise: system.noise.with_measurement_phases(&phases),
+            hamiltonian: system.hamiltonian,
+        };
+        // The conditional step is changed by the phase
+        let actual = rotated.get_step(&step, &state, 0f64);
+        assert!((&actual - &expected).iter().any(|d| d.norm() > 1e-6));
+        for (a, e) in actual.iter().zip(expected_rotated.iter()) {
+            assert!((a - e).norm() < 1e-12);
+        }
+
+        // The deterministic part of the evolution is independent of the phase
+        let actual = rotated.get_coherent_step(dt, &state, 0f64);
+        for (a, e) in actual.iter().zip(expected_coherent.iter()) {
+            assert!((a - e).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_amplitude_damping_kraus_matches_lowering() {
+        let (gamma, dt) = (0.7, 1e-3);
+        let one = Complex { re: 1f64, im: 0f64 };
+        let mut kraus = Array3::zeros([2, 2, 2]);
+        kraus[[0, 0, 0]] = one;
+        kraus[[0, 1, 1]] = one * (1f64 - gamma * dt).sqrt();
+        kraus[[1, 0, 1]] = one * (gamma * dt).sqrt();
+
+        let mut lowering = Array3::zeros([1, 2, 2]);
+        lowering[[0, 0, 1]] = one * gamma.sqrt();
+
+        let hamiltonian = Array2::from_diag(&Array1::from(vec![Complex::default(), one]));
+        let from_kraus = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_kraus(&kraus, dt),
+        };
+        let expected = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&lowering),
+        };
+
+        let amplitude = Complex {
+            re: std::f64::consts::FRAC_1_SQRT_2,
+            im: 0f64,
+        };
+        let state = Array1::from(vec![amplitude, amplitude]);
+        let step = SDEStep {
+            coherent: Complex { re: dt, im: 0f64 },
+            incoherent: vec![Complex {
+                re: 0.02,
+                im: -0.01,
+            }],
+        };
+        let actual = from_kraus.get_step(&step, &state, 0f64);
+        let expected = expected.get_step(&step, &state, 0f64);
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "sum K^dagger K = I")]
+    fn test_incomplete_kraus_panics() {
+        let mut kraus = Array3::zeros([2, 2, 2]);
+        kraus[[0, 0, 0]] = Complex { re: 1f64, im: 0f64 };
+        kraus[[1, 0, 1]] = Complex { re: 0.5, im: 0f64 };
+        let _ = FullNoise::from_kraus(&kraus, 1e-3);
+    }
+
+    #[test]
+    fn test_transform_into_eigenbasis() {
+        let n_states = 5;
+        let system = get_random_system(0, n_states);
+        let hamiltonian = &system.hamiltonian + &system.hamiltonian.adjoint();
+
+        let (_, eigenvectors) = eigh_hermitian(&hamiltonian);
+        let u = eigenvectors.adjoint();
+        assert!(is_unitary(&u, 1e-10));
+        assert!(!is_unitary(&(&u * Complex { re: 2f64, im: 0f64 }), 1e-10));
+
+        let diagonal = transform_operator(&hamiltonian, &u);
+        for ((i, j), value) in diagonal.indexed_iter() {
+            if i != j {
+                assert!(value.norm() < 1e-10);
+            }
+        }
+        let original = transform_operator(&diagonal, &u.adjoint());
+        for (a, e) in original.iter().zip(hamiltonian.iter()) {
+            assert!((a - e).norm() < 1e-10);
+        }
+
+        let state = get_initial_state(n_states);
+        let recovered = transform_state(&transform_state(&state, &u), &u.adjoint());
+        for (a, e) in recovered.iter().zip(state.iter()) {
+            assert!((a - e).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_transformed_system_step() {
+        let n_states = 4;
+        let hamiltonian = get_random_system(0, n_states).hamiltonian;
+        let mut lowering = Array3::zeros([1, n_states, n_states]);
+        lowering[[0, 0, 1]] = Complex { re: 1f64, im: 0f64 };
+        lowering[[0, 2, 3]] = Complex { re: 0.5, im: 0.5 };
+        let system = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&lowering),
+        };
+        let (_, u) = eigh_hermitian(&(&hamiltonian + &hamiltonian.adjoint()));
+        let transformed = system.transform(&u);
+
+        let amplitude = Complex { re: 0.5, im: 0f64 };
+        let state = Array1::from_elem(n_states, amplitude);
+        let step = SDEStep {
+            coherent: Complex { re: 0.01, im: 0f64 },
+            incoherent: vec![Complex { re: 0.1, im: -0.05 }],
+        };
+        let expected = transform_state(&system.get_step(&step, &state, 0f64), &u);
+        let actual = transformed.get_step(&step, &transform_state(&state, &u), 0f64);
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).norm() < 1e-10);
+        }
+    }
+
+    /// Integrate the lindblad master equation with a fourth order runge-kutta method
+    fn solve_lindblad(
+        rho: &Array2<Complex<f64>>,
+        hamiltonian: &Array2<Complex<f64>>,
+        operators: &Array3<Complex<f64>>,
+        n_step: usize,
+        dt: f64,
+    ) -> Array2<Complex<f64>> {
+        let i = Complex { re: 0f64, im: 1f64 };
+        let derivative = |rho: &Array2<Complex<f64>>| {
+            let mut out = (hamiltonian.dot(rho) - rho.dot(hamiltonian)) * -i;
+            for l in operators.outer_iter() {
+                let l = l.to_owned();
+                let l_dagger = l.adjoint();
+                let l_dagger_l = l_dagger.dot(&l);
+                out = out + l.dot(rho).dot(&l_dagger)
+                    - (l_dagger_l.dot(rho) + rho.dot(&l_dagger_l)) * Complex::from(0.5);
+            }
+            out
+        };
+        let mut out = rho.to_owned();
+        for _ in 0..n_step {
+            let k1 = derivative(&out);
+            let k2 = derivative(&(&out + &(&k1 * Complex::from(0.5 * dt))));
+            let k3 = derivative(&(&out + &(&k2 * Complex::from(0.5 * dt))));
+            let k4 = derivative(&(&out + &(&k3 * Complex::from(dt))));
+            out = out
+                + (k1 + k2 * Complex::from(2f64) + k3 * Complex::from(2f64) + k4)
+                    * Complex::from(dt / 6f64);
+        }
+        out
+    }
+
+    #[test]
+    fn test_hybrid_monitoring_reproduces_lindblad() {
+        // A driven qubit with decay L_0 = sqrt(0.5) sigma_- and dephasing L_1 = sqrt(0.3) sigma_z
+        let one = Complex { re: 1f64, im: 0f64 };
+        let hamiltonian = Array2::from_shape_vec(
+            [2, 2],
+            vec![Complex::default(), one, one, Complex::default()],
+        )
+        .unwrap();
+        let mut operators = Array3::zeros([2, 2, 2]);
+        operators[[0, 0, 1]] = one * 0.5f64.sqrt();
+        operators[[1, 0, 0]] = one * 0.3f64.sqrt();
+        operators[[1, 1, 1]] = -one * 0.3f64.sqrt();
+
+        let initial_state = get_initial_state(2);
+        let (n_step, dt, n_trajectories) = (100, 1e-2, 800);
+        let expected = solve_lindblad(
+            &compute_outer_product(&initial_state, &initial_state),
+            &hamiltonian,
+            &operators,
+            n_step,
+            dt,
+        );
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for monitoring in [
+            vec![Monitoring::Jump, Monitoring::Jump],
+            vec![Monitoring::Diffusive, Monitoring::Diffusive],
+            vec![Monitoring::Jump, Monitoring::Unmonitored],
+        ] {
+            let system = SSESystem {
+                hamiltonian: hamiltonian.clone(),
+                noise: HybridNoise::new(FullNoise::from_operators(&operators), monitoring),
+            };
+            let mut rho = Array2::<Complex<f64>>::zeros([2, 2]);
+            for _ in 0..n_trajectories {
+                let result =
+                    HybridSolver::solve_with_rng(&initial_state, &system, 2, n_step, dt, &mut rng);
+                let state = result.row(1).to_owned();
+                rho = rho + compute_outer_product(&state, &state.map(Complex::conj));
+            }
+            rho /= Complex::from(f64::from(n_trajectories));
+
+            for (a, e) in rho.iter().zip(expected.iter()) {
+                assert!((a - e).norm() < 0.08);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tensor_expectation() {
+        let n_states = 6;
+        let rng = rand::thread_rng();
+        let state = Array1::from_iter(
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states),
+        );
+        let dense = Array2::from_shape_vec(
+            [n_states, n_states],
+            rng.clone()
+                .sample_iter::<Complex<f64>, _>(StandardComplexNormal)
+                .take(n_states * n_states)
+                .collect(),
+        )
+        .unwrap();
+        let expected =
+            |operator: &Array2<Complex<f64>>| state.mapv(|s| s.conj()).dot(&operator.dot(&state));
+
+        assert!((dense.expectation(&state) - expected(&dense)).norm() < 1e-10);
+
+        let banded = BandedArray::from_dense(&dense);
+        assert!((banded.expectation(&state) - expected(&dense)).norm() < 1e-10);
+
+        let (bra, ket) = (state.map(|s| s * 0.3), state.map(Complex::conj));
+        let amplitude = Complex { re: 0.5, im: 2f64 };
+        let factorized = FactorizedArray::from_bra_ket(amplitude, bra.clone(), ket.clone());
+        let factorized_dense =
+            Array2::from_shape_fn([n_states, n_states], |(i, j)| amplitude * ket[i] * bra[j]);
+        assert!((factorized.expectation(&state) - expected(&factorized_dense)).norm() < 1e-10);
+    }
+
+    #[test]
+    fn test_driven_rabi_oscillation() {
+        // H(t) = w / 2 sigma_z + W / 2 (cos(wt) sigma_x + sin(wt) sigma_y)
+        // On resonance the population of the upper state is sin^2(W t / 2)
+        let (omega, rabi) = (5f64, 1f64);
+        let hamiltonian = OperatorFnHamiltonian(move |t: f64| {
+            let coupling = 0.5 * rabi * Complex::from_polar(1f64, -omega * t);
+            Array2::from_shape_vec(
+                [2, 2],
+                vec![
+                    Complex::from(0.5 * omega),
+                    coupling,
+                    coupling.conj(),
+                    Complex::from(-0.5 * omega),
+                ],
+            )
+            .unwrap()
+        });
+        let system = SSESystem {
+            hamiltonian,
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let initial_state = Array1::from_vec(vec![Complex::from(1f64), Complex::default()]);
+
+        let (n_out, step, dt) = (40, 500, 2e-4);
+        let result = NormalizedEulerSolver::solve(&initial_state, &system, n_out, step, dt);
+        for (i, state) in result.outer_iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let t = (i * step) as f64 * dt;
+            let expected = (0.5 * rabi * t).sin().powi(2);
+            assert!((state[1].norm_sqr() - expected).abs() < 1e-2);
+        }
+
+        // The same drive with a constant hamiltonian is not resonant
+        let undriven = SSESystem {
+            hamiltonian: system.hamiltonian.0(0f64),
+            noise: FullNoise::from_operators(&Array3::zeros([0, 2, 2])),
+        };
+        let result = NormalizedEulerSolver::solve(&initial_state, &undriven, n_out, step, dt);
+        assert!(result.column(1).iter().all(|s| s.norm_sqr() < 0.1));
+    }
+
+    #[test]
+    fn test_modulated_hamiltonian() {
+        let base = Array2::from_diag(&Array1::from_vec(vec![
+            Complex::from(1f64),
+            Complex::from(-1f64),
+        ]));
+        let drive = Array2::from_shape_vec(
+            [2, 2],
+            vec![
+                Complex::default(),
+                Complex::from(1f64),
+                Complex::from(1f64),
+                Complex::default(),
+            ],
+        )
+        .unwrap();
+        let envelope = |t: f64| Complex::from(t.cos());
+        let modulated = ModulatedHamiltonian {
+            base: base.clone(),
+            drive: drive.clone(),
+            envelope,
+        };
+        let dense = OperatorFnHamiltonian(|t: f64| &base + &(envelope(t) * &drive));
+
+        let state = Array1::from_vec(vec![Complex::from(0.6), Complex { re: 0f64, im: 0.8 }]);
+        for t in [0f64, 0.3, 1.7] {
+            let difference = modulated.apply(&state, t) - dense.apply(&state, t);
+            assert!(difference.iter().all(|d| d.norm() < 1e-12));
+        }
+        assert_eq!(base.apply(&state, 1f64), base.dot(&state));
+    }
+
+    #[test]
+    fn test_constant_time_dependent_noise_matches_full_noise() {
+        let n_states = 4;
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex::from(0.5);
+            operators[[1, i, i]] = Complex { re: 0f64, im: 0.2 };
+        }
+        let hamiltonian =
+            Array2::from_diag(&Array1::linspace(0f64, 1f64, n_states).mapv(Complex::from));
+        let constant = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let time_dependent = SSESystem {
+            hamiltonian,
+            noise: TimeDependentNoise::from_operators_fn(|_t| operators.clone()),
+        };
+        let initial_state = get_initial_state(n_states);
+
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &constant,
+            20,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(11),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &time_dependent,
+            20,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(11),
+        );
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_try_from_operators_rejects_non_square() {
+        let operators = Array3::<Complex<f64>>::zeros([2, 3, 4]);
+        assert!(matches!(
+            FullNoise::try_from_operators(&operators),
+            Err(SseError::NonSquareOperator {
+                index: 0,
+                shape: [3, 4]
+            })
+        ));
+    }
+
+    #[test]
+    fn test_try_from_diagonal_rejects_mismatched() {
+        let operators = [
+            DiagonalArray::from_diagonal(Array1::<Complex<f64>>::zeros(3)),
+            DiagonalArray::from_diagonal(Array1::<Complex<f64>>::zeros(4)),
+        ];
+        assert!(matches!(
+            FullNoise::try_from_diagonal(&operators),
+            Err(SseError::MismatchedOperator {
+                index: 1,
+                shape: [4, 4],
+                expected: [3, 3]
+            })
+        ));
+    }
+
+    #[test]
+    fn test_try_from_bra_ket_rejects_transposed() {
+        let amplitudes = Array1::from_elem(2, Complex { re: 1f64, im: 0f64 });
+        let bra = Array2::<Complex<f64>>::zeros([2, 3]);
+        let ket = Array2::<Complex<f64>>::zeros([3, 2]);
+        assert!(matches!(
+            FullNoise::try_from_bra_ket(amplitudes.clone(), &bra, &ket),
+            Err(SseError::MismatchedLength {
+                amplitudes: 2,
+                bras: 2,
+                kets: 3
+            })
+        ));
+
+        let ket = Array2::<Complex<f64>>::zeros([2, 4]);
+        assert!(matches!(
+            FullNoise::try_from_bra_ket(amplitudes, &bra, &ket),
+            Err(SseError::NonSquareOperator {
+                index: 0,
+                shape: [4, 3]
+            })
+        ));
+    }
+
+    #[test]
+    fn test_try_new_rejects_hamiltonian_mismatch() {
+        let noise =
+            FullNoise::try_from_operators(&Array3::<Complex<f64>>::zeros([1, 3, 3])).unwrap();
+        assert_eq!(noise.n_states(), Some(3));
+
+        let result = SSESystem::try_new(Array2::<Complex<f64>>::zeros([4, 4]), noise);
+        assert!(matches!(
+            result,
+            Err(SseError::HamiltonianMismatch {
+                shape: [4, 4],
+                n_states: 3
+            })
+        ));
+
+        let noise =
+            FullNoise::try_from_operators(&Array3::<Complex<f64>>::zeros([1, 3, 3])).unwrap();
+        assert!(SSESystem::try_new(Array2::<Complex<f64>>::zeros([3, 3]), noise).is_ok());
+    }
+
+    #[test]
+    fn test_uncorrelated_noise_matches_full_noise() {
+        let n_states = 4;
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex::from(0.5);
+            operators[[1, i, i]] = Complex { re: 0f64, im: 0.2 };
+        }
+        let hamiltonian =
+            Array2::from_diag(&Array1::linspace(0f64, 1f64, n_states).mapv(Complex::from));
+        let initial_state = get_initial_state(n_states);
+
+        let full = SSESystem {
+            hamiltonian: hamiltonian.clone(),
+            noise: FullNoise::from_operators(&operators),
+        };
+        let correlated = SSESystem {
+            hamiltonian,
+            noise: CorrelatedNoise::new(&operators, Array2::eye(2)),
+        };
+        let expected = EulerSolver::solve_with_rng(
+            &initial_state,
+            &full,
+            5,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(5),
+        );
+        let actual = EulerSolver::solve_with_rng(
+            &initial_state,
+            &correlated,
+            5,
+            10,
+            0.01,
+            &mut StdRng::seed_from_u64(5),
+        );
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_correlated_noise_dissipator() {
+        let n_states = 3;
+        let mut operators = Array3::zeros([2, n_states, n_states]);
+        for i in 1..n_states {
+            operators[[0, i - 1, i]] = Complex::from(0.5);
+            operators[[1, i, i - 1]] = Complex { re: 0.1, im: 0.3 };
+        }
+        let correlation = Array2::from_shape_vec(
+            [2, 2],
+            vec![
+                Complex::from(1f64),
+                Complex { re: 0.3, im: 0.4 },
+                Complex { re: 0.3, im: -0.4 },
+                Complex::from(2f64),
+            ],
+        )
+        .unwrap();
+        let noise = CorrelatedNoise::new(&operators, correlation.clone());
+
+        // \sum_j M_j^\dagger M_j = \sum_{ik} C_{ki} L_i^\dagger L_k
+        let mut expected = Array2::<Complex<f64>>::zeros([n_states, n_states]);
+        for ((k, i), c) in correlation.indexed_iter() {
+            let l_i = operators.index_axis(Axis(0), i).to_owned();
+            let l_k = operators.index_axis(Axis(0), k).to_owned();
+            expected = expected + l_i.adjoint().dot(&l_k) * *c;
+        }
+        let difference = noise.noise.l_dagger_l(n_states) - expected;
+        assert!(difference.iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_validate_hermitian_hamiltonian() {
+        let n_states = 5;
+        let random = get_random_system(0, n_states).hamiltonian;
+        let noise =
+            || FullNoise::from_operators(&Array3::<Complex<f64>>::zeros([0, n_states, n_states]));
+
+        let system = SSESystem {
+            hamiltonian: random.clone(),
+            noise: noise(),
+        };
+        assert!(matches!(
+            system.validate(1e-10),
+            Err(SseError::NonHermitian { .. })
+        ));
+        let system = SSESystem {
+            hamiltonian: BandedArray::from_dense(&random),
+            noise: noise(),
+        };
+        assert!(matches!(
+            system.validate(1e-10),
+            Err(SseError::NonHermitian { .. })
+        ));
+
+        let hermitian = &random + &random.adjoint();
+        let system = SSESystem {
+            hamiltonian: hermitian.clone(),
+            noise: noise(),
+        };
+        assert_eq!(system.validate(1e-10), Ok(()));
+        let system = SSESystem {
+            hamiltonian: BandedArray::from_dense(&hermitian),
+            noise: noise(),
+        };
+        assert_eq!(system.validate(1e-10), Ok(()));
+
+        // A banded hamiltonian with a single off diagonal band is not hermitian
+        let mut hopping = Array2::<Complex<f64>>::zeros([n_states, n_states]);
+        for i in 1..n_states {
+            hopping[[i - 1, i]] = Complex::from(1f64);
+        }
+        let system = SSESystem {
+            hamiltonian: BandedArray::from_dense(&hopping),
+            noise: noise(),
+        };
+        assert_eq!(
+            system.validate(1e-10),
+            Err(SseError::NonHermitian { error: 1f64 })
+        );
+
+        // Opaque hamiltonians are accepted
+        let system = SSESystem {
+            hamiltonian: OperatorFnHamiltonian(move |_| random.clone()),
+            noise: noise(),
+        };
+        assert_eq!(system.validate(1e-10), Ok(()));
+    }
 }
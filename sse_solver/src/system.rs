@@ -1,40 +1,85 @@
-use ndarray::Array1;
+use ndarray::{Array1, ScalarOperand};
+use ndarray_linalg::{Lapack, Scalar};
 use num_complex::Complex;
 
-pub struct SDEStep {
-    pub coherent: Complex<f64>,
-    pub incoherent: Vec<Complex<f64>>,
+/// The scalar used to represent the state of a system, either `Complex<f32>` or `Complex<f64>`.
+///
+/// Single precision halves the memory of large systems. Random increments are always
+/// sampled in double precision and then converted, so a seeded rng gives the same noise
+/// in either precision.
+pub trait ComplexScalar: Scalar<Complex = Self, Real: Into<f64>> + Lapack + ScalarOperand {
+    fn from_c64(value: Complex<f64>) -> Self;
+
+    fn to_c64(self) -> Complex<f64>;
+}
+
+impl ComplexScalar for Complex<f64> {
+    #[inline]
+    fn from_c64(value: Complex<f64>) -> Self {
+        value
+    }
+
+    #[inline]
+    fn to_c64(self) -> Complex<f64> {
+        self
+    }
+}
+
+impl ComplexScalar for Complex<f32> {
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_c64(value: Complex<f64>) -> Self {
+        Complex {
+            re: value.re as f32,
+            im: value.im as f32,
+        }
+    }
+
+    #[inline]
+    fn to_c64(self) -> Complex<f64> {
+        Complex {
+            re: self.re.into(),
+            im: self.im.into(),
+        }
+    }
 }
 
-pub struct SDEOperators {
-    pub coherent: Array1<Complex<f64>>,
-    pub incoherent: Vec<Array1<Complex<f64>>>,
+pub struct SDEStep<S = Complex<f64>> {
+    pub coherent: S,
+    pub incoherent: Vec<S>,
+}
+
+pub struct SDEOperators<S = Complex<f64>> {
+    pub coherent: Array1<S>,
+    pub incoherent: Vec<Array1<S>>,
 }
 
 /// Represents a SDE System, seperated into a 'coherent' term and a series of 'incoherent' terms
 #[allow(clippy::module_name_repetitions)]
-pub trait SDESystem {
+pub trait SDESystem<S: ComplexScalar = Complex<f64>> {
     /// Type used to store a cache of 'Parts' required to calculate a SDE step.
     type Parts<'a>: Into<Self::IncoherentParts<'a>> + Into<Self::CoherentParts<'a>>;
 
     /// Get the parts used to calculate an SDE step.
     /// This is useful if multiple separate steps are required, ie for supporting value calculations
-    fn get_parts<'a>(&self, state: &'a Array1<Complex<f64>>, t: f64) -> Self::Parts<'a>;
+    fn get_parts<'a>(&self, state: &'a Array1<S>, t: f64) -> Self::Parts<'a>;
 
     /// Get the resulting state after the given 'step' has been performed
     #[inline]
-    fn get_step(
-        &self,
-        step: &SDEStep,
-        state: &Array1<Complex<f64>>,
-        t: f64,
-    ) -> Array1<Complex<f64>> {
+    fn get_step(&self, step: &SDEStep<S>, state: &Array1<S>, t: f64) -> Array1<S> {
         let parts = self.get_parts(state, t);
         Self::get_step_from_parts(&parts, step)
     }
 
     /// Get the resulting state after the given 'step' has been performed
-    fn get_step_from_parts(parts: &Self::Parts<'_>, step: &SDEStep) -> Array1<Complex<f64>>;
+    fn get_step_from_parts(parts: &Self::Parts<'_>, step: &SDEStep<S>) -> Array1<S>;
+
+    /// Write the result of [`SDESystem::get_step_from_parts`] into `out`,
+    /// reusing its allocation where the system supports it
+    #[inline]
+    fn get_step_from_parts_into(parts: &Self::Parts<'_>, step: &SDEStep<S>, out: &mut Array1<S>) {
+        *out = Self::get_step_from_parts(parts, step);
+    }
 
     /// Type used to store a cache of 'Parts' required to calculate a SDE step involving only the incoherent term.
     type IncoherentParts<'a>;
@@ -44,20 +89,11 @@ pub trait SDESystem {
 
     /// Get the parts used to calculate an SDE step.
     /// This is useful if multiple separate steps are required, ie for supporting value calculations
-    fn get_incoherent_parts<'a>(
-        &self,
-        state: &'a Array1<Complex<f64>>,
-        t: f64,
-    ) -> Self::IncoherentParts<'a>;
+    fn get_incoherent_parts<'a>(&self, state: &'a Array1<S>, t: f64) -> Self::IncoherentParts<'a>;
 
     /// Get the resulting state after the given 'step' has been performed
     #[inline]
-    fn get_incoherent_steps(
-        &self,
-        incoherent_step: &[Complex<f64>],
-        state: &Array1<Complex<f64>>,
-        t: f64,
-    ) -> Array1<Complex<f64>> {
+    fn get_incoherent_steps(&self, incoherent_step: &[S], state: &Array1<S>, t: f64) -> Array1<S> {
         let parts = self.get_incoherent_parts(state, t);
         Self::get_incoherent_steps_from_parts(&parts, incoherent_step)
     }
@@ -66,15 +102,15 @@ pub trait SDESystem {
     /// Involving only incoherent terms
     fn get_incoherent_steps_from_parts(
         parts: &Self::IncoherentParts<'_>,
-        incoherent_step: &[Complex<f64>],
-    ) -> Array1<Complex<f64>>;
+        incoherent_step: &[S],
+    ) -> Array1<S>;
 
     /// Get the parts used to calculate an SDE step.
     /// This is useful if multiple separate steps are required, ie for supporting value calculations
     fn get_incoherent_part<'a>(
         &self,
         idx: usize,
-        state: &'a Array1<Complex<f64>>,
+        state: &'a Array1<S>,
         t: f64,
     ) -> Self::IncoherentPart<'a>;
 
@@ -83,10 +119,10 @@ pub trait SDESystem {
     fn get_incoherent_step(
         &self,
         idx: usize,
-        incoherent_step: Complex<f64>,
-        state: &Array1<Complex<f64>>,
+        incoherent_step: S,
+        state: &Array1<S>,
         t: f64,
-    ) -> Array1<Complex<f64>> {
+    ) -> Array1<S> {
         let parts = self.get_incoherent_part(idx, state, t);
         Self::get_incoherent_step_from_part(&parts, incoherent_step)
     }
@@ -95,41 +131,30 @@ pub trait SDESystem {
     /// Involving only incoherent terms
     fn get_incoherent_step_from_part(
         part: &Self::IncoherentPart<'_>,
-        incoherent_step: Complex<f64>,
-    ) -> Array1<Complex<f64>>;
+        incoherent_step: S,
+    ) -> Array1<S>;
 
     /// Type used to store a cache of 'Parts' required to calculate a SDE step involving only the incoherent term.
     type CoherentParts<'a>;
 
     /// Get the parts used to calculate an SDE step.
     /// This is useful if multiple separate steps are required, ie for supporting value calculations
-    fn get_coherent_parts<'a>(
-        &self,
-        state: &'a Array1<Complex<f64>>,
-        t: f64,
-    ) -> Self::CoherentParts<'a>;
+    fn get_coherent_parts<'a>(&self, state: &'a Array1<S>, t: f64) -> Self::CoherentParts<'a>;
 
     /// Get the resulting state after the given 'step' has been performed
     #[inline]
-    fn get_coherent_step(
-        &self,
-        coherent_step: Complex<f64>,
-        state: &Array1<Complex<f64>>,
-        t: f64,
-    ) -> Array1<Complex<f64>> {
+    fn get_coherent_step(&self, coherent_step: S, state: &Array1<S>, t: f64) -> Array1<S> {
         let parts = self.get_coherent_parts(state, t);
         Self::get_coherent_step_from_parts(&parts, coherent_step)
     }
 
     /// Get the resulting state after the given 'step' has been performed
     /// Involving only coherent terms
-    fn get_coherent_step_from_parts(
-        parts: &Self::CoherentParts<'_>,
-        coherent_step: Complex<f64>,
-    ) -> Array1<Complex<f64>>;
+    fn get_coherent_step_from_parts(parts: &Self::CoherentParts<'_>, coherent_step: S)
+        -> Array1<S>;
 
     /// The total number of incoherent terms
     fn n_incoherent(&self) -> usize;
 
-    fn operators_from_parts(&self, parts: &Self::Parts<'_>) -> SDEOperators;
+    fn operators_from_parts(&self, parts: &Self::Parts<'_>) -> SDEOperators<S>;
 }